@@ -0,0 +1,389 @@
+#[cfg(feature = "reflect")]
+use bevy_reflect::{Reflect, std_traits::ReflectDefault};
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub struct SkillNodeData {
+    pub id: u32,
+    pub name: String,
+    pub description: String,
+    pub image_name: String,
+    pub position: Vec2,
+    pub node_type: NodeType,
+    pub stats: Vec<StatModifier>,
+    /// Skill points needed to allocate the node
+    #[serde(default = "default_cost")]
+    pub cost: u32,
+    /// 0 to `MAX_TIER`, higher tiers are drawn larger
+    #[serde(default)]
+    pub tier: u32,
+    /// Sprite depth, nodes with a higher value are drawn on top.
+    /// Kept between -`MAX_Z_ORDER` and `MAX_Z_ORDER` by the editor.
+    #[serde(default)]
+    pub z_order: f32,
+    /// When the node was added, in seconds since the Unix epoch. 0 for
+    /// nodes from before timestamps were kept.
+    #[serde(default)]
+    #[cfg_attr(feature = "reflect", reflect(default))]
+    pub created_at: u64,
+    /// When the node was last edited, in seconds since the Unix epoch
+    #[serde(default)]
+    #[cfg_attr(feature = "reflect", reflect(default))]
+    pub modified_at: u64,
+    /// Points connections can attach to instead of the node's center
+    #[serde(default)]
+    #[cfg_attr(feature = "reflect", reflect(default))]
+    pub ports: Vec<PortAnchor>,
+}
+
+impl SkillNodeData {
+    pub const MAX_TIER: u32 = 10;
+    /// Extra sprite size per tier, in world units
+    pub const TIER_SIZE_STEP: f32 = 5.0;
+    pub const MAX_Z_ORDER: f32 = 400.0;
+
+    /// Offset from the node's position of the given port, or zero for the
+    /// center when there's no port or the node no longer has it
+    pub fn port_offset(&self, port: Option<u32>) -> Vec2 {
+        port.and_then(|id| self.ports.iter().find(|port| port.id == id))
+            .map_or(Vec2::ZERO, |port| port.local_offset)
+    }
+
+    /// Id for a new port, one past the highest in use
+    pub fn next_port_id(&self) -> u32 {
+        self.ports.iter().map(|port| port.id + 1).max().unwrap_or(0)
+    }
+}
+
+/// Named point on a node that connections can start or end at
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub struct PortAnchor {
+    /// Unique among the node's ports, referenced by
+    /// `ConnectionData::from_port` and `ConnectionData::to_port`
+    pub id: u32,
+    pub name: String,
+    /// Offset from the node's position, in world units
+    pub local_offset: Vec2,
+}
+
+fn default_cost() -> u32 {
+    1
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub struct ConnectionData {
+    pub from_id: u32,
+    pub to_id: u32,
+    #[serde(default)]
+    pub curve_type: CurveType,
+    /// Disabled connections stay in the tree but are left out of path
+    /// finding, letting designers try a layout without deleting anything
+    #[serde(default = "default_enabled")]
+    #[cfg_attr(feature = "reflect", reflect(default = "default_enabled"))]
+    pub enabled: bool,
+    /// Port of the `from` node the connection starts at, or its center
+    #[serde(default)]
+    #[cfg_attr(feature = "reflect", reflect(default))]
+    pub from_port: Option<u32>,
+    /// Port of the `to` node the connection ends at, or its center
+    #[serde(default)]
+    #[cfg_attr(feature = "reflect", reflect(default))]
+    pub to_port: Option<u32>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl ConnectionData {
+    /// Swaps the endpoints, keeping the connection's shape
+    pub fn reverse(&mut self) {
+        std::mem::swap(&mut self.from_id, &mut self.to_id);
+        std::mem::swap(&mut self.from_port, &mut self.to_port);
+        self.curve_type.reverse();
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Default))]
+pub enum CurveType {
+    #[default]
+    Straight,
+    Arc {
+        radius: f32,
+        clockwise: bool,
+        /// Whether the editor draws a pulse travelling along the arc, to
+        /// highlight it in demos. Has no effect on the tree itself.
+        #[serde(default)]
+        #[cfg_attr(feature = "reflect", reflect(default))]
+        animated: bool,
+    },
+    /// Smooth Catmull-Rom curve through `waypoints`, in order from the
+    /// `from` node to the `to` node
+    CatmullRom { waypoints: Vec<Vec2> },
+}
+
+impl CurveType {
+    /// The same shape traced from the other end: an arc's sweep is mirrored
+    /// and a curve's waypoints run backwards
+    pub fn reverse(&mut self) {
+        match self {
+            CurveType::Straight => {}
+            CurveType::Arc { clockwise, .. } => *clockwise = !*clockwise,
+            CurveType::CatmullRom { waypoints } => waypoints.reverse(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SkillTreeSaveData {
+    /// Layout version, upgraded on load by `migration::migrate`. Files from
+    /// before versioning read as 0.
+    #[serde(default)]
+    pub version: u32,
+    pub nodes: Vec<SkillNodeData>,
+    pub connections: Vec<ConnectionData>,
+    #[serde(default)]
+    pub start_node_id: Option<u32>,
+    /// Shown in the window title and available to games embedding the tree
+    #[serde(default)]
+    pub tree_name: String,
+    #[serde(default)]
+    pub tree_description: String,
+    /// Camera pan offset and zoom the tree was saved with, so the editor
+    /// can reopen it on the same view
+    #[serde(default)]
+    pub saved_camera_state: Option<(Vec2, f32)>,
+}
+
+/// A node's type, either one of the built-in PoE-style types or a
+/// game-specific name, which the editor styles through `node_type_styles.ron`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(from = "NodeTypeRepr", into = "NodeTypeRepr")]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Default))]
+pub enum NodeType {
+    Builtin(BuiltinNodeType),
+    Custom(String),
+}
+
+impl NodeType {
+    pub fn name(&self) -> &str {
+        match self {
+            NodeType::Builtin(builtin) => builtin.name(),
+            NodeType::Custom(name) => name,
+        }
+    }
+
+    pub fn is_builtin(&self, builtin: BuiltinNodeType) -> bool {
+        *self == NodeType::Builtin(builtin)
+    }
+
+    /// Sprite color when the node isn't highlighted and has no custom style,
+    /// in sRGB
+    pub fn tint(&self) -> [f32; 3] {
+        match self {
+            NodeType::Builtin(builtin) => builtin.tint(),
+            NodeType::Custom(_) => [0.55, 0.55, 0.55],
+        }
+    }
+
+    /// Sprite width and height in world units when there's no custom style
+    pub fn size(&self) -> f32 {
+        match self {
+            NodeType::Builtin(builtin) => builtin.size(),
+            NodeType::Custom(_) => 60.0,
+        }
+    }
+}
+
+impl Default for NodeType {
+    fn default() -> Self {
+        NodeType::Builtin(BuiltinNodeType::Normal)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Default))]
+pub enum BuiltinNodeType {
+    #[default]
+    Normal,
+    Notable,
+    Keystone,
+    Start,
+    Mastery,
+    Ascendancy,
+    Socket,
+}
+
+impl BuiltinNodeType {
+    pub const ALL: [BuiltinNodeType; 7] = [
+        BuiltinNodeType::Normal,
+        BuiltinNodeType::Notable,
+        BuiltinNodeType::Keystone,
+        BuiltinNodeType::Start,
+        BuiltinNodeType::Mastery,
+        BuiltinNodeType::Ascendancy,
+        BuiltinNodeType::Socket,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BuiltinNodeType::Normal => "Normal",
+            BuiltinNodeType::Notable => "Notable",
+            BuiltinNodeType::Keystone => "Keystone",
+            BuiltinNodeType::Start => "Start",
+            BuiltinNodeType::Mastery => "Mastery",
+            BuiltinNodeType::Ascendancy => "Ascendancy",
+            BuiltinNodeType::Socket => "Socket",
+        }
+    }
+
+    /// sRGB color
+    pub fn tint(self) -> [f32; 3] {
+        match self {
+            BuiltinNodeType::Mastery => [0.3, 0.8, 0.75],
+            BuiltinNodeType::Ascendancy => [0.65, 0.4, 0.9],
+            BuiltinNodeType::Socket => [0.55, 0.55, 0.55],
+            _ => [1.0, 1.0, 1.0],
+        }
+    }
+
+    pub fn size(self) -> f32 {
+        match self {
+            BuiltinNodeType::Mastery => 70.0,
+            BuiltinNodeType::Socket => 45.0,
+            _ => 60.0,
+        }
+    }
+}
+
+/// On-disk shape of `NodeType`. Built-in types stay bare variants (`Start`)
+/// so files written before custom types existed still load.
+#[derive(Serialize, Deserialize)]
+enum NodeTypeRepr {
+    Normal,
+    Notable,
+    Keystone,
+    Start,
+    Mastery,
+    Ascendancy,
+    Socket,
+    Custom(String),
+}
+
+impl From<NodeTypeRepr> for NodeType {
+    fn from(repr: NodeTypeRepr) -> Self {
+        let builtin = match repr {
+            NodeTypeRepr::Normal => BuiltinNodeType::Normal,
+            NodeTypeRepr::Notable => BuiltinNodeType::Notable,
+            NodeTypeRepr::Keystone => BuiltinNodeType::Keystone,
+            NodeTypeRepr::Start => BuiltinNodeType::Start,
+            NodeTypeRepr::Mastery => BuiltinNodeType::Mastery,
+            NodeTypeRepr::Ascendancy => BuiltinNodeType::Ascendancy,
+            NodeTypeRepr::Socket => BuiltinNodeType::Socket,
+            NodeTypeRepr::Custom(name) => return NodeType::Custom(name),
+        };
+        NodeType::Builtin(builtin)
+    }
+}
+
+impl From<NodeType> for NodeTypeRepr {
+    fn from(node_type: NodeType) -> Self {
+        match node_type {
+            NodeType::Builtin(BuiltinNodeType::Normal) => NodeTypeRepr::Normal,
+            NodeType::Builtin(BuiltinNodeType::Notable) => NodeTypeRepr::Notable,
+            NodeType::Builtin(BuiltinNodeType::Keystone) => NodeTypeRepr::Keystone,
+            NodeType::Builtin(BuiltinNodeType::Start) => NodeTypeRepr::Start,
+            NodeType::Builtin(BuiltinNodeType::Mastery) => NodeTypeRepr::Mastery,
+            NodeType::Builtin(BuiltinNodeType::Ascendancy) => NodeTypeRepr::Ascendancy,
+            NodeType::Builtin(BuiltinNodeType::Socket) => NodeTypeRepr::Socket,
+            NodeType::Custom(name) => NodeTypeRepr::Custom(name),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub struct StatModifier {
+    pub stat_name: String,
+    pub value: f32,
+    pub modifier_type: ModifierType,
+    /// Bounds the stat's total is clamped to
+    #[serde(default)]
+    pub min_value: Option<f32>,
+    #[serde(default)]
+    pub max_value: Option<f32>,
+    /// Only counted when the condition holds for the allocated nodes
+    #[serde(default)]
+    pub condition: Option<StatCondition>,
+}
+
+impl StatModifier {
+    pub fn is_clamped(&self) -> bool {
+        self.min_value.is_some() || self.max_value.is_some()
+    }
+
+    /// Whether `value` itself falls outside this modifier's own bounds
+    pub fn violates_bounds(&self) -> bool {
+        self.min_value.is_some_and(|min| self.value < min)
+            || self.max_value.is_some_and(|max| self.value > max)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+pub enum StatCondition {
+    NodeAllocated(u32),
+    NodeNotAllocated(u32),
+}
+
+impl StatCondition {
+    pub fn node_id(self) -> u32 {
+        match self {
+            StatCondition::NodeAllocated(id) | StatCondition::NodeNotAllocated(id) => id,
+        }
+    }
+
+    pub fn is_met(self, allocated: &HashSet<u32>) -> bool {
+        match self {
+            StatCondition::NodeAllocated(id) => allocated.contains(&id),
+            StatCondition::NodeNotAllocated(id) => !allocated.contains(&id),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Default))]
+pub enum ModifierType {
+    #[default]
+    Flat,
+    Percentage,
+    /// Multiplies the stat, applied after Base and Flat
+    Multiplicative,
+    /// Sets the stat's starting value before any other modifier
+    Base,
+}
+
+impl ModifierType {
+    /// In the order they're applied when totalling a stat
+    pub const ALL: [ModifierType; 4] = [
+        ModifierType::Base,
+        ModifierType::Flat,
+        ModifierType::Multiplicative,
+        ModifierType::Percentage,
+    ];
+}
+
+/// Lowest id among the Start nodes, saved as the tree's start node
+pub fn first_start_node_id(nodes: &[SkillNodeData]) -> Option<u32> {
+    nodes
+        .iter()
+        .filter(|node| node.node_type.is_builtin(BuiltinNodeType::Start))
+        .map(|node| node.id)
+        .min()
+}
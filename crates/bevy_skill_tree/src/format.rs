@@ -0,0 +1,385 @@
+use crate::data::*;
+use crate::migration;
+use serde::de::DeserializeOwned;
+use std::error::Error;
+use std::path::Path;
+
+/// Text encodings a skill tree can be written in
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SaveFormat {
+    Ron,
+    Json,
+}
+
+impl SaveFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "ron" => Some(SaveFormat::Ron),
+            "json" => Some(SaveFormat::Json),
+            _ => None,
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(Self::from_name)
+    }
+}
+
+/// Leading bytes of a `.stb` file: "STB" followed by the format version, so
+/// loaders can reject files written by an incompatible editor
+const BINARY_MAGIC: [u8; 3] = *b"STB";
+pub const BINARY_FORMAT_VERSION: u8 = 1;
+pub const BINARY_EXTENSION: &str = "stb";
+
+/// Reads a skill tree saved by the editor, as RON, JSON or the binary
+/// format depending on the file extension
+pub fn load_skill_tree(path: impl AsRef<Path>) -> Result<SkillTreeSaveData, Box<dyn Error>> {
+    let path = path.as_ref();
+    let is_binary = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(BINARY_EXTENSION));
+    if is_binary {
+        parse_skill_tree_binary(&std::fs::read(path)?)
+    } else {
+        let format = SaveFormat::from_path(path).unwrap_or(SaveFormat::Ron);
+        parse_skill_tree(&std::fs::read_to_string(path)?, format)
+    }
+}
+
+/// Parses a RON or JSON skill tree and brings it up to the current layout
+pub fn parse_skill_tree(
+    contents: &str,
+    format: SaveFormat,
+) -> Result<SkillTreeSaveData, Box<dyn Error>> {
+    let mut save_data = match format {
+        SaveFormat::Ron => ron::from_str(contents)?,
+        SaveFormat::Json => serde_json::from_str(contents)?,
+    };
+    upgrade(&mut save_data);
+    Ok(save_data)
+}
+
+pub fn serialize_skill_tree(
+    save_data: &SkillTreeSaveData,
+    format: SaveFormat,
+) -> Result<String, Box<dyn Error>> {
+    Ok(match format {
+        SaveFormat::Ron => ron::ser::to_string_pretty(save_data, Default::default())?,
+        SaveFormat::Json => serde_json::to_string_pretty(save_data)?,
+    })
+}
+
+/// Encodes the game-ready binary format, a bincode-encoded
+/// `SkillTreeSaveData` after the magic header
+pub fn serialize_skill_tree_binary(
+    save_data: &SkillTreeSaveData,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut bytes = Vec::from(BINARY_MAGIC);
+    bytes.push(BINARY_FORMAT_VERSION);
+    bincode::serialize_into(&mut bytes, save_data)?;
+    Ok(bytes)
+}
+
+pub fn parse_skill_tree_binary(bytes: &[u8]) -> Result<SkillTreeSaveData, Box<dyn Error>> {
+    let Some((header, body)) = bytes.split_first_chunk::<4>() else {
+        return Err("File is too short to be a binary skill tree".into());
+    };
+    if header[..3] != BINARY_MAGIC {
+        return Err("Not a binary skill tree file".into());
+    }
+    let mut save_data = match header[3] {
+        BINARY_FORMAT_VERSION => bincode::deserialize(body)?,
+        version => {
+            return Err(format!(
+                "Unsupported binary format version {} (expected {})",
+                version, BINARY_FORMAT_VERSION
+            )
+            .into());
+        }
+    };
+    upgrade(&mut save_data);
+    Ok(save_data)
+}
+
+/// Save data recovered from a file that failed to load, with a message for
+/// every entry that had to be dropped
+pub struct LenientLoadResult {
+    pub data: SkillTreeSaveData,
+    pub warnings: Vec<String>,
+}
+
+/// Salvages what it can from a malformed RON or JSON skill tree. Each node
+/// and connection is cut out of the raw text and parsed on its own, skipping
+/// entries that don't parse. The tree name and description are not
+/// recovered.
+pub fn recover_skill_tree(
+    contents: &str,
+    format: SaveFormat,
+) -> Result<LenientLoadResult, Box<dyn Error>> {
+    let mut warnings = Vec::new();
+    let nodes: Vec<SkillNodeData> = recover_entries(contents, "nodes", format, &mut warnings);
+    let node_ids: Vec<u32> = nodes.iter().map(|node| node.id).collect();
+    let mut connections: Vec<ConnectionData> =
+        recover_entries(contents, "connections", format, &mut warnings);
+    if nodes.is_empty() && connections.is_empty() {
+        return Err("No nodes or connections could be recovered".into());
+    }
+
+    // Skipped nodes would otherwise leave connections pointing at nothing
+    connections.retain(|conn| {
+        let is_dangling = !node_ids.contains(&conn.from_id) || !node_ids.contains(&conn.to_id);
+        if is_dangling {
+            let message = format!(
+                "Dropped connection {} -> {} to a missing node",
+                conn.from_id, conn.to_id
+            );
+            log::warn!("{}", message);
+            warnings.push(message);
+        }
+        !is_dangling
+    });
+
+    let mut data = SkillTreeSaveData {
+        version: 0,
+        nodes,
+        connections,
+        start_node_id: None,
+        tree_name: String::new(),
+        tree_description: String::new(),
+        saved_camera_state: None,
+    };
+    upgrade(&mut data);
+    Ok(LenientLoadResult { data, warnings })
+}
+
+fn recover_entries<T: DeserializeOwned>(
+    text: &str,
+    field: &str,
+    format: SaveFormat,
+    warnings: &mut Vec<String>,
+) -> Vec<T> {
+    let Some(entries) = array_entries(text, field) else {
+        let message = format!("No {} list found", field);
+        log::warn!("{}", message);
+        warnings.push(message);
+        return Vec::new();
+    };
+
+    let mut recovered = Vec::new();
+    for (index, entry) in entries.into_iter().enumerate() {
+        let parsed: Result<T, String> = match format {
+            SaveFormat::Ron => ron::from_str(entry).map_err(|e| e.to_string()),
+            SaveFormat::Json => serde_json::from_str(entry).map_err(|e| e.to_string()),
+        };
+        match parsed {
+            Ok(value) => recovered.push(value),
+            Err(e) => {
+                let message = format!("Skipped {} entry {}: {}", field, index, e);
+                log::warn!("{}", message);
+                warnings.push(message);
+            }
+        }
+    }
+    recovered
+}
+
+/// Raw text of each entry in the top-level `field: [...]` list of a RON or
+/// JSON document, split on commas outside of brackets and strings. An
+/// unterminated list yields the entries up to the end of the text.
+fn array_entries<'a>(text: &'a str, field: &str) -> Option<Vec<&'a str>> {
+    let bytes = text.as_bytes();
+    let mut depth = 0;
+    let mut i = 0;
+    let list_start = loop {
+        match *bytes.get(i)? {
+            b'"' => i = string_end(bytes, i),
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => depth -= 1,
+            b':' if depth == 1 => {
+                let key = text[..i].trim_end().trim_end_matches('"');
+                let is_field = key.strip_suffix(field).is_some_and(|before| {
+                    !before.ends_with(|c: char| c.is_alphanumeric() || c == '_')
+                });
+                let value = text[i + 1..].trim_start();
+                if is_field && value.starts_with('[') {
+                    break text.len() - value.len() + 1;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    };
+
+    let mut entries = Vec::new();
+    let mut push_entry = |entry: &'a str| {
+        let entry = entry.trim();
+        if !entry.is_empty() {
+            entries.push(entry);
+        }
+    };
+    let mut depth = 0;
+    let mut entry_start = list_start;
+    let mut i = list_start;
+    while let Some(&byte) = bytes.get(i) {
+        match byte {
+            b'"' => i = string_end(bytes, i),
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' if depth == 0 => {
+                push_entry(&text[entry_start..i]);
+                return Some(entries);
+            }
+            b')' | b']' | b'}' => depth -= 1,
+            b',' if depth == 0 => {
+                push_entry(&text[entry_start..i]);
+                entry_start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    push_entry(&text[entry_start..]);
+    Some(entries)
+}
+
+/// Index of the closing quote of the string opening at `start`, or the last
+/// index when it's unterminated
+fn string_end(bytes: &[u8], start: usize) -> usize {
+    let mut i = start + 1;
+    while let Some(&byte) = bytes.get(i) {
+        match byte {
+            b'\\' => i += 1,
+            b'"' => return i,
+            _ => {}
+        }
+        i += 1;
+    }
+    bytes.len().saturating_sub(1)
+}
+
+/// Migrates freshly parsed data to the current layout and drops what the
+/// editor no longer allows
+fn upgrade(save_data: &mut SkillTreeSaveData) {
+    migration::migrate(save_data);
+    if save_data.version > migration::CURRENT_VERSION {
+        log::warn!(
+            "Skill tree was saved by a newer editor (version {}, this crate supports {})",
+            save_data.version,
+            migration::CURRENT_VERSION
+        );
+    }
+    remove_self_loops(save_data);
+}
+
+/// Older files may contain connections from a node to itself, which the
+/// editor no longer allows
+fn remove_self_loops(save_data: &mut SkillTreeSaveData) {
+    save_data.connections.retain(|conn| {
+        let is_self_loop = conn.from_id == conn.to_id;
+        if is_self_loop {
+            log::warn!("Removed self-loop connection on node {}", conn.from_id);
+        }
+        !is_self_loop
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Vec2;
+
+    fn node(id: u32, stats: Vec<StatModifier>) -> SkillNodeData {
+        SkillNodeData {
+            id,
+            name: format!("Node {}", id),
+            description: String::new(),
+            image_name: String::new(),
+            position: Vec2::new(id as f32 * 100.0, 0.0),
+            node_type: NodeType::Builtin(BuiltinNodeType::Normal),
+            stats,
+            cost: 1,
+            tier: 0,
+            z_order: 0.0,
+            created_at: 0,
+            modified_at: 0,
+            ports: Vec::new(),
+        }
+    }
+
+    fn connection(from_id: u32, to_id: u32) -> ConnectionData {
+        ConnectionData {
+            from_id,
+            to_id,
+            curve_type: CurveType::default(),
+            enabled: true,
+            from_port: None,
+            to_port: None,
+        }
+    }
+
+    fn sample_tree() -> SkillTreeSaveData {
+        let strength = StatModifier {
+            stat_name: "Strength".to_string(),
+            value: 12.5,
+            modifier_type: ModifierType::Flat,
+            min_value: None,
+            max_value: None,
+            condition: None,
+        };
+        SkillTreeSaveData {
+            version: migration::CURRENT_VERSION,
+            nodes: vec![
+                node(1, vec![strength]),
+                node(2, Vec::new()),
+                node(3, Vec::new()),
+            ],
+            connections: vec![connection(1, 2), connection(2, 3)],
+            start_node_id: Some(1),
+            tree_name: "Sample".to_string(),
+            tree_description: String::new(),
+            saved_camera_state: Some((Vec2::new(10.0, -5.0), 1.5)),
+        }
+    }
+
+    fn assert_sample(save_data: &SkillTreeSaveData) {
+        assert_eq!(save_data.nodes.len(), 3);
+        assert_eq!(save_data.connections.len(), 2);
+        assert_eq!(save_data.start_node_id, Some(1));
+        let stat = &save_data.nodes[0].stats[0];
+        assert_eq!(stat.stat_name, "Strength");
+        assert_eq!(stat.value, 12.5);
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let text = serialize_skill_tree(&sample_tree(), SaveFormat::Json).unwrap();
+        assert_sample(&parse_skill_tree(&text, SaveFormat::Json).unwrap());
+    }
+
+    #[test]
+    fn ron_round_trip() {
+        let text = serialize_skill_tree(&sample_tree(), SaveFormat::Ron).unwrap();
+        assert_sample(&parse_skill_tree(&text, SaveFormat::Ron).unwrap());
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let bytes = serialize_skill_tree_binary(&sample_tree()).unwrap();
+        assert_eq!(bytes[..3], BINARY_MAGIC);
+        assert_eq!(bytes[3], BINARY_FORMAT_VERSION);
+        let save_data = parse_skill_tree_binary(&bytes).unwrap();
+        assert_sample(&save_data);
+        assert_eq!(
+            save_data.saved_camera_state,
+            Some((Vec2::new(10.0, -5.0), 1.5))
+        );
+    }
+
+    #[test]
+    fn binary_rejects_other_versions() {
+        let mut bytes = serialize_skill_tree_binary(&sample_tree()).unwrap();
+        bytes[3] = BINARY_FORMAT_VERSION + 1;
+        assert!(parse_skill_tree_binary(&bytes).is_err());
+    }
+}
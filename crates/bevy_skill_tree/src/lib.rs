@@ -0,0 +1,10 @@
+//! Skill tree data written by the skill tree editor, and the loaders for its
+//! RON, JSON and binary files. Enable the `reflect` feature to derive Bevy's
+//! `Reflect` on the data types.
+
+mod data;
+mod format;
+pub mod migration;
+
+pub use data::*;
+pub use format::*;
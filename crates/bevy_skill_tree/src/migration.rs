@@ -0,0 +1,83 @@
+use crate::data::*;
+
+/// Version written into `SkillTreeSaveData::version` by this editor. Bump it
+/// and append a step to `MIGRATIONS` whenever loaded data needs fixing up.
+pub const CURRENT_VERSION: u32 = 1;
+
+/// `MIGRATIONS[n]` upgrades data from version `n` to `n + 1`
+const MIGRATIONS: [fn(&mut SkillTreeSaveData); CURRENT_VERSION as usize] = [migrate_v0_to_v1];
+
+/// Brings `save_data` up to `CURRENT_VERSION` one step at a time. Data from a
+/// newer editor is left as is for the caller to decide on.
+pub fn migrate(save_data: &mut SkillTreeSaveData) {
+    let Some(steps) = MIGRATIONS.get(save_data.version as usize..) else {
+        return;
+    };
+    for step in steps {
+        step(save_data);
+        save_data.version += 1;
+    }
+}
+
+/// Files from before versioning. Newer fields load through their serde
+/// defaults, but the start node has to be worked out from the nodes.
+fn migrate_v0_to_v1(save_data: &mut SkillTreeSaveData) {
+    if save_data.start_node_id.is_none() {
+        save_data.start_node_id = first_start_node_id(&save_data.nodes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Vec2;
+
+    fn node(id: u32, node_type: BuiltinNodeType) -> SkillNodeData {
+        SkillNodeData {
+            id,
+            name: format!("Node {}", id),
+            description: String::new(),
+            image_name: String::new(),
+            position: Vec2::new(id as f32 * 100.0, 0.0),
+            node_type: NodeType::Builtin(node_type),
+            stats: Vec::new(),
+            cost: 1,
+            tier: 0,
+            z_order: 0.0,
+            created_at: 0,
+            modified_at: 0,
+            ports: Vec::new(),
+        }
+    }
+
+    fn save_data(version: u32) -> SkillTreeSaveData {
+        SkillTreeSaveData {
+            version,
+            nodes: vec![
+                node(1, BuiltinNodeType::Normal),
+                node(2, BuiltinNodeType::Start),
+            ],
+            connections: Vec::new(),
+            start_node_id: None,
+            tree_name: String::new(),
+            tree_description: String::new(),
+            saved_camera_state: None,
+        }
+    }
+
+    #[test]
+    fn v0_gets_a_start_node() {
+        let mut data = save_data(0);
+        migrate(&mut data);
+        assert_eq!(data.version, CURRENT_VERSION);
+        assert_eq!(data.start_node_id, Some(2));
+    }
+
+    #[test]
+    fn newer_versions_are_left_untouched() {
+        let mut data = save_data(CURRENT_VERSION + 1);
+        migrate(&mut data);
+        assert_eq!(data.version, CURRENT_VERSION + 1);
+        assert_eq!(data.start_node_id, None);
+    }
+}
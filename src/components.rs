@@ -1,86 +1,479 @@
+use crate::diff::TreeDiff;
+use crate::validation::ValidationReport;
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct SkillNodeData {
-    pub id: u32,
-    pub name: String,
-    pub description: String,
-    pub image_name: String,
-    pub position: Vec2,
-    pub node_type: NodeType,
-    pub stats: Vec<StatModifier>,
-}
+pub use bevy_skill_tree::{
+    BuiltinNodeType, ConnectionData, CurveType, ModifierType, NodeType, PortAnchor, SkillNodeData,
+    SkillTreeSaveData, StatCondition, StatModifier,
+};
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct ConnectionData {
-    pub from_id: u32,
-    pub to_id: u32,
-    #[serde(default)]
-    pub curve_type: CurveType,
+pub struct NodeTypeStyle {
+    /// sRGB color
+    pub color: [f32; 3],
+    pub size: f32,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
-pub enum CurveType {
-    Straight,
-    Arc { radius: f32, clockwise: bool },
+/// Colors and sizes for custom node types, by type name. Loaded from
+/// `node_type_styles.ron` next to the editor when that file exists.
+#[derive(Resource, Default)]
+pub struct NodeTypeStyles {
+    pub styles: HashMap<String, NodeTypeStyle>,
 }
 
-impl Default for CurveType {
-    fn default() -> Self {
-        CurveType::Straight
+impl NodeTypeStyles {
+    pub const FILE_NAME: &str = "node_type_styles.ron";
+
+    fn custom_style(&self, node_type: &NodeType) -> Option<&NodeTypeStyle> {
+        match node_type {
+            NodeType::Custom(name) => self.styles.get(name),
+            NodeType::Builtin(_) => None,
+        }
+    }
+
+    pub fn tint(&self, node_type: &NodeType) -> Color {
+        let [r, g, b] = self
+            .custom_style(node_type)
+            .map_or_else(|| node_type.tint(), |style| style.color);
+        Color::srgb(r, g, b)
+    }
+
+    pub fn size(&self, node_type: &NodeType) -> f32 {
+        self.custom_style(node_type)
+            .map_or_else(|| node_type.size(), |style| style.size)
+    }
+
+    /// Drawn sprite size of a node, its type's size grown by its tier
+    pub fn node_size(&self, node: &SkillNodeData) -> f32 {
+        self.size(&node.node_type) + node.tier as f32 * SkillNodeData::TIER_SIZE_STEP
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct SkillTreeSaveData {
-    pub nodes: Vec<SkillNodeData>,
-    pub connections: Vec<ConnectionData>,
-    #[serde(default)]
-    pub start_node_id: Option<u32>,
+/// Click radius around node centers, by node type name. Kept in
+/// `editor_config.ron` and editable under View > Settings. Types without an
+/// entry use half the node's drawn size, so clicks land inside the sprite.
+#[derive(Resource, Default)]
+pub struct NodeTypeHitRadius {
+    pub radii: HashMap<String, f32>,
+}
+
+impl NodeTypeHitRadius {
+    pub fn radius(&self, node: &SkillNodeData, styles: &NodeTypeStyles) -> f32 {
+        self.radii
+            .get(node.node_type.name())
+            .copied()
+            .unwrap_or_else(|| styles.node_size(node) / 2.0)
+    }
+
+    /// Largest radius any node can have, for querying the spatial hash
+    pub fn max_radius(&self, styles: &NodeTypeStyles) -> f32 {
+        let largest_size = BuiltinNodeType::ALL
+            .into_iter()
+            .map(BuiltinNodeType::size)
+            .chain(styles.styles.values().map(|style| style.size))
+            .chain([NodeType::Custom(String::new()).size()])
+            .fold(0.0, f32::max)
+            + SkillNodeData::MAX_TIER as f32 * SkillNodeData::TIER_SIZE_STEP;
+        self.radii
+            .values()
+            .copied()
+            .fold(largest_size / 2.0, f32::max)
+    }
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
-pub enum NodeType {
-    Normal,
-    Notable,
-    Keystone,
-    Start,
+/// Base tint for each node type by type name, editable under View >
+/// Settings and kept in `editor_config.ron`. Takes precedence over
+/// `NodeTypeStyles` colors.
+#[derive(Resource)]
+pub struct NodeTypeColors {
+    /// sRGBA colors
+    pub colors: HashMap<String, [f32; 4]>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct StatModifier {
-    pub stat_name: String,
-    pub value: f32,
-    pub modifier_type: ModifierType,
+impl NodeTypeColors {
+    pub fn default_colors() -> HashMap<String, [f32; 4]> {
+        HashMap::from([
+            ("Normal".to_string(), [1.0, 1.0, 1.0, 1.0]),
+            ("Notable".to_string(), [1.0, 0.9, 0.3, 1.0]),
+            ("Keystone".to_string(), [0.9, 0.3, 0.3, 1.0]),
+            ("Start".to_string(), [0.3, 0.9, 0.9, 1.0]),
+        ])
+    }
+
+    pub fn tint(&self, node_type: &NodeType) -> Option<Color> {
+        self.colors
+            .get(node_type.name())
+            .map(|&[r, g, b, a]| Color::srgba(r, g, b, a))
+    }
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
-pub enum ModifierType {
-    Flat,
-    Percentage,
+impl Default for NodeTypeColors {
+    fn default() -> Self {
+        Self {
+            colors: Self::default_colors(),
+        }
+    }
 }
 
-#[derive(Component)]
+#[derive(Component, Reflect)]
+#[reflect(Component)]
 pub struct SkillNode {
     pub id: u32,
     pub data: SkillNodeData,
 }
 
-#[derive(Component)]
-pub struct ConnectionVisual {
-    pub from_id: u32,
-    pub to_id: u32,
-}
-
 #[derive(Default, Clone, Copy, PartialEq)]
 pub enum NextActionAfterSaveAs {
     #[default]
     None,
     ShowLoadDialog,
     CreateNewFile,
+    LoadDroppedFile,
+}
+
+#[derive(Default, Clone, Copy, PartialEq)]
+pub enum LoadDialogMode {
+    /// Replace the current tree with the chosen file
+    #[default]
+    Open,
+    /// Append the chosen file's nodes and connections to the current tree
+    Merge,
+    /// Load the chosen file into `CompareTree` and diff it against the current tree
+    Compare,
+    /// Update existing nodes from the chosen CSV file
+    CsvUpdate,
+    /// Merge the chosen subtree file, keeping its node ids
+    MergeSubtree,
+    /// Append the nodes and edges of the chosen Graphviz file
+    DotImport,
+}
+
+/// Kind of color vision deficiency the canvas colors are remapped for
+#[derive(Serialize, Deserialize, Default, Clone, Copy, PartialEq, Debug)]
+pub enum ColorblindMode {
+    #[default]
+    None,
+    Deuteranopia,
+    Protanopia,
+    Tritanopia,
+}
+
+impl ColorblindMode {
+    pub const ALL: [ColorblindMode; 4] = [
+        ColorblindMode::None,
+        ColorblindMode::Deuteranopia,
+        ColorblindMode::Protanopia,
+        ColorblindMode::Tritanopia,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ColorblindMode::None => "Off",
+            ColorblindMode::Deuteranopia => "Deuteranopia",
+            ColorblindMode::Protanopia => "Protanopia",
+            ColorblindMode::Tritanopia => "Tritanopia",
+        }
+    }
+}
+
+/// Canvas color preferences, kept in `editor_config.ron` and editable under
+/// View > Settings
+#[derive(Resource, Default)]
+pub struct ThemeSettings {
+    pub colorblind_mode: ColorblindMode,
+    /// White and yellow nodes and connections with black outlines, thicker
+    /// lines and larger UI text. Takes precedence over `colorblind_mode`.
+    pub high_contrast: bool,
+}
+
+/// Dark sprite behind a node, showing as its outline in high contrast mode
+#[derive(Component)]
+pub struct NodeOutline;
+
+/// Curve type shown in the side panel's connection list
+#[derive(Default, Clone, Copy, PartialEq)]
+pub enum ConnectionTypeFilter {
+    #[default]
+    All,
+    Straight,
+    Arc,
+    Curve,
+}
+
+impl ConnectionTypeFilter {
+    pub const ALL: [ConnectionTypeFilter; 4] = [
+        ConnectionTypeFilter::All,
+        ConnectionTypeFilter::Straight,
+        ConnectionTypeFilter::Arc,
+        ConnectionTypeFilter::Curve,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ConnectionTypeFilter::All => "All",
+            ConnectionTypeFilter::Straight => "Straight",
+            ConnectionTypeFilter::Arc => "Arc",
+            ConnectionTypeFilter::Curve => "Curve",
+        }
+    }
+
+    pub fn matches(self, curve_type: &CurveType) -> bool {
+        match self {
+            ConnectionTypeFilter::All => true,
+            ConnectionTypeFilter::Straight => matches!(curve_type, CurveType::Straight),
+            ConnectionTypeFilter::Arc => matches!(curve_type, CurveType::Arc { .. }),
+            ConnectionTypeFilter::Curve => matches!(curve_type, CurveType::CatmullRom { .. }),
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    #[default]
+    Svg,
+    Png,
+    Binary,
+    Csv,
+    Dot,
+    /// The node and everything downstream of it, as a RON tree keeping the
+    /// original ids
+    Subtree {
+        root: u32,
+    },
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Svg => "svg",
+            ExportFormat::Png => "png",
+            ExportFormat::Binary => "stb",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Dot => "dot",
+            ExportFormat::Subtree { .. } => "ron",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportFormat::Svg => "SVG",
+            ExportFormat::Png => "PNG",
+            ExportFormat::Binary => "Binary",
+            ExportFormat::Csv => "CSV (Nodes)",
+            ExportFormat::Dot => "DOT (Graphviz)",
+            ExportFormat::Subtree { .. } => "Subtree",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct PngExportSettings {
+    pub width: u32,
+    pub height: u32,
+    pub dpi: u32,
+    pub include_grid: bool,
+    /// Frame every node instead of reusing the current camera view
+    pub fit_all: bool,
+}
+
+impl Default for PngExportSettings {
+    fn default() -> Self {
+        Self {
+            width: 2048,
+            height: 2048,
+            dpi: 96,
+            include_grid: false,
+            fit_all: true,
+        }
+    }
+}
+
+pub struct PngExportRequest {
+    pub path: PathBuf,
+    pub settings: PngExportSettings,
+    /// Camera center and world units per pixel, used instead of
+    /// `settings.fit_all` or the current view
+    pub view: Option<(Vec2, f32)>,
+    /// Column and row of `PngExportState::stitch` this render fills, instead
+    /// of being written to `path`
+    pub stitch_tile: Option<UVec2>,
+}
+
+pub struct PngExportJob {
+    pub path: PathBuf,
+    pub settings: PngExportSettings,
+    pub stitch_tile: Option<UVec2>,
+    pub camera: Entity,
+    pub target: Handle<Image>,
+    pub screenshot_requested: bool,
+}
+
+/// Print layout pages being assembled into one PNG, each cropped to its
+/// printable area
+pub struct StitchedPng {
+    pub path: PathBuf,
+    pub dpi: u32,
+    pub columns: u32,
+    pub rows: u32,
+    /// Printable area of a page in pixels
+    pub tile_size: UVec2,
+    /// Pixels cropped from each edge of a page
+    pub margin: u32,
+    /// RGBA pixels of the whole image, row by row
+    pub pixels: Vec<u8>,
+    pub remaining: u32,
+}
+
+/// Renders are run one at a time, in the order they were requested
+#[derive(Resource, Default)]
+pub struct PngExportState {
+    pub requests: VecDeque<PngExportRequest>,
+    pub job: Option<PngExportJob>,
+    pub stitch: Option<StitchedPng>,
+}
+
+impl PngExportState {
+    pub fn hides_grid(&self) -> bool {
+        self.job
+            .as_ref()
+            .is_some_and(|job| !job.settings.include_grid)
+    }
+
+    /// Renders still to run, counting the one in progress
+    pub fn pending(&self) -> usize {
+        self.requests.len() + usize::from(self.job.is_some())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum PaperSize {
+    #[default]
+    A4,
+    Letter,
+    A3,
+}
+
+impl PaperSize {
+    pub const ALL: [PaperSize; 3] = [PaperSize::A4, PaperSize::Letter, PaperSize::A3];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PaperSize::A4 => "A4",
+            PaperSize::Letter => "Letter",
+            PaperSize::A3 => "A3",
+        }
+    }
+
+    /// Portrait width and height in millimeters
+    pub fn size_mm(self) -> Vec2 {
+        match self {
+            PaperSize::A4 => Vec2::new(210.0, 297.0),
+            PaperSize::Letter => Vec2::new(215.9, 279.4),
+            PaperSize::A3 => Vec2::new(297.0, 420.0),
+        }
+    }
+}
+
+/// File > Print Layout settings and the pages tiling the tree. Each page is
+/// rendered whole, so its margin shows the neighboring pages' edges for
+/// lining up printouts, and the printable areas meet at the page boundary
+/// lines drawn on the canvas.
+#[derive(Resource)]
+pub struct PrintLayout {
+    pub paper_size: PaperSize,
+    pub dpi: u32,
+    /// Output pixels per canvas unit
+    pub scale: f32,
+    pub margin_mm: f32,
+    pub file_name_buffer: String,
+    /// Printable area of each page in world space, row by row from the top left
+    pub pages: Vec<Rect>,
+    pub columns: u32,
+}
+
+impl Default for PrintLayout {
+    fn default() -> Self {
+        Self {
+            paper_size: PaperSize::default(),
+            dpi: 150,
+            scale: 1.0,
+            margin_mm: 10.0,
+            file_name_buffer: "output".to_string(),
+            pages: Vec::new(),
+            columns: 0,
+        }
+    }
+}
+
+impl PrintLayout {
+    const MM_PER_INCH: f32 = 25.4;
+    /// Largest page side in pixels, the same limit as PNG export
+    const MAX_PAGE_PIXELS: f32 = 8192.0;
+    /// Largest stitched image, in pixels per side and in total (1 GiB of RGBA)
+    pub const MAX_STITCHED_SIDE: u32 = 32_768;
+    pub const MAX_STITCHED_PIXELS: u64 = 1 << 28;
+
+    /// Highest DPI whose pages still fit in a render target
+    pub fn max_dpi(&self) -> u32 {
+        let longest_inches = self.paper_size.size_mm().max_element() / Self::MM_PER_INCH;
+        (Self::MAX_PAGE_PIXELS / longest_inches) as u32
+    }
+
+    pub fn page_pixels(&self) -> UVec2 {
+        (self.paper_size.size_mm() / Self::MM_PER_INCH * self.dpi as f32)
+            .round()
+            .as_uvec2()
+            .max(UVec2::splat(3))
+    }
+
+    /// Margin in pixels, always leaving at least one printable pixel
+    pub fn margin_pixels(&self) -> u32 {
+        let margin = (self.margin_mm / Self::MM_PER_INCH * self.dpi as f32).round() as u32;
+        margin.min((self.page_pixels().min_element() - 1) / 2)
+    }
+
+    /// Size of a page's printable area in pixels
+    pub fn tile_pixels(&self) -> UVec2 {
+        self.page_pixels() - UVec2::splat(2 * self.margin_pixels())
+    }
+
+    pub fn rows(&self) -> u32 {
+        (self.pages.len() as u32)
+            .checked_div(self.columns)
+            .unwrap_or_default()
+    }
+
+    /// Size of every page's printable area side by side, or `None` when it's
+    /// over `MAX_STITCHED_SIDE` or `MAX_STITCHED_PIXELS`
+    pub fn stitched_pixels(&self) -> Option<UVec2> {
+        let tile = self.tile_pixels();
+        let width = self.columns.checked_mul(tile.x)?;
+        let height = self.rows().checked_mul(tile.y)?;
+        let within_budget = width <= Self::MAX_STITCHED_SIDE
+            && height <= Self::MAX_STITCHED_SIDE
+            && u64::from(width) * u64::from(height) <= Self::MAX_STITCHED_PIXELS;
+        within_budget.then_some(UVec2::new(width, height))
+    }
+
+    /// Covers `bounds` with as few pages as fit, centered on it
+    pub fn lay_out(&mut self, bounds: Rect) {
+        let tile = self.tile_pixels().as_vec2() / self.scale;
+        let columns = (bounds.width() / tile.x).ceil().max(1.0) as u32;
+        let rows = (bounds.height() / tile.y).ceil().max(1.0) as u32;
+        let top_left = bounds.center() + Vec2::new(-(columns as f32), rows as f32) * tile * 0.5;
+        self.columns = columns;
+        self.pages = (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (column, row)))
+            .map(|(column, row)| {
+                let min = top_left + Vec2::new(column as f32, -(row as f32 + 1.0)) * tile;
+                Rect::from_corners(min, min + tile)
+            })
+            .collect();
+    }
 }
 
 #[derive(Resource, Default)]
@@ -89,7 +482,7 @@ pub struct EditorState {
     pub show_save_as_dialog: bool,
     pub show_load_dialog: bool,
     pub save_as_file_name_buffer: String,
-    pub available_ron_files: Vec<PathBuf>,
+    pub available_skill_tree_files: Vec<PathBuf>,
     pub next_node_id: u32,
     pub save_as_conflict_path: Option<PathBuf>,
     pub save_as_show_overwrite_prompt: bool,
@@ -97,24 +490,218 @@ pub struct EditorState {
     pub show_unsaved_changes_on_load_dialog: bool,
     pub show_unsaved_changes_on_new_dialog: bool,
     pub next_action_after_save_as: NextActionAfterSaveAs,
+    /// Save As writes a Bevy scene instead of plain save data
+    pub save_as_scene: bool,
     pub trigger_pending_action: NextActionAfterSaveAs,
+    /// File dropped onto the window, waiting for the unsaved changes prompt to resolve
+    pub pending_dropped_file: Option<PathBuf>,
+    /// File being loaded; the load runs the frame after the "Loading…" overlay is shown
+    pub loading_file: Option<PathBuf>,
+    pub loading_overlay_shown: bool,
+    pub load_dialog_mode: LoadDialogMode,
+    pub show_export_dialog: bool,
+    pub export_format: ExportFormat,
+    pub export_file_name_buffer: String,
+    pub export_show_overwrite_prompt: bool,
+    pub png_export_settings: PngExportSettings,
+    pub import_image_buffer: String,
+    /// Show node and connection properties in a floating window instead of the side panel
+    pub detach_properties: bool,
+    /// Hides the menu bar, status bar and side panel, leaving only the canvas
+    pub presentation_mode: bool,
+    pub properties_window_pos: Option<Vec2>,
+    pub side_panel_width: f32,
+    /// Node properties sections the user collapsed, by section key
+    pub collapsed_sections: HashMap<String, bool>,
+    pub node_search_query: String,
+    pub connection_search_query: String,
+    pub connection_type_filter: ConnectionTypeFilter,
+    /// Result of the last CSV import, shown until dismissed
+    pub csv_import_summary: Option<String>,
+    /// Type picked under "Delete All of Type" in the side panel
+    pub batch_delete_type: Option<NodeType>,
+    pub show_batch_delete_confirmation: bool,
+    /// Ask for a tree name after File > New
+    pub show_tree_name_prompt: bool,
+    pub tree_name_buffer: String,
+    pub show_settings: bool,
+    /// File saved by a newer editor, held until the user confirms loading it
+    pub pending_newer_load: Option<(PathBuf, SkillTreeSaveData)>,
+    /// Set when `pending_newer_load` is a backup being restored over the open
+    /// file, which leaves the editor with unsaved changes
+    pub pending_newer_is_restore: bool,
+    /// File whose standard load failed, with the error, offering a lenient load
+    pub failed_load: Option<(PathBuf, String)>,
+    /// Entries dropped by the last lenient load, shown until dismissed
+    pub lenient_load_warnings: Option<Vec<String>>,
+    /// Backups rotated beside a file each time it's saved over
+    pub max_backups: u32,
+    /// Whether saves record the camera view and loads return to it
+    pub remember_camera: bool,
+    /// Result of Edit > Validate Tree, shown until closed
+    pub validation_report: Option<ValidationReport>,
+    /// Node ids shown side by side in the Compare Nodes window
+    pub compared_nodes: Option<(u32, u32)>,
+    pub show_print_layout: bool,
+    pub show_performance_overlay: bool,
 }
 
+impl EditorState {
+    /// Enters or leaves presentation mode, reminding the user how to leave
+    pub fn toggle_presentation_mode(&mut self, toast: &mut TimedToast) {
+        self.presentation_mode = !self.presentation_mode;
+        if self.presentation_mode {
+            toast.info("Presentation mode: press F11 to exit");
+        }
+    }
+}
+
+/// Editor preferences kept between sessions in `editor_config.ron`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EditorConfig {
+    pub side_panel_width: f32,
+    #[serde(default = "NodeTypeColors::default_colors")]
+    pub node_type_colors: HashMap<String, [f32; 4]>,
+    #[serde(default = "EditorConfig::default_max_backups")]
+    pub max_backups: u32,
+    /// See `NodeTypeHitRadius`
+    #[serde(default)]
+    pub node_hit_radii: HashMap<String, f32>,
+    #[serde(default)]
+    pub connection_style_presets: Vec<ConnectionStylePreset>,
+    /// See `HotReloadSettings`
+    #[serde(default)]
+    pub reload_changed_files: bool,
+    /// See `EditorState::remember_camera`
+    #[serde(default)]
+    pub remember_camera: bool,
+    #[serde(default)]
+    pub metric_thresholds: MetricThresholds,
+    #[serde(default)]
+    pub colorblind_mode: ColorblindMode,
+    #[serde(default)]
+    pub high_contrast: bool,
+}
+
+impl EditorConfig {
+    pub const FILE_NAME: &str = "editor_config.ron";
+
+    fn default_max_backups() -> u32 {
+        3
+    }
+}
+
+impl Default for EditorConfig {
+    fn default() -> Self {
+        Self {
+            side_panel_width: 250.0,
+            node_type_colors: NodeTypeColors::default_colors(),
+            max_backups: Self::default_max_backups(),
+            node_hit_radii: HashMap::new(),
+            connection_style_presets: Vec::new(),
+            reload_changed_files: false,
+            remember_camera: false,
+            metric_thresholds: MetricThresholds::default(),
+            colorblind_mode: ColorblindMode::None,
+            high_contrast: false,
+        }
+    }
+}
+
+/// Watches the open file for changes made outside the editor, such as in a
+/// text editor, and offers to reload it
+#[derive(Resource, Default)]
+pub struct HotReloadSettings {
+    pub enabled: bool,
+    /// File being watched, following `EditorState::current_file_path`
+    pub watch_path: Option<PathBuf>,
+    /// Modification time last seen, `None` until the next poll records it
+    pub last_modified: Option<SystemTime>,
+    /// The watched file changed on disk and the user hasn't answered yet
+    pub reload_prompt: bool,
+}
+
+impl HotReloadSettings {
+    pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+    /// Takes the file's next modification time as is, so the editor's own
+    /// saves don't prompt a reload
+    pub fn rebaseline(&mut self) {
+        self.last_modified = None;
+    }
+}
+
+/// Node images by file name relative to the assets folder
 #[derive(Resource, Default)]
 pub struct NodeImages {
-    pub skill_node: Handle<Image>,
+    pub images: HashMap<String, Handle<Image>>,
+    /// Shown for nodes whose `image_name` isn't loaded
+    pub placeholder: Handle<Image>,
+}
+
+impl NodeImages {
+    pub const PLACEHOLDER: &str = "skill_border_01.png";
+
+    pub fn get(&self, image_name: &str) -> Handle<Image> {
+        self.images
+            .get(image_name)
+            .unwrap_or(&self.placeholder)
+            .clone()
+    }
+
+    pub fn load(&mut self, asset_server: &AssetServer, image_name: &str) {
+        let handle = asset_server.load(image_name.to_string());
+        self.images.insert(image_name.to_string(), handle);
+    }
 }
 
 #[derive(Resource, Default)]
 pub struct GridSettings {
     pub snap_to_grid: bool,
     pub grid_size: f32,
+    /// How far from a connection a click still selects it, in screen pixels
+    pub connection_selection_tolerance: f32,
+    /// Whether connections sharing both endpoints are drawn side by side
+    /// instead of on top of each other
+    pub bundle_parallel: bool,
+    /// Whether disabled connections are left out of the canvas entirely,
+    /// rather than drawn dashed
+    pub hide_disabled: bool,
+}
+
+impl GridSettings {
+    pub const CONNECTION_TOLERANCE_RANGE: std::ops::RangeInclusive<f32> = 2.0..=30.0;
+
+    /// Connection click tolerance in world units. Scaled by the camera zoom
+    /// so it covers the same screen distance at every zoom level.
+    pub fn connection_tolerance(&self, editor_camera: &EditorCamera) -> f32 {
+        self.connection_selection_tolerance * editor_camera.zoom
+    }
+}
+
+/// Sideways offsets that spread connections sharing both endpoints into a
+/// bundle, by connection index. Filled by `bundle_connections` while
+/// `GridSettings::bundle_parallel` is on.
+#[derive(Resource, Default)]
+pub struct ConnectionBundles {
+    pub offsets: HashMap<usize, Vec2>,
+}
+
+/// Paths of straight connections that bend around the nodes they would
+/// cross, by connection index. Filled by `route_connections` while
+/// `ConnectionSettings::route_around_nodes` is on, and used both to draw
+/// those connections and to pick them.
+#[derive(Resource, Default)]
+pub struct ConnectionRoutes {
+    pub paths: HashMap<usize, Vec<Vec2>>,
 }
 
 #[derive(Resource, Default)]
 pub struct SkillTreeData {
     pub nodes: HashMap<u32, Entity>,
     pub connections: Vec<ConnectionData>,
+    pub tree_name: String,
+    pub tree_description: String,
 }
 
 #[derive(Resource, Default)]
@@ -123,6 +710,83 @@ pub struct SelectedNode {
     pub id: Option<u32>,
 }
 
+/// Cursor position on the canvas in world units, `None` while the cursor is
+/// outside the window
+#[derive(Resource, Default)]
+pub struct CursorWorldPos(pub Option<Vec2>);
+
+/// A node saved under a name so copies of it can be placed later
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SkillNodeTemplate {
+    pub name: String,
+    pub node: SkillNodeData,
+}
+
+/// Reusable node presets, kept in `templates.ron` in the working directory
+#[derive(Resource, Default)]
+pub struct TemplateLibrary {
+    pub templates: Vec<SkillNodeTemplate>,
+    /// Template placed by the next right-click on the canvas
+    pub placing: Option<usize>,
+}
+
+/// While active, right-clicking empty canvas places a copy of the selected
+/// node instead of opening the context menu
+#[derive(Resource, Default)]
+pub struct StampMode {
+    pub active: bool,
+}
+
+/// Node with keyboard focus, moved by Tab and the arrow keys. Separate from
+/// the selection: Space selects the focused node and Enter opens its edit
+/// dialog. While a node has focus the arrow keys move focus instead of
+/// panning the camera.
+#[derive(Resource, Default)]
+pub struct KeyboardNavigation {
+    pub focused: Option<u32>,
+}
+
+impl TemplateLibrary {
+    pub const FILE_NAME: &str = "templates.ron";
+
+    /// Adds a template, numbering the name if it's already taken
+    pub fn add(&mut self, name: &str, node: SkillNodeData) {
+        let mut unique_name = name.to_string();
+        let mut suffix = 2;
+        while self.templates.iter().any(|t| t.name == unique_name) {
+            unique_name = format!("{} ({})", name, suffix);
+            suffix += 1;
+        }
+        self.templates.push(SkillNodeTemplate {
+            name: unique_name,
+            node,
+        });
+    }
+
+    pub fn placing_template(&self) -> Option<&SkillNodeTemplate> {
+        self.placing.and_then(|index| self.templates.get(index))
+    }
+}
+
+/// Node under the cursor, set without clicking
+#[derive(Resource, Default)]
+pub struct HoveredNode {
+    pub entity: Option<Entity>,
+    pub id: Option<u32>,
+}
+
+/// Connection under the cursor, set without clicking
+#[derive(Resource, Default)]
+pub struct HoveredConnection {
+    pub index: Option<usize>,
+}
+
+/// Extra nodes selected together with Ctrl+click, by node id
+#[derive(Resource, Default)]
+pub struct MultiSelectedNodes {
+    pub nodes: HashSet<u32>,
+}
+
 #[derive(Resource, Default)]
 pub struct SelectedConnection {
     pub index: Option<usize>,
@@ -132,12 +796,246 @@ pub struct SelectedConnection {
 pub struct DragState {
     pub dragging: bool,
     pub offset: Vec2,
+    /// Where the dragged node would be without grid snapping
+    pub raw_position: Vec2,
+    /// How far snapping moves the node from `raw_position`
+    pub snap_delta: Vec2,
+    /// Offset of every other multi-selected node from the dragged node,
+    /// taken when the drag starts so the group keeps its shape
+    pub group_offsets: HashMap<u32, Vec2>,
+}
+
+/// Dragging one end of the selected connection onto another node
+#[derive(Resource, Default)]
+pub struct ConnectionDragState {
+    pub dragging: bool,
+    pub index: usize,
+    /// Whether the `from` end is being moved, rather than the `to` end
+    pub moving_from: bool,
+}
+
+/// Dragging one of the shape handles of the selected connection
+#[derive(Resource, Default)]
+pub struct ConnectionHandleDragState {
+    pub dragging: bool,
+    pub index: usize,
+    /// Handle being dragged, as numbered by `waypoint_handles`
+    pub handle: usize,
+    pub grab_position: Vec2,
+    /// Waypoint following the cursor, once the handle has moved
+    pub waypoint: Option<usize>,
+}
+
+/// Last left click on a node, for double-click detection
+#[derive(Resource, Default)]
+pub struct DoubleClickState {
+    /// Node id and `Time::elapsed_secs_f64` of the click
+    pub last_click: Option<(u32, f64)>,
+}
+
+impl DoubleClickState {
+    /// Longest gap between the clicks of a double-click, in seconds
+    pub const THRESHOLD: f64 = 0.3;
+}
+
+/// Node opened in the edit dialog by double-clicking. Changes go to `draft`
+/// and are only written back to the node on OK.
+#[derive(Resource, Default)]
+pub struct NodeEditDialog {
+    pub node: Option<(Entity, SkillNodeData)>,
+}
+
+/// Name being typed into the F2 rename box over a node
+#[derive(Resource, Default)]
+pub struct InlineRename {
+    pub node: Option<(Entity, String)>,
+    /// Whether the text field has been given keyboard focus yet
+    pub focused: bool,
+}
+
+/// What a context menu was opened on
+#[derive(Clone, Copy, PartialEq)]
+pub enum ContextMenuTarget {
+    Canvas,
+    Node { id: u32, entity: Entity },
+    Connection(usize),
+}
+
+/// Right-click menu, opened by `handle_mouse_input` and drawn by the UI
+#[derive(Resource, Default)]
+pub struct ContextMenuState {
+    pub target: Option<ContextMenuTarget>,
+    /// Right-clicked world position, snapped when snapping is on
+    pub world_position: Vec2,
+    /// Cursor position in the window, where the menu opens
+    pub screen_position: Vec2,
+    /// Port of the right-clicked node near the cursor, where Connect From
+    /// Here starts
+    pub port: Option<u32>,
 }
 
 #[derive(Resource, Default)]
 pub struct ConnectionMode {
     pub active: bool,
     pub start_node: Option<u32>,
+    /// Port of the start node the new connection leaves from, or its center
+    pub start_port: Option<u32>,
+}
+
+/// A node was added to the tree, by id
+#[derive(Event, Clone, Copy, Debug)]
+pub struct NodeCreatedEvent(pub u32);
+
+/// A node was removed from the tree, by id
+#[derive(Event, Clone, Copy, Debug)]
+pub struct NodeDeletedEvent(pub u32);
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ConnectionCreatedEvent {
+    pub from_id: u32,
+    pub to_id: u32,
+}
+
+/// A connection was removed. `index` is its position in
+/// `SkillTreeData::connections` before the removal.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ConnectionDeletedEvent {
+    pub index: usize,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct NodeMovedEvent {
+    pub id: u32,
+    pub new_pos: Vec2,
+}
+
+/// A connection look saved under a name, applied from the Presets menu in
+/// the connection properties
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ConnectionStylePreset {
+    pub name: String,
+    pub curve_type: CurveType,
+}
+
+/// Connection style presets, kept in `editor_config.ron`
+#[derive(Resource, Default)]
+pub struct ConnectionStylePresets {
+    pub presets: Vec<ConnectionStylePreset>,
+    /// Set when the presets changed and the config file should be rewritten
+    pub needs_save: bool,
+}
+
+impl ConnectionStylePresets {
+    /// Adds a preset named after its curve, numbering the name if it's
+    /// already taken. Returns the name used.
+    pub fn add(&mut self, curve_type: CurveType) -> String {
+        let name = match curve_type {
+            CurveType::Straight => "Straight".to_string(),
+            CurveType::Arc {
+                radius,
+                clockwise,
+                animated,
+            } => format!(
+                "Arc {:.0}{}{}",
+                radius,
+                if clockwise { " clockwise" } else { "" },
+                if animated { " animated" } else { "" }
+            ),
+            CurveType::CatmullRom { ref waypoints } => format!("Curve {}", waypoints.len()),
+        };
+        let mut unique_name = name.clone();
+        let mut suffix = 2;
+        while self.presets.iter().any(|p| p.name == unique_name) {
+            unique_name = format!("{} ({})", name, suffix);
+            suffix += 1;
+        }
+        self.presets.push(ConnectionStylePreset {
+            name: unique_name.clone(),
+            curve_type,
+        });
+        self.needs_save = true;
+        unique_name
+    }
+}
+
+/// Options for creating connections in connection mode, and for drawing them
+#[derive(Resource)]
+pub struct ConnectionSettings {
+    /// While Alt is held, only nodes at a multiple of this angle from the
+    /// start node can be connected to, in degrees
+    pub angle_snap_degrees: f32,
+    /// Bend straight connections around nodes they would otherwise cross
+    pub route_around_nodes: bool,
+}
+
+impl ConnectionSettings {
+    pub const ANGLE_SNAP_PRESETS: [f32; 4] = [15.0, 30.0, 45.0, 90.0];
+    /// How far off the snapped angle a node may lie and still be targeted,
+    /// in degrees, since hand-placed nodes are rarely exactly aligned
+    pub const ANGLE_SNAP_TOLERANCE: f32 = 3.0;
+}
+
+impl Default for ConnectionSettings {
+    fn default() -> Self {
+        Self {
+            angle_snap_degrees: 45.0,
+            route_around_nodes: false,
+        }
+    }
+}
+
+/// Marks the camera that renders the editor canvas to the window
+#[derive(Component)]
+pub struct MainCamera;
+
+/// Marks the camera that renders the secondary viewport window
+#[derive(Component)]
+pub struct ViewportCamera;
+
+/// View > New Viewport: a second window onto the same tree, with a camera
+/// that zooms and pans independently of the main canvas
+#[derive(Resource, Default)]
+pub struct SecondaryViewport {
+    pub window: Option<Entity>,
+    pub camera: EditorCamera,
+    /// Whether clicks in the viewport edit the tree like the main canvas,
+    /// rather than only looking at it
+    pub editable: bool,
+}
+
+impl SecondaryViewport {
+    /// Zoom the viewport opens at, so it starts as an overview of the tree
+    pub const INITIAL_ZOOM: f32 = 3.0;
+}
+
+/// Fly-in played when a tree loads: the camera starts far out and eases in
+/// until every node is framed. Any key skips to the end.
+#[derive(Resource, Default)]
+pub struct CameraTransition {
+    /// Set by a load; the transition starts once the loaded nodes exist
+    pub pending: bool,
+    /// View to fly to instead of framing every node, from the camera state
+    /// saved in the loaded file
+    pub saved_view: Option<(Vec2, f32)>,
+    pub active: bool,
+    /// Linear progress from 0 to 1, before easing
+    pub progress: f32,
+    pub start_pan: Vec2,
+    pub start_zoom: f32,
+    pub end_pan: Vec2,
+    pub end_zoom: f32,
+}
+
+impl CameraTransition {
+    /// Length of the fly-in in seconds
+    pub const DURATION: f32 = 1.0;
+    pub const START_ZOOM: f32 = EditorCamera::MAX_ZOOM;
+
+    /// Progress with an ease-out quadratic applied, so the camera slows down
+    /// as it settles
+    pub fn eased_progress(&self) -> f32 {
+        1.0 - (1.0 - self.progress).powi(2)
+    }
 }
 
 #[derive(Resource)]
@@ -147,6 +1045,33 @@ pub struct EditorCamera {
     pub pan_offset: Vec2,
 }
 
+impl EditorCamera {
+    /// Camera scale bounds; a smaller scale shows the canvas larger
+    pub const MIN_ZOOM: f32 = 0.1;
+    pub const MAX_ZOOM: f32 = 5.0;
+    pub const PRESETS_PERCENT: [f32; 4] = [25.0, 50.0, 100.0, 200.0];
+
+    /// Magnification shown in the status bar, where 100% is a scale of 1
+    pub fn target_zoom_percent(&self) -> f32 {
+        100.0 / self.target_zoom
+    }
+
+    /// Eases back to 100% zoom centered on the origin
+    pub fn reset_view(&mut self) {
+        self.target_zoom = 1.0;
+        self.pan_offset = Vec2::ZERO;
+    }
+
+    pub fn reset_zoom(&mut self) {
+        self.target_zoom = 1.0;
+    }
+
+    pub fn set_target_zoom_percent(&mut self, percent: f32) {
+        self.target_zoom =
+            (100.0 / percent.max(f32::EPSILON)).clamp(Self::MIN_ZOOM, Self::MAX_ZOOM);
+    }
+}
+
 impl Default for EditorCamera {
     fn default() -> Self {
         Self {
@@ -157,8 +1082,287 @@ impl Default for EditorCamera {
     }
 }
 
+/// Nodes reachable from a Start node, recomputed while the overlay is enabled
+#[derive(Resource, Default)]
+pub struct ReachabilitySet {
+    pub enabled: bool,
+    pub reachable: HashSet<u32>,
+}
+
+impl ReachabilitySet {
+    pub fn is_unreachable(&self, node_id: u32) -> bool {
+        self.enabled && !self.reachable.contains(&node_id)
+    }
+}
+
+/// Every node upstream or downstream of the selected node, following
+/// connection direction over any number of hops. The toggles reset when the
+/// node is deselected.
+#[derive(Resource, Default)]
+pub struct DependencyHighlight {
+    pub show_predecessors: bool,
+    pub show_successors: bool,
+    pub predecessors: HashSet<u32>,
+    pub successors: HashSet<u32>,
+}
+
+/// Paths from a Start node to the selected node, recomputed while the
+/// overlay is enabled
+#[derive(Resource, Default)]
+pub struct StartPaths {
+    pub enabled: bool,
+    /// Selected node the paths lead to, `None` when it's a Start node
+    pub target: Option<u32>,
+    /// The `MAX_SHOWN` shortest paths by hop count, or all of them when
+    /// there are fewer
+    pub paths: Vec<StartPath>,
+    /// Number of simple paths counted, at most `MAX_SEARCHED`
+    pub total: usize,
+}
+
+pub struct StartPath {
+    /// Node ids from the Start node to the selected node
+    pub nodes: Vec<u32>,
+    /// Sum of the cost of every node on the path
+    pub cost: u32,
+}
+
+impl StartPaths {
+    pub const MAX_SHOWN: usize = 5;
+    /// Dense trees can have exponentially many paths, so counting stops here
+    pub const MAX_SEARCHED: usize = 1000;
+
+    /// Distinct hue for the path at `index`
+    pub fn color(index: usize) -> Color {
+        Color::hsl(index as f32 * 360.0 / Self::MAX_SHOWN as f32, 0.85, 0.6)
+    }
+}
+
+/// View > Color by Centrality: nodes tinted by approximate betweenness
+/// centrality, recomputed while enabled
+#[derive(Resource, Default)]
+pub struct CentralityColoring {
+    pub enabled: bool,
+    /// Centrality of every node, from 0 to 1
+    pub values: HashMap<u32, f32>,
+}
+
+impl CentralityColoring {
+    /// Source nodes sampled for the estimate, traded against accuracy
+    pub const SAMPLES: usize = 64;
+
+    /// Gradient from blue for the least central nodes to red for the most
+    pub fn color(value: f32) -> Color {
+        Color::hsl(240.0 * (1.0 - value.clamp(0.0, 1.0)), 0.8, 0.55)
+    }
+
+    pub fn node_color(&self, node_id: u32) -> Option<Color> {
+        if !self.enabled {
+            return None;
+        }
+        self.values.get(&node_id).copied().map(Self::color)
+    }
+}
+
+/// Directed cycles in the connection graph, refreshed when connections change
+#[derive(Resource, Default)]
+pub struct CycleWarnings {
+    pub cycles: Vec<Vec<u32>>,
+    pub nodes: HashSet<u32>,
+}
+
+/// A second tree loaded with File > Compare With..., kept alongside the open
+/// tree without replacing it
+#[derive(Resource, Default)]
+pub struct CompareTree {
+    pub path: Option<PathBuf>,
+    pub nodes: HashMap<u32, SkillNodeData>,
+    pub diff: TreeDiff,
+}
+
+impl CompareTree {
+    pub fn is_active(&self) -> bool {
+        self.path.is_some()
+    }
+
+    pub fn close(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Marks a sprite standing in for a node that only exists in the compare tree
+#[derive(Component)]
+pub struct GhostNode;
+
+/// Marks the translucent node following the cursor over empty canvas, showing
+/// where a new node would be placed
+#[derive(Component)]
+pub struct GhostNodeSprite;
+
+/// Hides nodes below a tier, and connections touching them
+#[derive(Resource, Default)]
+pub struct TierFilter {
+    pub min_tier: u32,
+}
+
+impl TierFilter {
+    pub fn hides(&self, tier: u32) -> bool {
+        tier < self.min_tier
+    }
+}
+
+/// Problems found in node data, refreshed when nodes change
+#[derive(Resource, Default)]
+pub struct ValidationWarnings {
+    pub messages: Vec<String>,
+    /// Set when the tree doesn't have exactly one Start node
+    pub start_nodes: Option<String>,
+}
+
+/// Limits past which a topology metric is flagged in the Metrics section.
+/// Kept in `editor_config.ron` and editable under View > Settings.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct MetricThresholds {
+    pub max_degree: usize,
+    pub longest_path: usize,
+    pub isolated_nodes: usize,
+    pub cycles: usize,
+    pub components: usize,
+}
+
+impl Default for MetricThresholds {
+    fn default() -> Self {
+        Self {
+            max_degree: 10,
+            longest_path: 30,
+            isolated_nodes: 0,
+            cycles: 0,
+            components: 1,
+        }
+    }
+}
+
+/// Shape of the tree for the side panel's Metrics section, recomputed only
+/// when the tree changes
+#[derive(Resource, Default)]
+pub struct TopologyMetrics {
+    pub node_count: usize,
+    pub connection_count: usize,
+    /// Connections per node, counting both ends of each connection
+    pub average_degree: f32,
+    pub max_degree: usize,
+    pub isolated_nodes: usize,
+    /// Connections on the longest directed path from a Start node, or `None`
+    /// when a cycle reachable from Start makes it unbounded
+    pub longest_path: Option<usize>,
+    pub cycles: usize,
+    pub components: usize,
+    pub thresholds: MetricThresholds,
+}
+
+/// Per-frame counters shown in the status bar and the performance overlay
+#[derive(Resource, Default)]
+pub struct EditorStats {
+    pub culled_connections: u32,
+    /// Connections drawn this frame, after culling and filtering
+    pub drawn_connections: u32,
+    pub grid_lines: u32,
+}
+
+/// Recent frame times in seconds, oldest first, for the performance overlay
+#[derive(Resource, Default)]
+pub struct FrameTimeHistory {
+    pub frame_times: VecDeque<f32>,
+}
+
+impl FrameTimeHistory {
+    pub const LENGTH: usize = 60;
+
+    pub fn record(&mut self, frame_time: f32) {
+        if self.frame_times.len() == Self::LENGTH {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(frame_time);
+    }
+}
+
 #[derive(Resource, Default)]
 pub struct EguiInputState {
     pub wants_pointer_input: bool,
     pub wants_keyboard_input: bool,
 }
+
+#[derive(Default, Clone, Copy, PartialEq)]
+pub enum ToastKind {
+    #[default]
+    Info,
+    Warning,
+    Error,
+}
+
+/// Short-lived message shown at the bottom of the canvas
+#[derive(Resource, Default)]
+pub struct TimedToast {
+    pub message: String,
+    pub kind: ToastKind,
+    pub remaining: f32,
+}
+
+impl TimedToast {
+    const DURATION: f32 = 3.0;
+
+    pub fn show(&mut self, kind: ToastKind, message: impl Into<String>) {
+        self.message = message.into();
+        self.kind = kind;
+        self.remaining = Self::DURATION;
+    }
+
+    pub fn info(&mut self, message: impl Into<String>) {
+        self.show(ToastKind::Info, message);
+    }
+
+    pub fn warning(&mut self, message: impl Into<String>) {
+        self.show(ToastKind::Warning, message);
+    }
+
+    pub fn error(&mut self, message: impl Into<String>) {
+        self.show(ToastKind::Error, message);
+    }
+}
+
+/// Buckets node entities by grid cell so hit tests only look at nearby nodes
+#[derive(Resource, Default)]
+pub struct SpatialHashGrid {
+    pub cells: HashMap<(i32, i32), Vec<(Entity, u32)>>,
+}
+
+impl SpatialHashGrid {
+    /// Matches the node sprite size
+    pub const CELL_SIZE: f32 = 60.0;
+
+    pub fn cell_of(position: Vec2) -> (i32, i32) {
+        (
+            (position.x / Self::CELL_SIZE).floor() as i32,
+            (position.y / Self::CELL_SIZE).floor() as i32,
+        )
+    }
+
+    pub fn insert(&mut self, position: Vec2, entity: Entity, id: u32) {
+        self.cells
+            .entry(Self::cell_of(position))
+            .or_default()
+            .push((entity, id));
+    }
+
+    /// Nodes in every cell overlapping the square of `radius` around `position`
+    pub fn query(&self, position: Vec2, radius: f32) -> impl Iterator<Item = (Entity, u32)> + '_ {
+        let (min_x, min_y) = Self::cell_of(position - Vec2::splat(radius));
+        let (max_x, max_y) = Self::cell_of(position + Vec2::splat(radius));
+        (min_x..=max_x)
+            .flat_map(move |x| (min_y..=max_y).map(move |y| (x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+    }
+}
@@ -0,0 +1,45 @@
+use crate::components::*;
+use std::collections::{HashMap, HashSet};
+
+/// Node ids that differ between the open tree and a comparison tree, matched
+/// by id
+#[derive(Default)]
+pub struct TreeDiff {
+    pub only_current: HashSet<u32>,
+    pub only_compare: HashSet<u32>,
+    pub changed: HashSet<u32>,
+}
+
+impl TreeDiff {
+    pub fn is_empty(&self) -> bool {
+        self.only_current.is_empty() && self.only_compare.is_empty() && self.changed.is_empty()
+    }
+}
+
+pub fn diff_nodes<'a>(
+    current: impl IntoIterator<Item = &'a SkillNodeData>,
+    compare: &HashMap<u32, SkillNodeData>,
+) -> TreeDiff {
+    let mut diff = TreeDiff::default();
+    let mut seen = HashSet::new();
+
+    for node in current {
+        seen.insert(node.id);
+        match compare.get(&node.id) {
+            None => {
+                diff.only_current.insert(node.id);
+            }
+            Some(other) if other != node => {
+                diff.changed.insert(node.id);
+            }
+            Some(_) => {}
+        }
+    }
+
+    diff.only_compare = compare
+        .keys()
+        .filter(|id| !seen.contains(id))
+        .copied()
+        .collect();
+    diff
+}
@@ -0,0 +1,238 @@
+use crate::components::*;
+use crate::storage;
+use bevy::prelude::*;
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+const FIXED_COLUMNS: [&str; 8] = [
+    "id",
+    "name",
+    "description",
+    "node_type",
+    "position_x",
+    "position_y",
+    "tier",
+    "cost",
+];
+
+pub fn export_csv(path: &str, nodes: &[SkillNodeData]) -> std::io::Result<()> {
+    storage::write(path, nodes_to_csv(nodes))?;
+    info!("Node list exported to {}", path);
+    Ok(())
+}
+
+/// One row per node, followed by a column for every stat name used by any
+/// node. A node with several modifiers for the same stat lists their values
+/// separated by "; ".
+pub fn nodes_to_csv(nodes: &[SkillNodeData]) -> String {
+    let stat_names: BTreeSet<&str> = nodes
+        .iter()
+        .flat_map(|node| &node.stats)
+        .map(|stat| stat.stat_name.as_str())
+        .collect();
+
+    let mut csv = String::new();
+    let header: Vec<&str> = FIXED_COLUMNS
+        .iter()
+        .copied()
+        .chain(stat_names.iter().copied())
+        .collect();
+    write_row(&mut csv, &header);
+
+    for node in nodes {
+        let mut row = vec![
+            node.id.to_string(),
+            node.name.clone(),
+            node.description.clone(),
+            node.node_type.name().to_string(),
+            node.position.x.to_string(),
+            node.position.y.to_string(),
+            node.tier.to_string(),
+            node.cost.to_string(),
+        ];
+        for stat_name in &stat_names {
+            let values: Vec<String> = node
+                .stats
+                .iter()
+                .filter(|stat| stat.stat_name == *stat_name)
+                .map(|stat| stat.value.to_string())
+                .collect();
+            row.push(values.join("; "));
+        }
+        write_row(&mut csv, &row);
+    }
+    csv
+}
+
+/// Writes one RFC 4180 record, terminated by CRLF
+fn write_row(csv: &mut String, fields: &[impl AsRef<str>]) {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            csv.push(',');
+        }
+        let field = field.as_ref();
+        if field.contains([',', '"', '\r', '\n']) {
+            let _ = write!(csv, "\"{}\"", field.replace('"', "\"\""));
+        } else {
+            csv.push_str(field);
+        }
+    }
+    csv.push_str("\r\n");
+}
+
+/// One row of a CSV produced by `nodes_to_csv`, holding the fields that CSV
+/// import writes back. Positions and node types are left alone.
+pub struct CsvNodeRow {
+    pub id: u32,
+    pub name: String,
+    pub description: String,
+    pub tier: u32,
+    pub cost: u32,
+    /// Values for every stat column, empty when the cell was empty
+    pub stats: Vec<(String, Vec<f32>)>,
+}
+
+impl CsvNodeRow {
+    /// Updates `node` from this row. Existing modifiers of a stat take the
+    /// row's values in order; extra values become new flat modifiers and
+    /// modifiers without a value are removed.
+    pub fn apply(&self, node: &mut SkillNodeData) {
+        node.name = self.name.clone();
+        node.description = self.description.clone();
+        node.tier = self.tier.min(SkillNodeData::MAX_TIER);
+        node.cost = self.cost;
+
+        for (stat_name, values) in &self.stats {
+            let mut values = values.iter();
+            node.stats.retain_mut(|stat| {
+                if stat.stat_name != *stat_name {
+                    return true;
+                }
+                match values.next() {
+                    Some(&value) => {
+                        stat.value = value;
+                        true
+                    }
+                    None => false,
+                }
+            });
+            for &value in values {
+                node.stats.push(StatModifier {
+                    stat_name: stat_name.clone(),
+                    value,
+                    modifier_type: ModifierType::Flat,
+                    min_value: None,
+                    max_value: None,
+                    condition: None,
+                });
+            }
+        }
+    }
+}
+
+pub fn read_csv_nodes(path: &str) -> Result<Vec<CsvNodeRow>, Box<dyn std::error::Error>> {
+    let records = parse_csv(&storage::read_to_string(path)?)?;
+    let Some((header, rows)) = records.split_first() else {
+        return Ok(Vec::new());
+    };
+
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|column| column == name)
+            .ok_or(format!("Missing '{}' column", name))
+    };
+    let id_column = column("id")?;
+    let name_column = column("name")?;
+    let description_column = column("description")?;
+    let tier_column = column("tier")?;
+    let cost_column = column("cost")?;
+    let stat_columns: Vec<(usize, &String)> = header
+        .iter()
+        .enumerate()
+        .filter(|(_, column)| !FIXED_COLUMNS.contains(&column.as_str()))
+        .collect();
+
+    let mut nodes = Vec::with_capacity(rows.len());
+    for (i, row) in rows.iter().enumerate() {
+        // Header is line 1
+        let line = i + 2;
+        let cell = |column: usize| row.get(column).map_or("", String::as_str);
+        let number = |column: usize| {
+            cell(column)
+                .trim()
+                .parse::<u32>()
+                .map_err(|_| format!("Line {}: '{}' is not a whole number", line, cell(column)))
+        };
+
+        let mut stats = Vec::with_capacity(stat_columns.len());
+        for &(column, stat_name) in &stat_columns {
+            let values = cell(column)
+                .split(';')
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(|value| {
+                    value
+                        .parse::<f32>()
+                        .map_err(|_| format!("Line {}: '{}' is not a number", line, value))
+                })
+                .collect::<Result<Vec<f32>, String>>()?;
+            stats.push((stat_name.clone(), values));
+        }
+
+        nodes.push(CsvNodeRow {
+            id: number(id_column)?,
+            name: cell(name_column).to_string(),
+            description: cell(description_column).to_string(),
+            tier: number(tier_column)?,
+            cost: number(cost_column)?,
+            stats,
+        });
+    }
+    Ok(nodes)
+}
+
+/// Splits RFC 4180 text into records of fields. Accepts LF as well as CRLF
+/// line endings and skips blank lines.
+fn parse_csv(text: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                if !(record.len() == 1 && record[0].is_empty()) {
+                    records.push(std::mem::take(&mut record));
+                }
+                record.clear();
+            }
+            _ => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err("Unterminated quoted field".to_string());
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    Ok(records)
+}
@@ -0,0 +1,478 @@
+use crate::components::*;
+use crate::graph;
+use crate::storage;
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+pub fn export_dot(
+    path: &str,
+    nodes: &[SkillNodeData],
+    connections: &[ConnectionData],
+) -> std::io::Result<()> {
+    storage::write(path, skill_tree_to_dot(nodes, connections))?;
+    info!("Skill tree exported to {}", path);
+    Ok(())
+}
+
+/// Writes the tree as a Graphviz digraph, one node per skill node keyed by
+/// id and one edge per connection. Layout is left to Graphviz.
+pub fn skill_tree_to_dot(nodes: &[SkillNodeData], connections: &[ConnectionData]) -> String {
+    let mut dot = String::from("digraph skill_tree {\n");
+    let _ = writeln!(dot, "  node [style=filled, fillcolor=\"#f5f0e6\"];");
+    for node in nodes {
+        let _ = writeln!(
+            dot,
+            "  n{} [label=\"{}\", shape={}];",
+            node.id,
+            escape(&node.name),
+            shape(&node.node_type)
+        );
+    }
+    for connection in connections {
+        let style = if connection.enabled {
+            ""
+        } else {
+            " [style=dashed]"
+        };
+        let _ = writeln!(
+            dot,
+            "  n{} -> n{}{};",
+            connection.from_id, connection.to_id, style
+        );
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+fn shape(node_type: &NodeType) -> &'static str {
+    match node_type {
+        NodeType::Builtin(BuiltinNodeType::Start) => "doublecircle",
+        NodeType::Builtin(BuiltinNodeType::Keystone) => "diamond",
+        NodeType::Builtin(BuiltinNodeType::Notable) => "octagon",
+        NodeType::Builtin(BuiltinNodeType::Mastery) => "hexagon",
+        _ => "circle",
+    }
+}
+
+/// Escapes text for a double-quoted DOT string
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Spacing of the default grid for nodes without a `pos` attribute
+const IMPORT_GRID_SPACING: f32 = 150.0;
+
+/// Nodes and connections read by `read_dot`, with ids numbered from 0 in the
+/// order nodes first appear
+pub struct DotGraph {
+    pub nodes: Vec<SkillNodeData>,
+    pub connections: Vec<ConnectionData>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    /// Plain, numeral, quoted or HTML id, already unquoted
+    Id(String),
+    /// `->` or `--`
+    Edge,
+    Symbol(char),
+}
+
+/// A node as declared in the file, before it becomes a `SkillNodeData`
+#[derive(Default)]
+struct DotNode {
+    label: Option<String>,
+    shape: Option<String>,
+    pos: Option<Vec2>,
+}
+
+pub fn read_dot(path: &str) -> Result<DotGraph, Box<dyn std::error::Error>> {
+    Ok(parse_dot(&storage::read_to_string(path)?)?)
+}
+
+/// Parses the subset of DOT that trees drawn in other tools need: node
+/// statements with `label`, `shape` and `pos` attributes, and edge chains.
+/// Subgraphs are flattened and default/graph attributes are ignored.
+/// Nodes without a `pos` (as written by `dot -Tdot`) are laid out on a grid.
+fn parse_dot(text: &str) -> Result<DotGraph, String> {
+    let tokens = tokenize(text)?;
+    let mut tokens = tokens.iter().peekable();
+
+    if matches!(tokens.peek(), Some(Token::Id(id)) if id.eq_ignore_ascii_case("strict")) {
+        tokens.next();
+    }
+    match tokens.next() {
+        Some(Token::Id(id))
+            if id.eq_ignore_ascii_case("graph") || id.eq_ignore_ascii_case("digraph") => {}
+        _ => return Err("Expected 'graph' or 'digraph'".to_string()),
+    }
+    if matches!(tokens.peek(), Some(Token::Id(_))) {
+        tokens.next();
+    }
+    if tokens.next() != Some(&Token::Symbol('{')) {
+        return Err("Expected '{' after the graph header".to_string());
+    }
+
+    let mut order: Vec<String> = Vec::new();
+    let mut declared: HashMap<String, DotNode> = HashMap::new();
+    let mut edges: Vec<(String, String)> = Vec::new();
+    let mut depth = 1;
+
+    while depth > 0 {
+        let Some(token) = tokens.next() else {
+            return Err("Missing closing '}'".to_string());
+        };
+        match token {
+            Token::Symbol('{') => depth += 1,
+            Token::Symbol('}') => depth -= 1,
+            Token::Symbol(';') | Token::Symbol(',') => {}
+            Token::Id(keyword)
+                if keyword.eq_ignore_ascii_case("subgraph")
+                    && matches!(tokens.peek(), Some(Token::Id(_))) =>
+            {
+                tokens.next();
+            }
+            Token::Id(keyword) if keyword.eq_ignore_ascii_case("subgraph") => {}
+            Token::Id(keyword)
+                if ["node", "edge", "graph"]
+                    .iter()
+                    .any(|k| keyword.eq_ignore_ascii_case(k))
+                    && tokens.peek() == Some(&&Token::Symbol('[')) =>
+            {
+                parse_attributes(&mut tokens)?;
+            }
+            Token::Id(id) => {
+                if tokens.peek() == Some(&&Token::Symbol('=')) {
+                    // Graph attribute such as `rankdir=LR`
+                    tokens.next();
+                    tokens.next();
+                    continue;
+                }
+
+                let mut chain = vec![id.clone()];
+                while tokens.peek() == Some(&&Token::Edge) {
+                    tokens.next();
+                    match tokens.next() {
+                        Some(Token::Id(next)) => chain.push(next.clone()),
+                        _ => return Err(format!("Expected a node after '{} ->'", id)),
+                    }
+                }
+                let attributes = parse_attributes(&mut tokens)?;
+
+                for id in &chain {
+                    declare(&mut declared, &mut order, id);
+                }
+                if let [id] = chain.as_slice() {
+                    let node = declare(&mut declared, &mut order, id);
+                    for (key, value) in attributes {
+                        match key.as_str() {
+                            "label" => node.label = Some(value),
+                            "shape" => node.shape = Some(value),
+                            "pos" => node.pos = parse_pos(&value),
+                            _ => {}
+                        }
+                    }
+                } else {
+                    edges.extend(chain.windows(2).map(|w| (w[0].clone(), w[1].clone())));
+                }
+            }
+            Token::Edge | Token::Symbol(_) => {
+                return Err(format!("Unexpected {:?}", token));
+            }
+        }
+    }
+
+    let ids: HashMap<&str, u32> = order
+        .iter()
+        .enumerate()
+        .map(|(index, id)| (id.as_str(), index as u32))
+        .collect();
+    let unplaced = order
+        .iter()
+        .filter(|id| declared[*id].pos.is_none())
+        .count();
+    let columns = (unplaced as f32).sqrt().ceil().max(1.0) as usize;
+    let mut grid_index = 0;
+
+    let nodes = order
+        .iter()
+        .map(|dot_id| {
+            let node = &declared[dot_id];
+            let position = node.pos.unwrap_or_else(|| {
+                let (row, column) = (grid_index / columns, grid_index % columns);
+                grid_index += 1;
+                Vec2::new(column as f32, -(row as f32)) * IMPORT_GRID_SPACING
+            });
+            let name = match &node.label {
+                Some(label) => label_text(label, dot_id),
+                None => dot_id.clone(),
+            };
+            SkillNodeData {
+                id: ids[dot_id.as_str()],
+                name,
+                description: String::new(),
+                image_name: NodeImages::PLACEHOLDER.to_string(),
+                position,
+                node_type: node_type(node.shape.as_deref().unwrap_or_default()),
+                stats: vec![],
+                cost: 1,
+                tier: 0,
+                z_order: 0.0,
+                created_at: 0,
+                modified_at: 0,
+                ports: vec![],
+            }
+        })
+        .collect();
+    // Self-loops and repeated edges, in either direction, can't be drawn
+    // as separate connections in the editor
+    let mut connections: Vec<ConnectionData> = edges
+        .iter()
+        .filter(|(from, to)| from != to)
+        .map(|(from, to)| ConnectionData {
+            from_id: ids[from.as_str()],
+            to_id: ids[to.as_str()],
+            curve_type: CurveType::default(),
+            enabled: true,
+            from_port: None,
+            to_port: None,
+        })
+        .collect();
+    for index in graph::duplicate_connection_indices(&connections)
+        .into_iter()
+        .rev()
+    {
+        connections.remove(index);
+    }
+
+    Ok(DotGraph { nodes, connections })
+}
+
+/// Looks up a node by its DOT id, adding it on first mention
+fn declare<'a>(
+    declared: &'a mut HashMap<String, DotNode>,
+    order: &mut Vec<String>,
+    id: &str,
+) -> &'a mut DotNode {
+    declared.entry(id.to_string()).or_insert_with(|| {
+        order.push(id.to_string());
+        DotNode::default()
+    })
+}
+
+/// Reverse of `shape`, falling back to Normal for anything else
+fn node_type(shape: &str) -> NodeType {
+    match shape.to_ascii_lowercase().as_str() {
+        "doublecircle" => NodeType::Builtin(BuiltinNodeType::Start),
+        "diamond" | "mdiamond" => NodeType::Builtin(BuiltinNodeType::Keystone),
+        "octagon" | "doubleoctagon" | "tripleoctagon" => {
+            NodeType::Builtin(BuiltinNodeType::Notable)
+        }
+        "hexagon" => NodeType::Builtin(BuiltinNodeType::Mastery),
+        _ => NodeType::default(),
+    }
+}
+
+/// Reads any number of `[key=value, ...]` lists following a statement
+fn parse_attributes<'a>(
+    tokens: &mut std::iter::Peekable<impl Iterator<Item = &'a Token>>,
+) -> Result<Vec<(String, String)>, String> {
+    let mut attributes = Vec::new();
+    while tokens.peek() == Some(&&Token::Symbol('[')) {
+        tokens.next();
+        loop {
+            match tokens.next() {
+                Some(Token::Symbol(']')) => break,
+                Some(Token::Symbol(';')) | Some(Token::Symbol(',')) => {}
+                Some(Token::Id(key)) => {
+                    if tokens.peek() == Some(&&Token::Symbol('=')) {
+                        tokens.next();
+                        match tokens.next() {
+                            Some(Token::Id(value)) => {
+                                attributes.push((key.to_ascii_lowercase(), value.clone()))
+                            }
+                            _ => return Err(format!("Expected a value for '{}'", key)),
+                        }
+                    }
+                }
+                Some(token) => return Err(format!("Unexpected {:?} in attributes", token)),
+                None => return Err("Missing closing ']'".to_string()),
+            }
+        }
+    }
+    Ok(attributes)
+}
+
+/// `"x,y"` in points, optionally pinned with a trailing `!`
+fn parse_pos(value: &str) -> Option<Vec2> {
+    let (x, y) = value.trim().trim_end_matches('!').split_once(',')?;
+    Some(Vec2::new(x.trim().parse().ok()?, y.trim().parse().ok()?))
+}
+
+/// Resolves Graphviz label escapes: `\N` is the node id and line breaks
+/// become spaces, since node names are a single line
+fn label_text(label: &str, dot_id: &str) -> String {
+    let mut text = String::with_capacity(label.len());
+    let mut chars = label.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('N') => text.push_str(dot_id),
+            Some('n') | Some('l') | Some('r') => text.push(' '),
+            Some(other) => text.push(other),
+            None => {}
+        }
+    }
+    text.trim().to_string()
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    let mut line_start = true;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let at_line_start = line_start;
+        line_start = c == '\n' || (line_start && c.is_whitespace());
+
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '#' && at_line_start || c == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '/' && chars.get(i + 1) == Some(&'*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                i += 1;
+            }
+            i += 2;
+        } else if c == '-' && matches!(chars.get(i + 1), Some('>') | Some('-')) {
+            tokens.push(Token::Edge);
+            i += 2;
+        } else if c == '"' {
+            let mut value = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => return Err("Unterminated string".to_string()),
+                    Some('"') => break,
+                    // Quoted strings only escape quotes and line continuations;
+                    // other backslashes are kept for `label_text`
+                    Some('\\') if chars.get(i + 1) == Some(&'"') => {
+                        value.push('"');
+                        i += 1;
+                    }
+                    Some('\\') if chars.get(i + 1) == Some(&'\n') => i += 1,
+                    Some(&c) => value.push(c),
+                }
+                i += 1;
+            }
+            i += 1;
+            tokens.push(Token::Id(value));
+        } else if c == '<' {
+            // HTML label, kept verbatim without the outer brackets
+            let mut depth = 0;
+            let start = i + 1;
+            loop {
+                match chars.get(i) {
+                    None => return Err("Unterminated HTML string".to_string()),
+                    Some('<') => depth += 1,
+                    Some('>') => {
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+                i += 1;
+            }
+            tokens.push(Token::Id(chars[start..i].iter().collect()));
+            i += 1;
+        } else if c.is_alphanumeric() || c == '_' || c == '.' || c == '-' || !c.is_ascii() {
+            let start = i;
+            i += 1;
+            while chars
+                .get(i)
+                .is_some_and(|&c| c.is_alphanumeric() || c == '_' || c == '.' || !c.is_ascii())
+            {
+                i += 1;
+            }
+            tokens.push(Token::Id(chars[start..i].iter().collect()));
+        } else if "{}[];,=".contains(c) {
+            tokens.push(Token::Symbol(c));
+            i += 1;
+        } else {
+            return Err(format!("Unexpected character '{}'", c));
+        }
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edges(graph: &DotGraph) -> Vec<(u32, u32)> {
+        graph
+            .connections
+            .iter()
+            .map(|conn| (conn.from_id, conn.to_id))
+            .collect()
+    }
+
+    #[test]
+    fn parses_nodes_and_edge_chains() {
+        let graph = parse_dot(
+            r#"digraph tree {
+                rankdir=LR;
+                a [label="Start" shape=doublecircle pos="10,20!"];
+                a -> b -> c;
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(graph.nodes.len(), 3);
+        assert_eq!(graph.nodes[0].name, "Start");
+        assert_eq!(graph.nodes[0].position, Vec2::new(10.0, 20.0));
+        assert_eq!(graph.nodes[1].name, "b");
+        assert_eq!(edges(&graph), vec![(0, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn skips_self_loops() {
+        let graph = parse_dot("digraph { a -> a; a -> b; b -> b }").unwrap();
+        assert_eq!(edges(&graph), vec![(0, 1)]);
+    }
+
+    #[test]
+    fn skips_duplicate_and_reversed_edges() {
+        let graph = parse_dot("graph { a -- b; a -- b; b -- a; b -- c }").unwrap();
+        assert_eq!(edges(&graph), vec![(0, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn rejects_malformed_graphs() {
+        assert!(parse_dot("tree { a -> b }").is_err());
+        assert!(parse_dot("digraph { a -> b").is_err());
+        assert!(parse_dot("digraph { a -> ; }").is_err());
+        assert!(parse_dot("digraph { a [label=\"open }").is_err());
+    }
+}
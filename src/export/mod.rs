@@ -0,0 +1,4 @@
+pub mod csv;
+pub mod dot;
+pub mod png;
+pub mod svg;
@@ -0,0 +1,212 @@
+use crate::CANVAS_COLOR;
+use crate::components::*;
+use crate::storage;
+use bevy::prelude::*;
+use bevy::render::camera::RenderTarget;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages};
+use bevy::render::view::screenshot::{Screenshot, ScreenshotCaptured};
+use bevy::window::PrimaryWindow;
+use std::path::Path;
+
+/// Space around the node bounding box when framing every node
+const FIT_ALL_MARGIN: f32 = 60.0;
+const INCHES_PER_METER: f32 = 39.3701;
+
+/// Drives an off-screen PNG export: spawns a camera rendering into an image on
+/// the first frame, captures it on the next, then writes the file once the
+/// capture arrives from the render world. Stitched print pages are pasted into
+/// `PngExportState::stitch` instead, which is written after the last one.
+pub fn run_png_export(
+    mut commands: Commands,
+    mut png_export_state: ResMut<PngExportState>,
+    mut images: ResMut<Assets<Image>>,
+    editor_camera: Res<EditorCamera>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    node_query: Query<&SkillNode>,
+) {
+    if let Some(job) = png_export_state.job.as_mut() {
+        if !job.screenshot_requested {
+            let camera = job.camera;
+            let target = job.target.clone();
+
+            let path = job.path.clone();
+            let dpi = job.settings.dpi;
+            let stitch_tile = job.stitch_tile;
+            commands.spawn(Screenshot::image(target)).observe(
+                move |trigger: Trigger<ScreenshotCaptured>,
+                      mut commands: Commands,
+                      mut png_export_state: ResMut<PngExportState>,
+                      mut toast: ResMut<TimedToast>| {
+                    commands.entity(camera).despawn();
+                    png_export_state.job = None;
+
+                    let image = &trigger.event().0;
+                    let result = match (stitch_tile, png_export_state.stitch.as_mut()) {
+                        (Some(tile), Some(stitch)) => match paste_tile(stitch, tile, image) {
+                            Ok(()) if stitch.remaining > 0 => return,
+                            Ok(()) => png_export_state.stitch.take().map_or(Ok(()), |stitch| {
+                                let size =
+                                    UVec2::new(stitch.columns, stitch.rows) * stitch.tile_size;
+                                encode_png(&stitch.path, size, &stitch.pixels, stitch.dpi)
+                            }),
+                            Err(e) => {
+                                // Later pages have nowhere to go
+                                png_export_state.stitch = None;
+                                png_export_state
+                                    .requests
+                                    .retain(|request| request.stitch_tile.is_none());
+                                Err(e)
+                            }
+                        },
+                        (Some(_), None) => return,
+                        (None, _) => write_png(&path, image, dpi),
+                    };
+                    match result {
+                        Ok(()) => {
+                            info!("Skill tree exported to {}", path.display());
+                            toast.info(format!("Exported {}", path.display()));
+                        }
+                        Err(e) => {
+                            error!("Failed to export PNG to {}: {}", path.display(), e);
+                            toast.error(format!("Failed to export {}: {}", path.display(), e));
+                        }
+                    }
+                },
+            );
+            job.screenshot_requested = true;
+        }
+        return;
+    }
+
+    let Some(request) = png_export_state.requests.pop_front() else {
+        return;
+    };
+
+    let size = Extent3d {
+        width: request.settings.width.max(1),
+        height: request.settings.height.max(1),
+        depth_or_array_layers: 1,
+    };
+    let mut image = Image::new_fill(
+        size,
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.texture_descriptor.usage = TextureUsages::TEXTURE_BINDING
+        | TextureUsages::COPY_SRC
+        | TextureUsages::COPY_DST
+        | TextureUsages::RENDER_ATTACHMENT;
+    let target = images.add(image);
+
+    let output_size = Vec2::new(size.width as f32, size.height as f32);
+    let (center, scale) = if let Some(view) = request.view {
+        view
+    } else if request.settings.fit_all {
+        fit_all_framing(&node_query, output_size)
+    } else {
+        // Keep the world width visible in the window, at the output resolution
+        let window_width = windows.single().map_or(output_size.x, |w| w.width());
+        (
+            editor_camera.pan_offset,
+            editor_camera.zoom * window_width / output_size.x,
+        )
+    };
+
+    let camera = commands
+        .spawn((
+            Camera2d,
+            Camera {
+                target: RenderTarget::Image(target.clone().into()),
+                order: -1,
+                clear_color: ClearColorConfig::Custom(CANVAS_COLOR),
+                ..default()
+            },
+            Transform::from_translation(center.extend(0.0)).with_scale(Vec3::splat(scale)),
+        ))
+        .id();
+
+    png_export_state.job = Some(PngExportJob {
+        path: request.path,
+        settings: request.settings,
+        stitch_tile: request.stitch_tile,
+        camera,
+        target,
+        screenshot_requested: false,
+    });
+}
+
+/// Camera center and scale that fit every node into the output image
+fn fit_all_framing(node_query: &Query<&SkillNode>, output_size: Vec2) -> (Vec2, f32) {
+    let mut positions = node_query.iter().map(|node| node.data.position);
+    let Some(first) = positions.next() else {
+        return (Vec2::ZERO, 1.0);
+    };
+    let (min, max) = positions.fold((first, first), |(min, max), p| (min.min(p), max.max(p)));
+    let min = min - Vec2::splat(FIT_ALL_MARGIN);
+    let max = max + Vec2::splat(FIT_ALL_MARGIN);
+    let extent = max - min;
+    let scale = (extent.x / output_size.x).max(extent.y / output_size.y);
+    ((min + max) * 0.5, scale)
+}
+
+/// Copies the printable area of a rendered page into its place in the
+/// stitched image
+fn paste_tile(
+    stitch: &mut StitchedPng,
+    tile: UVec2,
+    image: &Image,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let rgba = image.clone().try_into_dynamic()?.to_rgba8();
+    let page_size = stitch.tile_size + UVec2::splat(2 * stitch.margin);
+    if rgba.width() < page_size.x || rgba.height() < page_size.y {
+        return Err(format!("page rendered at {}×{}", rgba.width(), rgba.height()).into());
+    }
+
+    let row_bytes = stitch.tile_size.x as usize * 4;
+    let stitched_width = (stitch.columns * stitch.tile_size.x) as usize;
+    for y in 0..stitch.tile_size.y {
+        let source = ((y + stitch.margin) * rgba.width() + stitch.margin) as usize * 4;
+        let target_y = (tile.y * stitch.tile_size.y + y) as usize;
+        let target = (target_y * stitched_width + (tile.x * stitch.tile_size.x) as usize) * 4;
+        stitch.pixels[target..target + row_bytes]
+            .copy_from_slice(&rgba.as_raw()[source..source + row_bytes]);
+    }
+    stitch.remaining = stitch.remaining.saturating_sub(1);
+    Ok(())
+}
+
+fn write_png(path: &Path, image: &Image, dpi: u32) -> Result<(), Box<dyn std::error::Error>> {
+    let rgba = image.clone().try_into_dynamic()?.to_rgba8();
+    encode_png(
+        path,
+        UVec2::new(rgba.width(), rgba.height()),
+        rgba.as_raw(),
+        dpi,
+    )
+}
+
+/// Writes RGBA pixels to `path` as a PNG tagged with the given DPI
+fn encode_png(
+    path: &Path,
+    size: UVec2,
+    pixels: &[u8],
+    dpi: u32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, size.x, size.y);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let pixels_per_meter = (dpi as f32 * INCHES_PER_METER).round() as u32;
+    encoder.set_pixel_dims(Some(png::PixelDimensions {
+        xppu: pixels_per_meter,
+        yppu: pixels_per_meter,
+        unit: png::Unit::Meter,
+    }));
+    encoder.write_header()?.write_image_data(pixels)?;
+    storage::write(path, bytes)?;
+    storage::offer_download(path);
+    Ok(())
+}
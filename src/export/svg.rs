@@ -0,0 +1,178 @@
+use crate::components::*;
+use crate::storage;
+use crate::{arc_angle_range, calculate_arc_center, curve_points};
+use bevy::prelude::*;
+use std::fmt::Write;
+
+const NODE_RADIUS: f32 = 30.0;
+/// Space around the node bounding box, large enough to fit the name labels
+const MARGIN: f32 = 60.0;
+
+pub fn export_svg(
+    path: &str,
+    nodes: &[SkillNodeData],
+    connections: &[ConnectionData],
+) -> std::io::Result<()> {
+    storage::write(path, skill_tree_to_svg(nodes, connections))?;
+    info!("Skill tree exported to {}", path);
+    Ok(())
+}
+
+/// Renders the tree as a standalone SVG document. World space is y-up while
+/// SVG is y-down, so every y coordinate is flipped.
+pub fn skill_tree_to_svg(nodes: &[SkillNodeData], connections: &[ConnectionData]) -> String {
+    let (min, max) = if nodes.is_empty() {
+        (Vec2::ZERO, Vec2::ZERO)
+    } else {
+        nodes.iter().fold(
+            (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+            |(min, max), node| (min.min(node.position), max.max(node.position)),
+        )
+    };
+    let min = min - Vec2::splat(NODE_RADIUS + MARGIN);
+    let max = max + Vec2::splat(NODE_RADIUS + MARGIN);
+    let size = max - min;
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{:.0}" height="{:.0}" viewBox="{:.2} {:.2} {:.2} {:.2}">"#,
+        size.x, size.y, min.x, -max.y, size.x, size.y
+    );
+    let _ = writeln!(
+        svg,
+        r#"  <rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" fill="rgb(26,26,38)"/>"#,
+        min.x, -max.y, size.x, size.y
+    );
+
+    let _ = writeln!(
+        svg,
+        r#"  <g fill="none" stroke="rgb(179,153,102)" stroke-width="2">"#
+    );
+    for connection in connections {
+        let from = nodes.iter().find(|n| n.id == connection.from_id);
+        let to = nodes.iter().find(|n| n.id == connection.to_id);
+        if let (Some(from), Some(to)) = (from, to) {
+            let _ = writeln!(
+                svg,
+                "    {}",
+                connection_path(
+                    from.position + from.port_offset(connection.from_port),
+                    to.position + to.port_offset(connection.to_port),
+                    &connection.curve_type
+                )
+            );
+        }
+    }
+    let _ = writeln!(svg, "  </g>");
+
+    let _ = writeln!(
+        svg,
+        r#"  <g font-family="sans-serif" font-size="12" text-anchor="middle">"#
+    );
+    for node in nodes {
+        let (x, y) = (node.position.x, -node.position.y);
+        let _ = writeln!(
+            svg,
+            r#"    <circle cx="{:.2}" cy="{:.2}" r="{:.2}" fill="{}" stroke="rgb(20,20,20)" stroke-width="2"/>"#,
+            x,
+            y,
+            NODE_RADIUS,
+            node_type_fill(&node.node_type)
+        );
+        let _ = writeln!(
+            svg,
+            r#"    <text x="{:.2}" y="{:.2}" fill="white">{}</text>"#,
+            x,
+            y + NODE_RADIUS + 16.0,
+            escape_xml(&node.name)
+        );
+    }
+    let _ = writeln!(svg, "  </g>");
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn connection_path(from: Vec2, to: Vec2, curve_type: &CurveType) -> String {
+    let straight = format!(
+        r#"<path d="M {:.2} {:.2} L {:.2} {:.2}""#,
+        from.x, -from.y, to.x, -to.y
+    );
+    match curve_type {
+        CurveType::Straight => format!("{straight}/>"),
+        CurveType::Arc {
+            radius, clockwise, ..
+        } => {
+            match calculate_arc_center(from, to, *radius, *clockwise) {
+                Some((_, start_angle, end_angle)) => {
+                    let angle_range = arc_angle_range(start_angle, end_angle, *clockwise);
+                    let large_arc = angle_range > std::f32::consts::PI;
+                    // SVG's positive sweep direction is clockwise on screen,
+                    // matching a clockwise arc in the editor
+                    format!(
+                        r#"<path d="M {:.2} {:.2} A {:.2} {:.2} 0 {} {} {:.2} {:.2}"/>"#,
+                        from.x,
+                        -from.y,
+                        radius,
+                        radius,
+                        large_arc as u8,
+                        *clockwise as u8,
+                        to.x,
+                        -to.y
+                    )
+                }
+                // Invalid arcs are drawn dashed, matching the editor
+                None => format!(r#"{straight} stroke-dasharray="10 5"/>"#),
+            }
+        }
+        CurveType::CatmullRom { waypoints } => {
+            // Each Catmull-Rom span is exactly a cubic Bézier with these
+            // control points
+            let points = curve_points(from, to, waypoints);
+            let last = points.len() - 1;
+            let mut path = format!(r#"<path d="M {:.2} {:.2}"#, from.x, -from.y);
+            for span in 0..last {
+                let p0 = points[span.saturating_sub(1)];
+                let p1 = points[span];
+                let p2 = points[span + 1];
+                let p3 = points[(span + 2).min(last)];
+                let c1 = p1 + (p2 - p0) / 6.0;
+                let c2 = p2 - (p3 - p1) / 6.0;
+                let _ = write!(
+                    path,
+                    " C {:.2} {:.2} {:.2} {:.2} {:.2} {:.2}",
+                    c1.x, -c1.y, c2.x, -c2.y, p2.x, -p2.y
+                );
+            }
+            path.push_str(r#""/>"#);
+            path
+        }
+    }
+}
+
+fn node_type_fill(node_type: &NodeType) -> &'static str {
+    match node_type {
+        NodeType::Builtin(BuiltinNodeType::Normal) => "rgb(200,200,200)",
+        NodeType::Builtin(BuiltinNodeType::Notable) => "rgb(230,190,80)",
+        NodeType::Builtin(BuiltinNodeType::Keystone) => "rgb(200,80,80)",
+        NodeType::Builtin(BuiltinNodeType::Start) => "rgb(80,200,220)",
+        NodeType::Builtin(BuiltinNodeType::Mastery) => "rgb(77,204,191)",
+        NodeType::Builtin(BuiltinNodeType::Ascendancy) => "rgb(166,102,230)",
+        NodeType::Builtin(BuiltinNodeType::Socket) | NodeType::Custom(_) => "rgb(140,140,140)",
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
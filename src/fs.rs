@@ -1,11 +1,133 @@
-use std::fs;
-use bevy::prelude::*;
 use crate::components::*;
+use crate::storage;
+use bevy::prelude::*;
+use bevy_skill_tree::{BINARY_EXTENSION, LenientLoadResult, first_start_node_id, migration};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub use bevy_skill_tree::SaveFormat;
+
+/// File extensions the editor can open as skill trees
+pub const SKILL_TREE_EXTENSIONS: [&str; 2] = ["ron", "json"];
+
+pub fn is_skill_tree_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| SKILL_TREE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// File extensions loaded as node images
+pub const IMAGE_EXTENSIONS: [&str; 3] = ["png", "jpg", "jpeg"];
+
+pub fn is_image_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// Names of the image files directly inside `dir`
+pub fn list_image_files(dir: &Path) -> Vec<String> {
+    storage::list_files(dir)
+        .into_iter()
+        .filter(|path| is_image_file(path))
+        .filter_map(|path| Some(path.file_name()?.to_str()?.to_string()))
+        .collect()
+}
+
+/// Copies an image into `dir` so the asset server can load it by file name,
+/// returning that name. Files already in `dir` are used as they are.
+pub fn import_image(path: &Path, dir: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or("Image path has no valid file name")?
+        .to_string();
+    let destination = dir.join(&file_name);
+
+    if storage::exists(&destination) {
+        if !storage::same_file(&destination, path)? {
+            return Err(format!(
+                "A different '{}' already exists in {}",
+                file_name,
+                dir.display()
+            )
+            .into());
+        }
+    } else {
+        storage::copy(path, &destination)?;
+        info!("Copied {} to {}", path.display(), destination.display());
+    }
+    Ok(file_name)
+}
+
+/// Backups kept beside a saved file at most: `.bak`, then `.bak.1` to `.bak.5`
+pub const MAX_BACKUPS: u32 = 6;
+
+/// Backup slot `index` of `path`, 0 being the newest
+pub fn backup_path(path: &Path, index: u32) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    if index == 0 {
+        name.push(".bak");
+    } else {
+        name.push(format!(".bak.{}", index));
+    }
+    PathBuf::from(name)
+}
+
+/// Backups of `path` that exist on disk, newest first
+pub fn list_backups(path: &Path) -> Vec<PathBuf> {
+    (0..MAX_BACKUPS)
+        .map(|index| backup_path(path, index))
+        .filter(|backup| storage::exists(backup))
+        .collect()
+}
+
+/// `path` without a trailing `.bak` or `.bak.N`, so backups load in the
+/// format of the file they were copied from
+fn without_backup_suffix(path: &str) -> &str {
+    let without_index = path
+        .rsplit_once('.')
+        .filter(|(_, index)| !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit()))
+        .map_or(path, |(rest, _)| rest);
+    without_index.strip_suffix(".bak").unwrap_or(path)
+}
+
+/// Copies `path` into the newest backup slot before it's overwritten,
+/// shifting older backups along and dropping any past `max_backups`
+pub fn rotate_backups(path: &Path, max_backups: u32) -> std::io::Result<()> {
+    let max_backups = max_backups.min(MAX_BACKUPS);
+    if !storage::exists(path) {
+        return Ok(());
+    }
+    // The oldest kept slot is freed for the shift, along with any left over
+    // from a higher `max_backups`
+    for index in max_backups.saturating_sub(1)..MAX_BACKUPS {
+        let backup = backup_path(path, index);
+        if storage::exists(&backup) {
+            storage::remove_file(&backup)?;
+        }
+    }
+    if max_backups == 0 {
+        return Ok(());
+    }
+    for index in (0..max_backups - 1).rev() {
+        let backup = backup_path(path, index);
+        if storage::exists(&backup) {
+            storage::rename(&backup, backup_path(path, index + 1))?;
+        }
+    }
+    storage::copy(path, backup_path(path, 0))?;
+    Ok(())
+}
 
+/// Saves the tree as RON or JSON by the path's extension, first backing up
+/// the file it replaces
 pub fn save_skill_tree(
     path: &str,
     skill_tree_data: &SkillTreeData,
     node_query: &Query<&mut SkillNode>,
+    saved_camera_state: Option<(Vec2, f32)>,
+    max_backups: u32,
 ) {
     let mut nodes = Vec::new();
     for node in node_query.iter() {
@@ -13,29 +135,277 @@ pub fn save_skill_tree(
     }
 
     let save_data = SkillTreeSaveData {
+        version: migration::CURRENT_VERSION,
+        start_node_id: first_start_node_id(&nodes),
         nodes,
         connections: skill_tree_data.connections.clone(),
-        start_node_id: None,
+        tree_name: skill_tree_data.tree_name.clone(),
+        tree_description: skill_tree_data.tree_description.clone(),
+        saved_camera_state,
     };
 
-    let ron_string = ron::ser::to_string_pretty(&save_data, Default::default()).unwrap();
     if path.is_empty() {
         warn!("Attempted to save with an empty path. Save operation cancelled.");
         return;
     }
-    if let Err(e) = fs::write(path, ron_string) {
+    // Written in the format `load_skill_tree` will read it back with
+    let format = SaveFormat::from_path(Path::new(path)).unwrap_or(SaveFormat::Ron);
+    if let Err(e) = rotate_backups(Path::new(path), max_backups) {
+        // Still save, losing the backup is better than losing the edits
+        warn!("Failed to back up {}: {}", path, e);
+    }
+    if let Err(e) = write_skill_tree(path, &save_data, format) {
         error!("Failed to save skill tree to {}: {}", path, e);
     } else {
-        info!("Skill tree saved to {}", path);
+        storage::offer_download(path);
+    }
+}
+
+pub fn write_skill_tree(
+    path: &str,
+    save_data: &SkillTreeSaveData,
+    format: SaveFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if path.is_empty() {
+        return Err("Save path is empty".into());
+    }
+    storage::write(
+        path,
+        bevy_skill_tree::serialize_skill_tree(save_data, format)?,
+    )?;
+    info!("Skill tree saved to {}", path);
+    Ok(())
+}
+
+/// Writes the game-ready binary format
+pub fn write_skill_tree_binary(
+    path: &str,
+    save_data: &SkillTreeSaveData,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if path.is_empty() {
+        return Err("Save path is empty".into());
     }
+    storage::write(
+        path,
+        bevy_skill_tree::serialize_skill_tree_binary(save_data)?,
+    )?;
+    info!("Skill tree exported to {}", path);
+    Ok(())
 }
 
 pub fn load_skill_tree(path: &str) -> Result<SkillTreeSaveData, Box<dyn std::error::Error>> {
     if path.is_empty() {
         return Err("Load path is empty".into());
     }
-    let contents = fs::read_to_string(path)?;
-    let save_data: SkillTreeSaveData = ron::from_str(&contents)?;
+    let path_ref = Path::new(without_backup_suffix(path));
+    let is_binary = path_ref
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(BINARY_EXTENSION));
+    let save_data = if is_binary {
+        bevy_skill_tree::parse_skill_tree_binary(&storage::read(path)?)?
+    } else {
+        let format = SaveFormat::from_path(path_ref).unwrap_or(SaveFormat::Ron);
+        bevy_skill_tree::parse_skill_tree(&storage::read_to_string(path)?, format)?
+    };
     info!("Skill tree loaded from {}", path);
     Ok(save_data)
 }
+
+/// Loads a RON or JSON skill tree, falling back to
+/// `bevy_skill_tree::recover_skill_tree` when the file is malformed
+pub fn load_skill_tree_lenient(
+    path: &str,
+) -> Result<LenientLoadResult, Box<dyn std::error::Error>> {
+    match load_skill_tree(path) {
+        Ok(data) => {
+            return Ok(LenientLoadResult {
+                data,
+                warnings: Vec::new(),
+            });
+        }
+        Err(e) => warn!("Recovering entries from {} after: {}", path, e),
+    }
+
+    let contents = storage::read_to_string(path)?;
+    let format = SaveFormat::from_path(Path::new(path)).unwrap_or(SaveFormat::Ron);
+    let result = bevy_skill_tree::recover_skill_tree(&contents, format)?;
+    info!(
+        "Recovered {} nodes and {} connections from {}",
+        result.data.nodes.len(),
+        result.data.connections.len(),
+        path
+    );
+    Ok(result)
+}
+
+pub fn load_node_type_styles(
+    path: &Path,
+) -> Result<HashMap<String, NodeTypeStyle>, Box<dyn std::error::Error>> {
+    let contents = storage::read_to_string(path)?;
+    let styles = ron::from_str(&contents)?;
+    info!("Node type styles loaded from {}", path.display());
+    Ok(styles)
+}
+
+pub fn load_templates(path: &Path) -> Result<Vec<SkillNodeTemplate>, Box<dyn std::error::Error>> {
+    let contents = storage::read_to_string(path)?;
+    let templates = ron::from_str(&contents)?;
+    info!("Node templates loaded from {}", path.display());
+    Ok(templates)
+}
+
+pub fn save_templates(
+    path: &Path,
+    templates: &[SkillNodeTemplate],
+) -> Result<(), Box<dyn std::error::Error>> {
+    storage::write(
+        path,
+        ron::ser::to_string_pretty(templates, Default::default())?,
+    )?;
+    Ok(())
+}
+
+pub fn load_editor_config(path: &Path) -> Result<EditorConfig, Box<dyn std::error::Error>> {
+    let contents = storage::read_to_string(path)?;
+    Ok(ron::from_str(&contents)?)
+}
+
+pub fn save_editor_config(
+    path: &Path,
+    config: &EditorConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    storage::write(
+        path,
+        ron::ser::to_string_pretty(config, Default::default())?,
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    /// A small tree as an older editor wrote it
+    const TREE: &str = r#"(
+        nodes: [
+            (
+                id: 0,
+                name: "Origin",
+                description: "",
+                image_name: "",
+                position: (0.0, 0.0),
+                node_type: Start,
+                stats: [],
+            ),
+            (
+                id: 1,
+                name: "Might",
+                description: "",
+                image_name: "",
+                position: (100.0, 0.0),
+                node_type: Normal,
+                stats: [(stat_name: "Strength", value: 12.5, modifier_type: Flat)],
+            ),
+            (
+                id: 2,
+                name: "Focus",
+                description: "",
+                image_name: "",
+                position: (0.0, 100.0),
+                node_type: Notable,
+                stats: [(stat_name: "Mana", value: 5.0, modifier_type: Percentage)],
+            ),
+        ],
+        connections: [(from_id: 0, to_id: 1), (from_id: 0, to_id: 2)],
+    )"#;
+
+    /// Unique file in the temp directory, removed again when dropped
+    struct TempFile(String);
+
+    impl TempFile {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "skill_tree_editor_{}_{}",
+                std::process::id(),
+                name
+            ));
+            Self(path.to_string_lossy().into_owned())
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = storage::remove_file(&self.0);
+        }
+    }
+
+    /// Spawns `save_data` the way the editor does and saves it with
+    /// `save_skill_tree`
+    fn save_through_editor(save_data: &SkillTreeSaveData, path: &str) {
+        let mut world = World::new();
+        let mut skill_tree_data = SkillTreeData::default();
+        for node_data in &save_data.nodes {
+            let entity = world
+                .spawn(SkillNode {
+                    id: node_data.id,
+                    data: node_data.clone(),
+                })
+                .id();
+            skill_tree_data.nodes.insert(node_data.id, entity);
+        }
+        skill_tree_data.connections = save_data.connections.clone();
+        world.insert_resource(skill_tree_data);
+
+        let path = path.to_string();
+        world
+            .run_system_once(
+                move |skill_tree_data: Res<SkillTreeData>, node_query: Query<&mut SkillNode>| {
+                    save_skill_tree(&path, &skill_tree_data, &node_query, None, 0);
+                },
+            )
+            .unwrap();
+    }
+
+    fn assert_round_trip(file_name: &str) -> String {
+        let source = TempFile::new("source.ron");
+        storage::write(&source.0, TREE).unwrap();
+        let original = load_skill_tree(&source.0).unwrap();
+
+        let saved = TempFile::new(file_name);
+        save_through_editor(&original, &saved.0);
+        let loaded = load_skill_tree(&saved.0).unwrap();
+
+        assert_eq!(loaded.nodes.len(), 3);
+        assert_eq!(loaded.connections.len(), 2);
+        let might = loaded.nodes.iter().find(|node| node.id == 1).unwrap();
+        assert_eq!(might.stats[0].stat_name, "Strength");
+        assert_eq!(might.stats[0].value, 12.5);
+        storage::read_to_string(&saved.0).unwrap()
+    }
+
+    #[test]
+    fn json_save_round_trip() {
+        let contents = assert_round_trip("tree.json");
+        assert!(serde_json::from_str::<serde_json::Value>(&contents).is_ok());
+    }
+
+    #[test]
+    fn ron_save_round_trip() {
+        let contents = assert_round_trip("tree.ron");
+        assert!(serde_json::from_str::<serde_json::Value>(&contents).is_err());
+    }
+
+    #[test]
+    fn format_from_path() {
+        assert_eq!(
+            SaveFormat::from_path(Path::new("tree.json")),
+            Some(SaveFormat::Json)
+        );
+        assert_eq!(
+            SaveFormat::from_path(Path::new("tree.RON")),
+            Some(SaveFormat::Ron)
+        );
+        assert_eq!(SaveFormat::from_path(Path::new("tree.png")), None);
+    }
+}
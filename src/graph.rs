@@ -0,0 +1,531 @@
+use crate::components::*;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Neighbours of every node, treating connections as two-way. Disabled
+/// connections are skipped here and in the other adjacency maps, so nothing
+/// built on them follows one.
+pub fn undirected_adjacency(connections: &[ConnectionData]) -> HashMap<u32, Vec<u32>> {
+    let mut adjacency: HashMap<u32, Vec<u32>> = HashMap::new();
+    for connection in connections.iter().filter(|connection| connection.enabled) {
+        adjacency
+            .entry(connection.from_id)
+            .or_default()
+            .push(connection.to_id);
+        adjacency
+            .entry(connection.to_id)
+            .or_default()
+            .push(connection.from_id);
+    }
+    adjacency
+}
+
+/// Every node reachable from any of `start_ids` by breadth-first search,
+/// including the start nodes themselves
+pub fn reachable_from(
+    start_ids: impl IntoIterator<Item = u32>,
+    adjacency: &HashMap<u32, Vec<u32>>,
+) -> HashSet<u32> {
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::new();
+    for id in start_ids {
+        if visited.insert(id) {
+            queue.push_back(id);
+        }
+    }
+
+    while let Some(id) = queue.pop_front() {
+        for &neighbour in adjacency.get(&id).into_iter().flatten() {
+            if visited.insert(neighbour) {
+                queue.push_back(neighbour);
+            }
+        }
+    }
+    visited
+}
+
+/// Most connections on any directed path from one of `start_ids`, by
+/// dynamic programming over the reachable nodes in topological order.
+/// `None` when a cycle is reachable, since paths could then be endless.
+pub fn longest_path_from(
+    start_ids: impl IntoIterator<Item = u32>,
+    adjacency: &HashMap<u32, Vec<u32>>,
+) -> Option<usize> {
+    let start_ids: Vec<u32> = start_ids.into_iter().collect();
+    let reachable = reachable_from(start_ids.iter().copied(), adjacency);
+    let mut in_degree: HashMap<u32, usize> = reachable.iter().map(|&id| (id, 0)).collect();
+    for id in &reachable {
+        for neighbour in adjacency.get(id).into_iter().flatten() {
+            *in_degree.get_mut(neighbour).unwrap() += 1;
+        }
+    }
+
+    // Kahn's algorithm, every reachable node's longest distance is final
+    // once all of its predecessors have been visited
+    let mut lengths: HashMap<u32, usize> = start_ids.iter().map(|&id| (id, 0)).collect();
+    let mut queue: VecDeque<u32> = in_degree
+        .iter()
+        .filter(|&(_, &degree)| degree == 0)
+        .map(|(&id, _)| id)
+        .collect();
+    let mut visited = 0;
+    while let Some(id) = queue.pop_front() {
+        visited += 1;
+        let length = lengths.get(&id).copied().unwrap_or(0) + 1;
+        for &neighbour in adjacency.get(&id).into_iter().flatten() {
+            let longest = lengths.entry(neighbour).or_default();
+            *longest = (*longest).max(length);
+            let degree = in_degree.get_mut(&neighbour).unwrap();
+            *degree -= 1;
+            if *degree == 0 {
+                queue.push_back(neighbour);
+            }
+        }
+    }
+
+    (visited == reachable.len()).then(|| lengths.into_values().max().unwrap_or(0))
+}
+
+/// Number of groups of nodes joined to each other by connections, counting
+/// every isolated node as its own group
+pub fn connected_components(node_ids: &[u32], adjacency: &HashMap<u32, Vec<u32>>) -> usize {
+    let mut visited: HashSet<u32> = HashSet::with_capacity(node_ids.len());
+    let mut components = 0;
+    for &id in node_ids {
+        if !visited.contains(&id) {
+            visited.extend(reachable_from([id], adjacency));
+            components += 1;
+        }
+    }
+    components
+}
+
+/// Betweenness centrality of every node: how many shortest paths between
+/// other nodes pass through it. Estimated with Brandes' algorithm from up to
+/// `samples` evenly spread source nodes rather than all of them, and scaled
+/// so the most central node is 1.
+pub fn approximate_betweenness(
+    node_ids: &[u32],
+    adjacency: &HashMap<u32, Vec<u32>>,
+    samples: usize,
+) -> HashMap<u32, f32> {
+    let mut ids = node_ids.to_vec();
+    ids.sort_unstable();
+    let index: HashMap<u32, usize> = ids.iter().enumerate().map(|(i, &id)| (id, i)).collect();
+    // Duplicate connections would count the same path twice
+    let neighbours: Vec<Vec<usize>> = ids
+        .iter()
+        .map(|id| {
+            let mut neighbours: Vec<usize> = adjacency
+                .get(id)
+                .into_iter()
+                .flatten()
+                .filter_map(|neighbour| index.get(neighbour).copied())
+                .collect();
+            neighbours.sort_unstable();
+            neighbours.dedup();
+            neighbours
+        })
+        .collect();
+
+    let count = ids.len();
+    let mut centrality = vec![0.0f32; count];
+    let step = count.div_ceil(samples.max(1)).max(1);
+    for source in (0..count).step_by(step) {
+        // Breadth-first search counting shortest paths to every node
+        let mut order = Vec::with_capacity(count);
+        let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); count];
+        let mut path_counts = vec![0.0f32; count];
+        let mut distances: Vec<Option<usize>> = vec![None; count];
+        path_counts[source] = 1.0;
+        distances[source] = Some(0);
+        let mut queue = VecDeque::from([source]);
+        while let Some(node) = queue.pop_front() {
+            order.push(node);
+            let next = distances[node].unwrap_or_default() + 1;
+            for &neighbour in &neighbours[node] {
+                if distances[neighbour].is_none() {
+                    distances[neighbour] = Some(next);
+                    queue.push_back(neighbour);
+                }
+                if distances[neighbour] == Some(next) {
+                    path_counts[neighbour] += path_counts[node];
+                    predecessors[neighbour].push(node);
+                }
+            }
+        }
+
+        // Walk back from the farthest nodes, accumulating each node's share
+        // of the paths through it
+        let mut dependency = vec![0.0f32; count];
+        for &node in order.iter().rev() {
+            for &predecessor in &predecessors[node] {
+                dependency[predecessor] +=
+                    path_counts[predecessor] / path_counts[node] * (1.0 + dependency[node]);
+            }
+            if node != source {
+                centrality[node] += dependency[node];
+            }
+        }
+    }
+
+    let max = centrality.iter().copied().fold(0.0, f32::max);
+    ids.into_iter()
+        .zip(centrality)
+        .map(|(id, value)| (id, if max > 0.0 { value / max } else { 0.0 }))
+        .collect()
+}
+
+/// Number of paths from any of `start_ids` to `target` that visit no node
+/// twice and don't pass through another start, counted by depth-first
+/// search. Stops at `limit`.
+pub fn count_simple_paths(
+    start_ids: &[u32],
+    target: u32,
+    adjacency: &HashMap<u32, Vec<u32>>,
+    limit: usize,
+) -> usize {
+    let mut paths = 0;
+
+    for &start in start_ids {
+        let mut path: Vec<u32> = vec![start];
+        let mut next_edge: Vec<usize> = vec![0];
+        let mut on_path: HashSet<u32> = HashSet::from([start]);
+
+        while let Some(&id) = path.last() {
+            if paths >= limit {
+                return paths;
+            }
+            let edge = next_edge.last_mut().unwrap();
+            let neighbour = adjacency.get(&id).and_then(|n| n.get(*edge)).copied();
+            *edge += 1;
+
+            match neighbour {
+                Some(neighbour) if neighbour == target => paths += 1,
+                Some(neighbour)
+                    if on_path.contains(&neighbour) || start_ids.contains(&neighbour) => {}
+                Some(neighbour) => {
+                    on_path.insert(neighbour);
+                    path.push(neighbour);
+                    next_edge.push(0);
+                }
+                None => {
+                    on_path.remove(&id);
+                    path.pop();
+                    next_edge.pop();
+                }
+            }
+        }
+    }
+    paths
+}
+
+/// The `count` fewest-hop paths from any of `start_ids` to `target`, with
+/// the same rules as `count_simple_paths`, shortest first. Uses Yen's
+/// algorithm, so the work grows with `count` rather than with the number of
+/// paths in the tree.
+pub fn shortest_simple_paths(
+    start_ids: &[u32],
+    target: u32,
+    adjacency: &HashMap<u32, Vec<u32>>,
+    count: usize,
+) -> Vec<Vec<u32>> {
+    let no_edges = HashSet::new();
+    let Some(shortest) = spur_path(start_ids, target, adjacency, start_ids, &[], &no_edges) else {
+        return Vec::new();
+    };
+    let mut paths = vec![shortest];
+    let mut candidates: Vec<Vec<u32>> = Vec::new();
+
+    while paths.len() < count {
+        let previous = paths.last().unwrap().clone();
+        // Deviate from the previous path after each prefix of it, the empty
+        // prefix meaning a different Start node
+        for root_len in 0..previous.len() {
+            let root = &previous[..root_len];
+            let blocked_edges: HashSet<(u32, u32)> = paths
+                .iter()
+                .filter(|path| path.len() > root_len && path.starts_with(root))
+                .map(|path| match root_len {
+                    0 => (u32::MAX, path[0]),
+                    _ => (path[root_len - 1], path[root_len]),
+                })
+                .collect();
+            let (sources, blocked_nodes) = match root.split_last() {
+                Some((&spur, before)) => (vec![spur], before),
+                None => (
+                    start_ids
+                        .iter()
+                        .copied()
+                        .filter(|start| !blocked_edges.contains(&(u32::MAX, *start)))
+                        .collect(),
+                    root,
+                ),
+            };
+            let Some(spur) = spur_path(
+                &sources,
+                target,
+                adjacency,
+                start_ids,
+                blocked_nodes,
+                &blocked_edges,
+            ) else {
+                continue;
+            };
+            let mut candidate = root[..root_len.saturating_sub(1)].to_vec();
+            candidate.extend(spur);
+            if !paths.contains(&candidate) && !candidates.contains(&candidate) {
+                candidates.push(candidate);
+            }
+        }
+
+        let Some(index) = (0..candidates.len()).min_by_key(|&index| candidates[index].len()) else {
+            break;
+        };
+        paths.push(candidates.remove(index));
+    }
+    paths
+}
+
+/// Fewest-hop path from one of `sources` to `target` by breadth-first
+/// search, avoiding `blocked_nodes`, `blocked_edges` and every start other
+/// than the source
+fn spur_path(
+    sources: &[u32],
+    target: u32,
+    adjacency: &HashMap<u32, Vec<u32>>,
+    start_ids: &[u32],
+    blocked_nodes: &[u32],
+    blocked_edges: &HashSet<(u32, u32)>,
+) -> Option<Vec<u32>> {
+    let mut previous: HashMap<u32, Option<u32>> = HashMap::new();
+    let mut queue = VecDeque::new();
+    for &source in sources {
+        if !blocked_nodes.contains(&source) && previous.insert(source, None).is_none() {
+            queue.push_back(source);
+        }
+    }
+
+    while let Some(id) = queue.pop_front() {
+        if id == target {
+            let mut path = vec![id];
+            while let Some(&Some(before)) = previous.get(path.last().unwrap()) {
+                path.push(before);
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for &neighbour in adjacency.get(&id).into_iter().flatten() {
+            let blocked = blocked_nodes.contains(&neighbour)
+                || blocked_edges.contains(&(id, neighbour))
+                || (neighbour != target && start_ids.contains(&neighbour))
+                || previous.contains_key(&neighbour);
+            if !blocked {
+                previous.insert(neighbour, Some(id));
+                queue.push_back(neighbour);
+            }
+        }
+    }
+    None
+}
+
+/// Outgoing neighbours of every node, following connection direction
+pub fn directed_adjacency(connections: &[ConnectionData]) -> HashMap<u32, Vec<u32>> {
+    let mut adjacency: HashMap<u32, Vec<u32>> = HashMap::new();
+    for connection in connections.iter().filter(|connection| connection.enabled) {
+        adjacency
+            .entry(connection.from_id)
+            .or_default()
+            .push(connection.to_id);
+    }
+    adjacency
+}
+
+/// Incoming neighbours of every node, against connection direction
+pub fn reverse_adjacency(connections: &[ConnectionData]) -> HashMap<u32, Vec<u32>> {
+    let mut adjacency: HashMap<u32, Vec<u32>> = HashMap::new();
+    for connection in connections.iter().filter(|connection| connection.enabled) {
+        adjacency
+            .entry(connection.to_id)
+            .or_default()
+            .push(connection.from_id);
+    }
+    adjacency
+}
+
+/// Cycles found as back-edges of a depth-first search over the directed
+/// graph. Each cycle lists its nodes in order, without repeating the first.
+pub fn find_cycles(node_ids: &[u32], adjacency: &HashMap<u32, Vec<u32>>) -> Vec<Vec<u32>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Visit {
+        InProgress,
+        Done,
+    }
+
+    let mut sorted_ids = node_ids.to_vec();
+    sorted_ids.sort_unstable();
+
+    let mut state: HashMap<u32, Visit> = HashMap::new();
+    let mut cycles = Vec::new();
+
+    for &root in &sorted_ids {
+        if state.contains_key(&root) {
+            continue;
+        }
+
+        // Iterative DFS so deep trees can't overflow the stack
+        let mut path: Vec<u32> = vec![root];
+        let mut next_edge: Vec<usize> = vec![0];
+        state.insert(root, Visit::InProgress);
+
+        while let Some(&id) = path.last() {
+            let edge = next_edge.last_mut().unwrap();
+            let neighbour = adjacency.get(&id).and_then(|n| n.get(*edge)).copied();
+            *edge += 1;
+
+            match neighbour {
+                Some(neighbour) => match state.get(&neighbour) {
+                    None => {
+                        state.insert(neighbour, Visit::InProgress);
+                        path.push(neighbour);
+                        next_edge.push(0);
+                    }
+                    Some(Visit::InProgress) => {
+                        let start = path.iter().position(|&n| n == neighbour).unwrap();
+                        cycles.push(path[start..].to_vec());
+                    }
+                    Some(Visit::Done) => {}
+                },
+                None => {
+                    state.insert(id, Visit::Done);
+                    path.pop();
+                    next_edge.pop();
+                }
+            }
+        }
+    }
+    cycles
+}
+
+/// The two nodes a connection joins, smallest id first, so a connection and
+/// its reverse give the same pair
+pub fn node_pair(connection: &ConnectionData) -> (u32, u32) {
+    let (from_id, to_id) = (connection.from_id, connection.to_id);
+    (from_id.min(to_id), from_id.max(to_id))
+}
+
+/// Indices of connections joining a pair of nodes that an earlier connection
+/// already joins, in either direction
+pub fn duplicate_connection_indices(connections: &[ConnectionData]) -> Vec<usize> {
+    let mut seen: HashSet<(u32, u32)> = HashSet::with_capacity(connections.len());
+    connections
+        .iter()
+        .enumerate()
+        .filter(|(_, conn)| !seen.insert(node_pair(conn)))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connection(from_id: u32, to_id: u32) -> ConnectionData {
+        ConnectionData {
+            from_id,
+            to_id,
+            curve_type: CurveType::Straight,
+            enabled: true,
+            from_port: None,
+            to_port: None,
+        }
+    }
+
+    fn adjacency(edges: &[(u32, u32)]) -> HashMap<u32, Vec<u32>> {
+        let connections: Vec<ConnectionData> = edges
+            .iter()
+            .map(|&(from_id, to_id)| connection(from_id, to_id))
+            .collect();
+        undirected_adjacency(&connections)
+    }
+
+    fn directed(edges: &[(u32, u32)]) -> HashMap<u32, Vec<u32>> {
+        let connections: Vec<ConnectionData> = edges
+            .iter()
+            .map(|&(from_id, to_id)| connection(from_id, to_id))
+            .collect();
+        directed_adjacency(&connections)
+    }
+
+    #[test]
+    fn longest_path_through_a_diamond() {
+        // 1 splits to 2 and 3, which both lead to 4; 3 takes a detour via 5
+        let adjacency = directed(&[(1, 2), (1, 3), (3, 5), (2, 4), (5, 4), (4, 6)]);
+        assert_eq!(longest_path_from([1], &adjacency), Some(4));
+    }
+
+    #[test]
+    fn longest_path_ignores_unreachable_nodes() {
+        // 10 to 14 is longer, but can't be reached from the start
+        let adjacency = directed(&[(1, 2), (10, 11), (11, 12), (12, 13), (13, 14)]);
+        assert_eq!(longest_path_from([1], &adjacency), Some(1));
+        assert_eq!(longest_path_from([], &adjacency), Some(0));
+    }
+
+    #[test]
+    fn longest_path_is_unbounded_with_a_cycle() {
+        let adjacency = directed(&[(1, 2), (2, 3), (3, 2)]);
+        assert_eq!(longest_path_from([1], &adjacency), None);
+        // A cycle the start can't reach doesn't matter
+        let adjacency = directed(&[(1, 2), (5, 6), (6, 5)]);
+        assert_eq!(longest_path_from([1], &adjacency), Some(1));
+    }
+
+    #[test]
+    fn fewer_paths_than_requested() {
+        let adjacency = adjacency(&[(1, 2), (2, 3), (1, 4), (4, 5), (5, 3)]);
+        let paths = shortest_simple_paths(&[1], 3, &adjacency, 5);
+        assert_eq!(paths, vec![vec![1, 2, 3], vec![1, 4, 5, 3]]);
+    }
+
+    #[test]
+    fn paths_from_every_start() {
+        // The 1-5 connection can't be used, since no path passes through a
+        // second Start node
+        let adjacency = adjacency(&[(1, 2), (2, 3), (5, 3), (1, 5)]);
+        let paths = shortest_simple_paths(&[1, 5], 3, &adjacency, 5);
+        assert_eq!(paths, vec![vec![5, 3], vec![1, 2, 3]]);
+        assert_eq!(count_simple_paths(&[1, 5], 3, &adjacency, 100), 2);
+    }
+
+    #[test]
+    fn disabled_connections_are_skipped() {
+        let mut shortcut = connection(1, 3);
+        shortcut.enabled = false;
+        let connections = [connection(1, 2), connection(2, 3), shortcut];
+        let adjacency = undirected_adjacency(&connections);
+        assert_eq!(
+            shortest_simple_paths(&[1], 3, &adjacency, 5),
+            vec![vec![1, 2, 3]]
+        );
+        assert_eq!(count_simple_paths(&[1], 3, &adjacency, 100), 1);
+    }
+
+    #[test]
+    fn count_includes_paths_beyond_the_shown() {
+        // Six two-hop paths from 1 to 8, one through each of 2 to 7
+        let edges: Vec<(u32, u32)> = (2..=7).flat_map(|id| [(1, id), (id, 8)]).collect();
+        let adjacency = adjacency(&edges);
+        let paths = shortest_simple_paths(&[1], 8, &adjacency, 5);
+        assert_eq!(paths.len(), 5);
+        assert!(paths.iter().all(|path| path.len() == 3));
+        assert_eq!(count_simple_paths(&[1], 8, &adjacency, 100), 6);
+        assert_eq!(count_simple_paths(&[1], 8, &adjacency, 4), 4);
+    }
+
+    #[test]
+    fn no_path_to_an_unreachable_target() {
+        let adjacency = adjacency(&[(1, 2), (3, 4)]);
+        assert!(shortest_simple_paths(&[1], 4, &adjacency, 5).is_empty());
+        assert_eq!(count_simple_paths(&[1], 4, &adjacency, 100), 0);
+    }
+}
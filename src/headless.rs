@@ -0,0 +1,87 @@
+use crate::fs::{SaveFormat, load_skill_tree, write_skill_tree};
+use bevy::log::LogPlugin;
+use bevy::prelude::*;
+use std::path::PathBuf;
+
+const USAGE: &str =
+    "Usage: skill_tree_editor --headless --input <file> --output <file> [--format ron|json]";
+
+#[derive(Resource)]
+struct HeadlessArgs {
+    input: PathBuf,
+    output: PathBuf,
+    format: SaveFormat,
+}
+
+/// Converts a skill tree between formats without opening a window, for use in
+/// build pipelines. Only the minimal plugins are added, no rendering or egui.
+pub fn main_headless() -> AppExit {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{e}\n{USAGE}");
+            return AppExit::from_code(2);
+        }
+    };
+
+    App::new()
+        .add_plugins((MinimalPlugins, LogPlugin::default()))
+        .insert_resource(args)
+        .add_systems(Startup, convert_skill_tree)
+        .run()
+}
+
+fn parse_args(args: impl Iterator<Item = String>) -> Result<HeadlessArgs, String> {
+    let mut input = None;
+    let mut output = None;
+    let mut format = None;
+
+    let mut args = args;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--headless" => {}
+            "--input" => input = args.next().map(PathBuf::from),
+            "--output" => output = args.next().map(PathBuf::from),
+            "--format" => {
+                let name = args.next().ok_or("--format needs a value")?;
+                format =
+                    Some(SaveFormat::from_name(&name).ok_or(format!("Unknown format '{name}'"))?);
+            }
+            _ => return Err(format!("Unknown argument '{arg}'")),
+        }
+    }
+
+    let input = input.ok_or("Missing --input")?;
+    let output = output.ok_or("Missing --output")?;
+    // Fall back to the output extension when no format is given
+    let format = format
+        .or_else(|| SaveFormat::from_path(&output))
+        .ok_or("Cannot infer the output format, pass --format")?;
+
+    Ok(HeadlessArgs {
+        input,
+        output,
+        format,
+    })
+}
+
+fn convert_skill_tree(args: Res<HeadlessArgs>, mut app_exit: EventWriter<AppExit>) {
+    let result = load_skill_tree(&args.input.to_string_lossy()).and_then(|save_data| {
+        write_skill_tree(&args.output.to_string_lossy(), &save_data, args.format)
+    });
+
+    match result {
+        Ok(()) => {
+            app_exit.write(AppExit::Success);
+        }
+        Err(e) => {
+            error!(
+                "Failed to convert {} to {}: {}",
+                args.input.display(),
+                args.output.display(),
+                e
+            );
+            app_exit.write(AppExit::from_code(1));
+        }
+    }
+}
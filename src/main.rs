@@ -1,77 +1,367 @@
 mod components;
+mod diff;
+mod export;
 mod fs;
+mod graph;
+mod headless;
+mod scene;
+mod search;
+mod stats;
+mod storage;
 mod ui;
+mod validation;
 
 use crate::components::*;
-use crate::ui::ui_system;
+use crate::export::png::run_png_export;
+use crate::fs::{
+    import_image, is_image_file, is_skill_tree_file, list_image_files, load_editor_config,
+    load_node_type_styles, load_templates,
+};
+use crate::ui::UiSystems;
+use bevy::diagnostic::FrameTimeDiagnosticsPlugin;
+use bevy::ecs::system::SystemParam;
 use bevy::input::mouse::MouseWheel;
 use bevy::prelude::*;
-use bevy_egui::{EguiContexts, EguiPlugin};
+use bevy::render::camera::RenderTarget;
+use bevy::time::common_conditions::on_timer;
+use bevy::window::{ExitCondition, PrimaryWindow, WindowClosed, WindowRef};
+use bevy_egui::{EguiContexts, EguiPlugin, egui};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{SystemTime, UNIX_EPOCH};
 
 // TODO: UNDO / REDO SYSTEM
 
 const GRID_SIZE: f32 = 50.0;
+const CONNECTION_SELECTION_TOLERANCE: f32 = 10.0;
+/// Where Bevy's asset server looks for node images
+const ASSETS_DIR: &str = "assets";
+const CANVAS_COLOR: Color = Color::srgb(0.1, 0.1, 0.15);
 /// Number of segments to approximate an arc
 const ARC_SEGMENTS: u32 = 32;
+/// Share of an animated arc's length covered by its travelling pulse
+const ARC_PULSE_FRACTION: f32 = 0.2;
+/// World units per second travelled by the pulse of an animated arc
+const ARC_PULSE_SPEED: f32 = 120.0;
+const ARC_PULSE_COLOR: Color = Color::srgb(1.0, 0.95, 0.75);
+/// Opacity of an animated arc behind its pulse
+const ANIMATED_ARC_ALPHA: f32 = 0.35;
+/// Extra world-space border around the viewport before connections are culled
+const CULL_MARGIN: f32 = 20.0;
+/// Gap between neighbouring connections in a bundle of parallel connections
+const BUNDLE_SPACING: f32 = 5.0;
+/// Semi-transparent cyan so the preview reads differently from real connections
+const CONNECTION_PREVIEW_COLOR: Color = Color::srgba(0.0, 1.0, 1.0, 0.6);
+const ANGLE_GUIDE_COLOR: Color = Color::srgba(1.0, 0.85, 0.3, 0.5);
+/// Distance from a node's center to the grab handles of its selected connections
+const ENDPOINT_HANDLE_DISTANCE: f32 = 38.0;
+const ENDPOINT_HANDLE_RADIUS: f32 = 6.0;
+/// Length of the dashes, and gaps, of a disabled connection
+const DISABLED_DASH_LENGTH: f32 = 8.0;
+const DISABLED_CONNECTION_ALPHA: f32 = 0.6;
+/// How far the hovered connection's color moves toward white
+const HOVER_BRIGHTEN: f32 = 0.4;
+/// Gap between a focused node's sprite and its focus outline
+const FOCUS_OUTLINE_PADDING: f32 = 6.0;
+const FOCUS_OUTLINE_COLOR: Color = Color::srgb(0.2, 0.9, 1.0);
+/// Focus moves to a node within this many degrees of the pressed arrow's
+/// direction
+const FOCUS_DIRECTION_CONE: f32 = 45.0;
+/// Fraction of the visible canvas a focused node is kept inside before the
+/// camera recenters on it
+const FOCUS_VISIBLE_FRACTION: f32 = 0.8;
+const WAYPOINT_HANDLE_RADIUS: f32 = 5.0;
+/// Line segments drawn for each span between two points of a curve
+const CURVE_SEGMENTS: u32 = 16;
+/// Extra depth for the selected node so it stays on top while dragged
+const SELECTED_Z_LIFT: f32 = 0.001;
+/// Sprite depth of a node with z-order 0. Node sprites stay within
+/// `SkillNodeData::MAX_Z_ORDER` of it, above the ghost sprites and inside the
+/// camera's ±1000 depth range.
+const NODE_Z_BASE: f32 = 500.0;
+const SNAP_OFFSET_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.5);
+/// Opacity of the placement preview node
+const GHOST_NODE_ALPHA: f32 = 0.5;
+/// Sideways spacing between paths drawn by the "Show Paths from Start" overlay
+const PATH_OFFSET: f32 = 4.0;
+/// Space kept around nodes framed by Select by Type or the load fly-in
+const FRAME_MARGIN: f32 = 80.0;
+
+fn main() -> AppExit {
+    if std::env::args().any(|arg| arg == "--headless") {
+        return headless::main_headless();
+    }
 
-fn main() {
     App::new()
         .add_plugins((
-            DefaultPlugins,
+            // The secondary viewport shouldn't keep the editor running on its own
+            DefaultPlugins.set(WindowPlugin {
+                exit_condition: ExitCondition::OnPrimaryClosed,
+                ..default()
+            }),
             EguiPlugin {
                 enable_multipass_for_primary_context: false,
             },
+            FrameTimeDiagnosticsPlugin::default(),
         ))
+        .register_type::<SkillNode>()
+        .register_type::<SkillNodeData>()
+        .register_type::<ConnectionData>()
+        .register_type::<CurveType>()
+        .register_type::<NodeType>()
+        .register_type::<BuiltinNodeType>()
+        .register_type::<StatModifier>()
+        .register_type::<StatCondition>()
+        .register_type::<ModifierType>()
+        .register_type::<PortAnchor>()
+        .register_type::<scene::SceneTreeData>()
+        .add_event::<NodeCreatedEvent>()
+        .add_event::<NodeDeletedEvent>()
+        .add_event::<ConnectionCreatedEvent>()
+        .add_event::<ConnectionDeletedEvent>()
+        .add_event::<NodeMovedEvent>()
         .init_resource::<EditorState>()
         .init_resource::<SkillTreeData>()
         .init_resource::<SelectedNode>()
+        .init_resource::<HoveredNode>()
+        .init_resource::<HoveredConnection>()
+        .init_resource::<CursorWorldPos>()
+        .init_resource::<MultiSelectedNodes>()
         .init_resource::<SelectedConnection>()
         .init_resource::<DragState>()
+        .init_resource::<ConnectionDragState>()
+        .init_resource::<ConnectionHandleDragState>()
         .init_resource::<ConnectionMode>()
+        .init_resource::<ContextMenuState>()
+        .init_resource::<DoubleClickState>()
+        .init_resource::<NodeEditDialog>()
+        .init_resource::<InlineRename>()
         .init_resource::<EditorCamera>()
+        .init_resource::<SecondaryViewport>()
+        .init_resource::<CameraTransition>()
         .init_resource::<EguiInputState>()
         .init_resource::<GridSettings>()
         .init_resource::<NodeImages>()
+        .init_resource::<TimedToast>()
+        .init_resource::<PngExportState>()
+        .init_resource::<SpatialHashGrid>()
+        .init_resource::<EditorStats>()
+        .init_resource::<FrameTimeHistory>()
+        .init_resource::<ThemeSettings>()
+        .init_resource::<ReachabilitySet>()
+        .init_resource::<StartPaths>()
+        .init_resource::<DependencyHighlight>()
+        .init_resource::<CycleWarnings>()
+        .init_resource::<ValidationWarnings>()
+        .init_resource::<TopologyMetrics>()
+        .init_resource::<CentralityColoring>()
+        .init_resource::<ConnectionBundles>()
+        .init_resource::<ConnectionRoutes>()
+        .init_resource::<TierFilter>()
+        .init_resource::<CompareTree>()
+        .init_resource::<NodeTypeStyles>()
+        .init_resource::<NodeTypeColors>()
+        .init_resource::<NodeTypeHitRadius>()
+        .init_resource::<ConnectionSettings>()
+        .init_resource::<ConnectionStylePresets>()
+        .init_resource::<HotReloadSettings>()
+        .init_resource::<TemplateLibrary>()
+        .init_resource::<StampMode>()
+        .init_resource::<KeyboardNavigation>()
+        .init_resource::<PrintLayout>()
         .add_systems(Startup, setup)
         .add_systems(
             Update,
             (
-                ui_system,
-                update_egui_input_state.after(ui_system),
+                (handle_file_drop, tick_toast).before(UiSystems),
+                (
+                    apply_high_contrast,
+                    (
+                        ui::menu_bar_ui,
+                        ui::status_bar_ui,
+                        ui::properties_panel_ui,
+                        ui::canvas_overlays_ui,
+                        ui::settings_window_ui,
+                        ui::file_dialogs_ui,
+                        ui::analysis_windows_ui,
+                        ui::canvas_popups_ui,
+                        ui::toast_ui,
+                    )
+                        .chain()
+                        .in_set(UiSystems),
+                    ui::performance_overlay_ui,
+                    ui::print_layout_ui,
+                )
+                    .chain(),
+                update_egui_input_state.after(UiSystems),
+                update_window_title.after(UiSystems),
+                update_cursor_world_pos,
+                rebuild_spatial_hash.after(update_egui_input_state),
+                update_placement_ghost.after(update_hovered_node),
+                compute_dependency_highlight
+                    .after(rebuild_spatial_hash)
+                    .before(update_node_visuals),
+                (emit_tree_events, track_node_modifications)
+                    .after(UiSystems)
+                    .after(handle_mouse_input)
+                    .after(handle_node_dragging)
+                    .after(handle_keyboard_shortcuts),
+                log_tree_events.after(emit_tree_events),
+                scene::attach_scene_nodes.before(UiSystems),
+                watch_skill_tree_file
+                    .run_if(on_timer(HotReloadSettings::POLL_INTERVAL))
+                    .after(UiSystems),
+                (update_viewport_camera, close_secondary_viewport).chain(),
+                compute_topology_metrics
+                    .after(detect_cycles)
+                    .after(rebuild_spatial_hash),
+                (
+                    draw_snap_offset.after(handle_node_dragging),
+                    (handle_keyboard_navigation, draw_focus_outline)
+                        .chain()
+                        .after(rebuild_spatial_hash)
+                        .before(update_camera),
+                    update_print_layout.after(UiSystems),
+                    draw_ports.after(update_node_visuals),
+                ),
+                update_hovered_connection
+                    .after(update_hovered_node)
+                    .before(draw_connections),
+                compute_centrality
+                    .after(rebuild_spatial_hash)
+                    .before(update_node_visuals),
+                run_camera_transition
+                    .after(UiSystems)
+                    .after(scene::attach_scene_nodes)
+                    .before(update_camera),
+                (
+                    handle_connection_endpoint_drag,
+                    handle_connection_waypoint_drag,
+                )
+                    .chain()
+                    .after(rebuild_spatial_hash)
+                    .before(handle_node_selection)
+                    .before(handle_connection_selection),
                 (
                     update_camera,
                     handle_mouse_input,
                     handle_node_selection,
+                    update_hovered_node,
                     handle_node_dragging,
                     handle_connection_selection,
-                    update_node_visuals,
-                    draw_connections,
+                    compute_reachability,
+                    compute_start_paths,
+                    draw_start_paths.after(compute_start_paths),
+                    detect_cycles,
+                    validate_nodes,
+                    validate_start_nodes,
+                    update_tree_diff,
+                    sync_node_transforms,
+                    (update_node_visuals, update_node_outlines)
+                        .chain()
+                        .after(compute_reachability)
+                        .after(detect_cycles)
+                        .after(update_tree_diff),
+                    (
+                        bundle_connections,
+                        route_connections,
+                        draw_connections.after(compute_reachability),
+                    )
+                        .chain(),
+                    draw_connection_preview,
                     draw_grid,
                     handle_keyboard_shortcuts,
+                    run_png_export,
                 )
-                    .after(update_egui_input_state),
+                    .after(rebuild_spatial_hash),
             ),
         )
-        .run();
+        .run()
 }
 
+#[allow(clippy::too_many_arguments)]
 fn setup(
     mut commands: Commands,
     mut grid_settings: ResMut<GridSettings>,
     mut node_images: ResMut<NodeImages>,
+    mut node_type_styles: ResMut<NodeTypeStyles>,
+    mut node_type_colors: ResMut<NodeTypeColors>,
+    mut node_type_hit_radius: ResMut<NodeTypeHitRadius>,
+    mut connection_style_presets: ResMut<ConnectionStylePresets>,
+    mut template_library: ResMut<TemplateLibrary>,
+    mut editor_state: ResMut<EditorState>,
+    mut hot_reload: ResMut<HotReloadSettings>,
+    mut topology_metrics: ResMut<TopologyMetrics>,
+    mut theme_settings: ResMut<ThemeSettings>,
     asset_server: Res<AssetServer>,
 ) {
     commands.spawn((
         Camera2d,
+        MainCamera,
         Camera {
-            clear_color: ClearColorConfig::Custom(Color::srgb(0.1, 0.1, 0.15)),
+            clear_color: ClearColorConfig::Custom(CANVAS_COLOR),
             ..default()
         },
     ));
     grid_settings.grid_size = GRID_SIZE;
     grid_settings.snap_to_grid = true;
+    grid_settings.bundle_parallel = true;
+    grid_settings.connection_selection_tolerance = CONNECTION_SELECTION_TOLERANCE;
+
+    node_images.placeholder = asset_server.load(NodeImages::PLACEHOLDER);
+    for image_name in list_image_files(Path::new(ASSETS_DIR)) {
+        node_images.load(&asset_server, &image_name);
+    }
+
+    let ghost_type = NodeType::default();
+    commands.spawn((
+        GhostNodeSprite,
+        Transform::from_translation(Vec3::new(0.0, 0.0, -0.25)),
+        Sprite {
+            custom_size: Some(Vec2::splat(ghost_type.size())),
+            image: node_images.placeholder.clone(),
+            color: Color::srgb_from_array(ghost_type.tint()).with_alpha(GHOST_NODE_ALPHA),
+            ..default()
+        },
+        Visibility::Hidden,
+    ));
+
+    let config_path = Path::new(EditorConfig::FILE_NAME);
+    let config = if storage::exists(config_path) {
+        load_editor_config(config_path).unwrap_or_else(|e| {
+            error!("Failed to load {}: {}", config_path.display(), e);
+            EditorConfig::default()
+        })
+    } else {
+        EditorConfig::default()
+    };
+    editor_state.side_panel_width = config.side_panel_width;
+    node_type_colors.colors = config.node_type_colors;
+    node_type_hit_radius.radii = config.node_hit_radii;
+    connection_style_presets.presets = config.connection_style_presets;
+    editor_state.max_backups = config.max_backups;
+    hot_reload.enabled = config.reload_changed_files;
+    editor_state.remember_camera = config.remember_camera;
+    topology_metrics.thresholds = config.metric_thresholds;
+    theme_settings.colorblind_mode = config.colorblind_mode;
+    theme_settings.high_contrast = config.high_contrast;
+
+    let templates_path = Path::new(TemplateLibrary::FILE_NAME);
+    if storage::exists(templates_path) {
+        match load_templates(templates_path) {
+            Ok(templates) => template_library.templates = templates,
+            Err(e) => error!("Failed to load {}: {}", templates_path.display(), e),
+        }
+    }
 
-    node_images.skill_node = asset_server.load("skill_border_01.png");
+    let styles_path = Path::new(NodeTypeStyles::FILE_NAME);
+    if storage::exists(styles_path) {
+        match load_node_type_styles(styles_path) {
+            Ok(styles) => node_type_styles.styles = styles,
+            Err(e) => error!("Failed to load {}: {}", styles_path.display(), e),
+        }
+    }
 }
 
 fn update_egui_input_state(
@@ -84,8 +374,130 @@ fn update_egui_input_state(
     }
 }
 
+/// Shows the open file and tree name in the window title
+fn update_window_title(
+    editor_state: Res<EditorState>,
+    skill_tree_data: Res<SkillTreeData>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !editor_state.is_changed() && !skill_tree_data.is_changed() {
+        return;
+    }
+    let Ok(mut window) = windows.single_mut() else {
+        return;
+    };
+    let file_name = editor_state
+        .current_file_path
+        .as_ref()
+        .and_then(|p| p.file_name())
+        .map_or("untitled".into(), |name| name.to_string_lossy());
+    let title = if skill_tree_data.tree_name.is_empty() {
+        format!("{} — Skill Tree Editor", file_name)
+    } else {
+        format!(
+            "{} — {} — Skill Tree Editor",
+            file_name, skill_tree_data.tree_name
+        )
+    };
+    if window.title != title {
+        window.title = title;
+    }
+}
+
+fn handle_file_drop(
+    mut drop_events: EventReader<FileDragAndDrop>,
+    mut editor_state: ResMut<EditorState>,
+    mut toast: ResMut<TimedToast>,
+    selected_node: Res<SelectedNode>,
+    mut node_query: Query<&mut SkillNode>,
+    mut node_images: ResMut<NodeImages>,
+    asset_server: Res<AssetServer>,
+) {
+    for event in drop_events.read() {
+        let FileDragAndDrop::DroppedFile { path_buf, .. } = event else {
+            continue;
+        };
+
+        // Images dropped onto the window become the selected node's image
+        if is_image_file(path_buf) {
+            let Some(mut node) = selected_node
+                .entity
+                .and_then(|entity| node_query.get_mut(entity).ok())
+            else {
+                toast.error("Select a node before dropping an image onto it");
+                continue;
+            };
+            match import_image(path_buf, Path::new(ASSETS_DIR)) {
+                Ok(image_name) => {
+                    node_images.load(&asset_server, &image_name);
+                    toast.info(format!(
+                        "Image '{}' assigned to Node {}.",
+                        image_name, node.id
+                    ));
+                    node.data.image_name = image_name;
+                    editor_state.dirty = true;
+                }
+                Err(e) => toast.error(format!("Failed to import image: {}", e)),
+            }
+            continue;
+        }
+
+        if !is_skill_tree_file(path_buf) {
+            toast.error(format!(
+                "Unsupported file type: {}",
+                path_buf.file_name().unwrap_or_default().to_string_lossy()
+            ));
+            continue;
+        }
+
+        if editor_state.dirty {
+            editor_state.pending_dropped_file = Some(path_buf.clone());
+            editor_state.show_unsaved_changes_on_load_dialog = true;
+            editor_state.next_action_after_save_as = NextActionAfterSaveAs::None;
+        } else {
+            editor_state.loading_file = Some(path_buf.clone());
+        }
+    }
+}
+
+/// Polls the open file's modification time, raising the reload prompt when
+/// something other than the editor changed it
+fn watch_skill_tree_file(
+    mut hot_reload: ResMut<HotReloadSettings>,
+    editor_state: Res<EditorState>,
+) {
+    if !hot_reload.enabled {
+        return;
+    }
+    if hot_reload.watch_path != editor_state.current_file_path {
+        hot_reload.watch_path = editor_state.current_file_path.clone();
+        hot_reload.last_modified = None;
+        hot_reload.reload_prompt = false;
+    }
+    let Some(path) = &hot_reload.watch_path else {
+        return;
+    };
+    let modified = storage::modified(path);
+    match hot_reload.last_modified {
+        None => hot_reload.last_modified = modified,
+        Some(last) if modified.is_some_and(|modified| modified != last) => {
+            hot_reload.last_modified = modified;
+            hot_reload.reload_prompt = true;
+        }
+        Some(_) => {}
+    }
+}
+
+fn tick_toast(mut toast: ResMut<TimedToast>, time: Res<Time>) {
+    if toast.remaining > 0.0 {
+        toast.remaining = (toast.remaining - time.delta_secs()).max(0.0);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn update_camera(
-    mut camera_query: Query<&mut Transform, With<Camera2d>>,
+    mut camera_query: Query<&mut Transform, With<MainCamera>>,
+    primary_window: Query<(Entity, &Window), With<PrimaryWindow>>,
     mut editor_camera: ResMut<EditorCamera>,
     mouse_button: Res<ButtonInput<MouseButton>>,
     mut mouse_motion: EventReader<CursorMoved>,
@@ -93,34 +505,65 @@ fn update_camera(
     keyboard: Res<ButtonInput<KeyCode>>,
     time: Res<Time>,
     egui_input_state: Res<EguiInputState>,
+    keyboard_navigation: Res<KeyboardNavigation>,
+    node_query: Query<&SkillNode>,
 ) {
     let Ok(mut camera_transform) = camera_query.single_mut() else {
         return;
     };
 
+    // Ctrl+Shift+W fits the nodes' width and Ctrl+Shift+H their height
+    let ctrl_pressed =
+        keyboard.pressed(KeyCode::ControlLeft) || keyboard.pressed(KeyCode::ControlRight);
+    let shift_pressed =
+        keyboard.pressed(KeyCode::ShiftLeft) || keyboard.pressed(KeyCode::ShiftRight);
+    if ctrl_pressed && shift_pressed && !egui_input_state.wants_keyboard_input {
+        let axes = if keyboard.just_pressed(KeyCode::KeyW) {
+            Some(Vec2::X)
+        } else if keyboard.just_pressed(KeyCode::KeyH) {
+            Some(Vec2::Y)
+        } else {
+            None
+        };
+        if let (Some(axes), Ok((_, window))) = (axes, primary_window.single()) {
+            let positions: Vec<Vec2> = node_query.iter().map(|node| node.data.position).collect();
+            if let Some((center, zoom)) = framing_axes(&positions, window.size(), axes) {
+                editor_camera.pan_offset = center;
+                editor_camera.target_zoom = zoom;
+            }
+        }
+    }
+
     if egui_input_state.wants_pointer_input {
         mouse_wheel.clear();
         mouse_motion.clear();
         return;
     }
 
-    for event in mouse_wheel.read() {
+    // Input over the secondary viewport moves its own camera instead
+    let primary_window = primary_window.single().ok().map(|(entity, _)| entity);
+    for event in mouse_wheel
+        .read()
+        .filter(|event| Some(event.window) == primary_window)
+    {
         editor_camera.target_zoom *= 1.0 - event.y * 0.1;
-        editor_camera.target_zoom = editor_camera.target_zoom.clamp(0.1, 5.0);
+        editor_camera.target_zoom = editor_camera
+            .target_zoom
+            .clamp(EditorCamera::MIN_ZOOM, EditorCamera::MAX_ZOOM);
     }
 
     editor_camera.zoom = editor_camera
         .zoom
         .lerp(editor_camera.target_zoom, 6.0 * time.delta_secs());
 
-    let shift_pressed =
-        keyboard.pressed(KeyCode::ShiftLeft) || keyboard.pressed(KeyCode::ShiftRight);
-
     if mouse_button.pressed(MouseButton::Middle)
         || (shift_pressed && mouse_button.pressed(MouseButton::Left))
     {
         let mut pan_input_delta = Vec2::ZERO;
-        for event in mouse_motion.read() {
+        for event in mouse_motion
+            .read()
+            .filter(|event| Some(event.window) == primary_window)
+        {
             if let Some(e_delta) = event.delta {
                 pan_input_delta.x -= e_delta.x;
                 pan_input_delta.y += e_delta.y;
@@ -132,7 +575,8 @@ fn update_camera(
         mouse_motion.clear();
     }
 
-    if !egui_input_state.wants_keyboard_input {
+    // Arrow keys move keyboard focus instead while a node has it
+    if !egui_input_state.wants_keyboard_input && keyboard_navigation.focused.is_none() {
         let pan_speed = 500.0 * time.delta_secs() * editor_camera.zoom;
         if keyboard.pressed(KeyCode::ArrowLeft) {
             editor_camera.pan_offset.x -= pan_speed;
@@ -154,6 +598,471 @@ fn update_camera(
         .extend(camera_transform.translation.z);
 }
 
+/// Camera center and zoom that fit every position in a viewport of the given
+/// size, or `None` without any positions
+pub fn framing(positions: &[Vec2], viewport: Vec2) -> Option<(Vec2, f32)> {
+    framing_axes(positions, viewport, Vec2::ONE)
+}
+
+/// Like `framing`, but only the axes set to 1 in `axes` are fit, so
+/// `Vec2::X` fits the width and lets nodes above and below go off screen
+pub fn framing_axes(positions: &[Vec2], viewport: Vec2, axes: Vec2) -> Option<(Vec2, f32)> {
+    let (&first, rest) = positions.split_first()?;
+    let (min, max) = rest
+        .iter()
+        .fold((first, first), |(min, max), &p| (min.min(p), max.max(p)));
+    let extent = max - min + Vec2::splat(2.0 * FRAME_MARGIN);
+    let zoom = (extent / viewport.max(Vec2::ONE) * axes)
+        .max_element()
+        .clamp(EditorCamera::MIN_ZOOM, EditorCamera::MAX_ZOOM);
+    Some(((min + max) * 0.5, zoom))
+}
+
+/// Plays the fly-in after a load, driving the editor camera directly until
+/// it finishes or a key is pressed
+fn run_camera_transition(
+    mut transition: ResMut<CameraTransition>,
+    mut editor_camera: ResMut<EditorCamera>,
+    node_query: Query<&SkillNode>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+) {
+    if transition.pending {
+        transition.pending = false;
+        let viewport = windows.single().map_or(Vec2::ONE, |window| window.size());
+        let positions: Vec<Vec2> = node_query.iter().map(|node| node.data.position).collect();
+        let (end_pan, end_zoom) = transition
+            .saved_view
+            .or_else(|| framing(&positions, viewport))
+            .unwrap_or((Vec2::ZERO, 1.0));
+        *transition = CameraTransition {
+            pending: false,
+            saved_view: None,
+            active: true,
+            progress: 0.0,
+            start_pan: editor_camera.pan_offset,
+            start_zoom: CameraTransition::START_ZOOM,
+            end_pan,
+            end_zoom,
+        };
+    }
+    if !transition.active {
+        return;
+    }
+
+    transition.progress = if keyboard.get_just_pressed().next().is_some() {
+        1.0
+    } else {
+        (transition.progress + time.delta_secs() / CameraTransition::DURATION).min(1.0)
+    };
+    let t = transition.eased_progress();
+    editor_camera.pan_offset = transition.start_pan.lerp(transition.end_pan, t);
+    editor_camera.zoom = transition.start_zoom.lerp(transition.end_zoom, t);
+    editor_camera.target_zoom = editor_camera.zoom;
+    transition.active = transition.progress < 1.0;
+}
+
+/// Zoom and middle-drag panning for the secondary viewport, from input over
+/// its window
+fn update_viewport_camera(
+    mut camera_query: Query<&mut Transform, (With<ViewportCamera>, Without<MainCamera>)>,
+    mut secondary_viewport: ResMut<SecondaryViewport>,
+    mouse_button: Res<ButtonInput<MouseButton>>,
+    mut mouse_motion: EventReader<CursorMoved>,
+    mut mouse_wheel: EventReader<MouseWheel>,
+    time: Res<Time>,
+) {
+    let Some(window) = secondary_viewport.window else {
+        mouse_wheel.clear();
+        mouse_motion.clear();
+        return;
+    };
+    let Ok(mut camera_transform) = camera_query.single_mut() else {
+        return;
+    };
+    let camera = &mut secondary_viewport.camera;
+
+    for event in mouse_wheel.read().filter(|event| event.window == window) {
+        camera.target_zoom = (camera.target_zoom * (1.0 - event.y * 0.1))
+            .clamp(EditorCamera::MIN_ZOOM, EditorCamera::MAX_ZOOM);
+    }
+    camera.zoom = camera
+        .zoom
+        .lerp(camera.target_zoom, 6.0 * time.delta_secs());
+
+    let dragging = mouse_button.pressed(MouseButton::Middle);
+    for event in mouse_motion.read().filter(|event| event.window == window) {
+        if let Some(delta) = event.delta.filter(|_| dragging) {
+            camera.pan_offset += Vec2::new(-delta.x, delta.y) * camera.zoom;
+        }
+    }
+
+    camera_transform.scale = Vec3::splat(camera.zoom);
+    camera_transform.translation = camera.pan_offset.extend(camera_transform.translation.z);
+}
+
+/// Opens the secondary viewport, starting zoomed out over the area the main
+/// canvas is looking at
+pub fn open_secondary_viewport(
+    commands: &mut Commands,
+    secondary_viewport: &mut SecondaryViewport,
+    editor_camera: &EditorCamera,
+) {
+    let window = commands
+        .spawn(Window {
+            title: "Viewport — Skill Tree Editor".to_string(),
+            ..default()
+        })
+        .id();
+    let zoom = (editor_camera.target_zoom * SecondaryViewport::INITIAL_ZOOM)
+        .clamp(EditorCamera::MIN_ZOOM, EditorCamera::MAX_ZOOM);
+    secondary_viewport.window = Some(window);
+    secondary_viewport.camera = EditorCamera {
+        zoom,
+        target_zoom: zoom,
+        pan_offset: editor_camera.pan_offset,
+    };
+    commands.spawn((
+        Camera2d,
+        ViewportCamera,
+        Camera {
+            target: RenderTarget::Window(WindowRef::Entity(window)),
+            clear_color: ClearColorConfig::Custom(CANVAS_COLOR),
+            ..default()
+        },
+        Transform::from_translation(editor_camera.pan_offset.extend(0.0))
+            .with_scale(Vec3::splat(zoom)),
+    ));
+}
+
+/// Drops the viewport's camera once its window has been closed
+fn close_secondary_viewport(
+    mut commands: Commands,
+    mut closed: EventReader<WindowClosed>,
+    mut secondary_viewport: ResMut<SecondaryViewport>,
+    camera_query: Query<Entity, With<ViewportCamera>>,
+) {
+    let Some(window) = secondary_viewport.window else {
+        closed.clear();
+        return;
+    };
+    if closed.read().any(|event| event.window == window) {
+        for camera in camera_query.iter() {
+            commands.entity(camera).despawn();
+        }
+        secondary_viewport.window = None;
+    }
+}
+
+/// The focused node or connection, and the group of nodes selected alongside
+#[derive(SystemParam)]
+pub struct Selection<'w> {
+    selected_node: ResMut<'w, SelectedNode>,
+    selected_connection: ResMut<'w, SelectedConnection>,
+    multi_selected: ResMut<'w, MultiSelectedNodes>,
+}
+
+/// Modes and options for adding nodes and connections on the canvas
+#[derive(SystemParam)]
+pub struct EditTools<'w> {
+    connection_mode: ResMut<'w, ConnectionMode>,
+    connection_settings: ResMut<'w, ConnectionSettings>,
+    stamp_mode: ResMut<'w, StampMode>,
+    template_library: ResMut<'w, TemplateLibrary>,
+}
+
+/// Sideways bundle offsets and routes around nodes that move connections off
+/// the line between their nodes
+#[derive(SystemParam)]
+pub struct ConnectionLayout<'w> {
+    bundles: Res<'w, ConnectionBundles>,
+    routes: Res<'w, ConnectionRoutes>,
+}
+
+impl ConnectionLayout<'_> {
+    /// How far the connection at `index` is shifted to spread out its bundle
+    fn offset(&self, index: usize) -> Vec2 {
+        self.bundles
+            .offsets
+            .get(&index)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Path of the connection at `index` when it bends around nodes
+    fn route(&self, index: usize) -> Option<&Vec<Vec2>> {
+        self.routes.paths.get(&index)
+    }
+}
+
+/// What's needed to find the node or connection under a point on the canvas
+#[derive(SystemParam)]
+pub struct CanvasPicking<'w> {
+    spatial_hash: Res<'w, SpatialHashGrid>,
+    hit_radius: Res<'w, NodeTypeHitRadius>,
+    node_type_styles: Res<'w, NodeTypeStyles>,
+    tier_filter: Res<'w, TierFilter>,
+    grid_settings: Res<'w, GridSettings>,
+    editor_camera: Res<'w, EditorCamera>,
+    layout: ConnectionLayout<'w>,
+}
+
+impl CanvasPicking<'_> {
+    /// The node under `world_position`, as (entity, id, position). Each node
+    /// is hit within its type's radius from `NodeTypeHitRadius`, and nodes
+    /// hidden by the tier filter aren't hit at all.
+    fn node_at(
+        &self,
+        world_position: Vec2,
+        node_query: &Query<(Entity, &SkillNode, &Transform)>,
+    ) -> Option<(Entity, u32, Vec2)> {
+        let mut closest_node = None;
+        let mut closest_distance = f32::MAX;
+
+        let query_radius = self.hit_radius.max_radius(&self.node_type_styles);
+        for (entity, _) in self.spatial_hash.query(world_position, query_radius) {
+            let Ok((entity, node, transform)) = node_query.get(entity) else {
+                continue;
+            };
+            if self.tier_filter.hides(node.data.tier) {
+                continue;
+            }
+            let distance = world_position.distance(transform.translation.xy());
+            if distance < self.hit_radius.radius(&node.data, &self.node_type_styles)
+                && distance < closest_distance
+            {
+                closest_distance = distance;
+                closest_node = Some((entity, node.id, transform.translation.xy()));
+            }
+        }
+        closest_node
+    }
+
+    /// Index of the connection passing closest to `world_position`, if any
+    /// passes within the click tolerance. Hidden disabled connections and
+    /// those touching a node hidden by the tier filter can't be hit.
+    fn connection_at(
+        &self,
+        world_position: Vec2,
+        skill_tree_data: &SkillTreeData,
+        node_query: &Query<(Entity, &SkillNode, &Transform)>,
+    ) -> Option<usize> {
+        let tolerance = self.grid_settings.connection_tolerance(&self.editor_camera);
+        // Where a connection attaches to one of its nodes, or None when the
+        // node is missing or hidden
+        let endpoint = |id: u32, port: Option<u32>| {
+            let entity = *skill_tree_data.nodes.get(&id)?;
+            let (_, node, transform) = node_query.get(entity).ok()?;
+            (!self.tier_filter.hides(node.data.tier)).then(|| attach_point(node, transform, port))
+        };
+        let distances =
+            skill_tree_data
+                .connections
+                .iter()
+                .enumerate()
+                .map(|(index, connection)| {
+                    if !connection.enabled && self.grid_settings.hide_disabled {
+                        return f32::MAX;
+                    }
+                    let (Some(from), Some(to)) = (
+                        endpoint(connection.from_id, connection.from_port),
+                        endpoint(connection.to_id, connection.to_port),
+                    ) else {
+                        return f32::MAX;
+                    };
+                    // Bundled connections are drawn shifted sideways, so pick them there
+                    let offset = self.layout.offset(index);
+                    let (from, to) = (from + offset, to + offset);
+                    if let Some(path) = self.layout.route(index) {
+                        return polyline_distance(world_position, path);
+                    }
+                    match &connection.curve_type {
+                        CurveType::Straight => point_to_line_distance(world_position, from, to),
+                        CurveType::Arc {
+                            radius, clockwise, ..
+                        } => {
+                            // If arc is invalid, fall back to straight line distance
+                            point_to_arc_distance(world_position, from, to, *radius, *clockwise)
+                                .unwrap_or_else(|| point_to_line_distance(world_position, from, to))
+                        }
+                        CurveType::CatmullRom { waypoints } => polyline_distance(
+                            world_position,
+                            &catmull_rom_polyline(from, to, waypoints),
+                        ),
+                    }
+                });
+        distances
+            .enumerate()
+            .filter(|(_, distance)| *distance < tolerance)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(index, _)| index)
+    }
+}
+
+/// Part of the world visible in either the main window or the secondary
+/// viewport, since gizmos draw to both
+#[derive(SystemParam)]
+pub struct VisibleCanvas<'w, 's> {
+    primary_window: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+    windows: Query<'w, 's, &'static Window>,
+    editor_camera: Res<'w, EditorCamera>,
+    secondary_viewport: Res<'w, SecondaryViewport>,
+}
+
+/// Cursor position on the canvas in world space. Comes from the main window,
+/// or from the secondary viewport while it's editable and under the cursor.
+#[derive(SystemParam)]
+pub struct CanvasCursor<'w, 's> {
+    primary_window: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+    windows: Query<'w, 's, &'static Window>,
+    main_camera: Query<'w, 's, (&'static Camera, &'static GlobalTransform), With<MainCamera>>,
+    viewport_camera:
+        Query<'w, 's, (&'static Camera, &'static GlobalTransform), With<ViewportCamera>>,
+    secondary_viewport: Res<'w, SecondaryViewport>,
+}
+
+impl CanvasCursor<'_, '_> {
+    pub fn world_position(&self) -> Option<Vec2> {
+        let primary = self.primary_window.single().ok();
+        let main = primary
+            .and_then(|window| window.cursor_position())
+            .zip(self.main_camera.single().ok())
+            .and_then(|(cursor_position, (camera, camera_transform))| {
+                camera
+                    .viewport_to_world_2d(camera_transform, cursor_position)
+                    .ok()
+            });
+        if main.is_some() || !self.secondary_viewport.editable {
+            return main;
+        }
+
+        self.secondary_viewport
+            .window
+            .and_then(|window| self.windows.get(window).ok())
+            .and_then(|window| window.cursor_position())
+            .zip(self.viewport_camera.single().ok())
+            .and_then(|(cursor_position, (camera, camera_transform))| {
+                camera
+                    .viewport_to_world_2d(camera_transform, cursor_position)
+                    .ok()
+            })
+    }
+
+    /// Where on the main window a menu for a click at `world_position`
+    /// opens. Clicks in the viewport open it over the same point of the
+    /// main canvas, kept inside the window.
+    pub fn menu_position(&self, world_position: Vec2) -> Vec2 {
+        let Ok(window) = self.primary_window.single() else {
+            return Vec2::ZERO;
+        };
+        window.cursor_position().unwrap_or_else(|| {
+            self.main_camera
+                .single()
+                .ok()
+                .and_then(|(camera, camera_transform)| {
+                    camera
+                        .world_to_viewport(camera_transform, world_position.extend(0.0))
+                        .ok()
+                })
+                .unwrap_or_default()
+                .clamp(Vec2::ZERO, window.size())
+        })
+    }
+}
+
+/// Seconds since the Unix epoch. Browsers have no `SystemTime`, so web
+/// builds ask JavaScript's `Date` instead.
+pub fn unix_timestamp() -> u64 {
+    #[cfg(target_arch = "wasm32")]
+    {
+        (js_sys::Date::now() / 1000.0) as u64
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs())
+    }
+}
+
+/// `node_data` stamped as created and modified now, for a node the user
+/// just added, whether new or copied from another
+pub fn mark_created(mut node_data: SkillNodeData) -> SkillNodeData {
+    let now = unix_timestamp();
+    node_data.created_at = now;
+    node_data.modified_at = now;
+    node_data
+}
+
+/// IBM Design's colorblind-safe palette: blue, purple, magenta, orange, gold
+const CVD_BLUE: Color = Color::srgb(0.392, 0.561, 1.0);
+const CVD_PURPLE: Color = Color::srgb(0.471, 0.369, 0.941);
+const CVD_MAGENTA: Color = Color::srgb(0.863, 0.149, 0.498);
+const CVD_ORANGE: Color = Color::srgb(0.996, 0.38, 0.0);
+const CVD_GOLD: Color = Color::srgb(1.0, 0.69, 0.0);
+/// Colors less saturated than this are greys and read the same to everyone
+const CVD_MIN_SATURATION: f32 = 0.15;
+/// High contrast color for selected and otherwise highlighted nodes and
+/// connections; everything else is white
+const HIGH_CONTRAST_HIGHLIGHT: Color = Color::srgb(1.0, 0.9, 0.0);
+/// High contrast color for unreachable nodes and connections
+const HIGH_CONTRAST_DIM: Color = Color::srgb(0.45, 0.45, 0.45);
+/// Width of the black outline around nodes in high contrast mode
+const HIGH_CONTRAST_OUTLINE_WIDTH: f32 = 4.0;
+const HIGH_CONTRAST_LINE_SCALE: f32 = 2.0;
+const HIGH_CONTRAST_FONT_SCALE: f32 = 1.3;
+const PRINT_PAGE_COLOR: Color = Color::srgb(0.3, 0.6, 1.0);
+/// Extra distance kept between a routed connection and the nodes it bends
+/// around
+const ROUTE_CLEARANCE: f32 = 8.0;
+/// How close to a port a click has to land to attach a connection to it
+/// instead of the node's center, in world units
+const PORT_SNAP_DISTANCE: f32 = 12.0;
+const PORT_RADIUS: f32 = 4.0;
+const PORT_COLOR: Color = Color::srgb(0.4, 0.9, 1.0);
+
+/// `color` moved onto a palette that stays distinguishable under `mode`.
+/// The hue picks the palette entry, while lightness and alpha are kept so
+/// lighter and dimmed variants of a color stay lighter and dimmed.
+pub fn remap_color_for_cvd(color: Color, mode: ColorblindMode) -> Color {
+    // Palette entries for the red, yellow, green, cyan, blue and magenta
+    // sextants of the hue wheel
+    let palette = match mode {
+        ColorblindMode::None => return color,
+        // Red and green are confused, so they become orange and blue
+        ColorblindMode::Deuteranopia | ColorblindMode::Protanopia => [
+            CVD_ORANGE,
+            CVD_GOLD,
+            CVD_BLUE,
+            CVD_BLUE,
+            CVD_PURPLE,
+            CVD_MAGENTA,
+        ],
+        // Blue and yellow are confused, so they move toward magenta and orange
+        ColorblindMode::Tritanopia => [
+            CVD_MAGENTA,
+            CVD_ORANGE,
+            CVD_BLUE,
+            CVD_BLUE,
+            CVD_PURPLE,
+            CVD_MAGENTA,
+        ],
+    };
+    let hsla = Hsla::from(color);
+    if hsla.saturation < CVD_MIN_SATURATION {
+        return color;
+    }
+    let sextant = ((hsla.hue + 30.0).rem_euclid(360.0) / 60.0) as usize % palette.len();
+    let target = Hsla::from(palette[sextant]);
+    Color::from(Hsla::new(
+        target.hue,
+        target.saturation,
+        hsla.lightness,
+        hsla.alpha,
+    ))
+}
+
 fn snap_to_grid_logic(position: Vec2, grid_size: f32) -> Vec2 {
     Vec2::new(
         (position.x / grid_size).round() * grid_size,
@@ -161,20 +1070,30 @@ fn snap_to_grid_logic(position: Vec2, grid_size: f32) -> Vec2 {
     )
 }
 
+#[allow(clippy::too_many_arguments)]
 fn handle_mouse_input(
     mut commands: Commands,
     mouse_button: Res<ButtonInput<MouseButton>>,
-    windows: Query<&Window>,
-    camera_query: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    canvas_cursor: CanvasCursor,
     mut editor_state: ResMut<EditorState>,
     mut skill_tree_data: ResMut<SkillTreeData>,
-    mut connection_mode: ResMut<ConnectionMode>,
-    node_query: Query<(&SkillNode, &Transform)>,
+    node_query: Query<(Entity, &SkillNode, &Transform)>,
     egui_input_state: Res<EguiInputState>,
     keyboard: Res<ButtonInput<KeyCode>>,
     grid_settings: Res<GridSettings>,
+    mut toast: ResMut<TimedToast>,
+    mut context_menu: ResMut<ContextMenuState>,
     node_images: Res<NodeImages>,
+    selected_node: Res<SelectedNode>,
+    picking: CanvasPicking,
+    edit_tools: EditTools,
 ) {
+    let EditTools {
+        mut connection_mode,
+        connection_settings,
+        stamp_mode,
+        mut template_library,
+    } = edit_tools;
     if egui_input_state.wants_pointer_input {
         return;
     }
@@ -185,228 +1104,847 @@ fn handle_mouse_input(
         return;
     }
 
-    let Ok(window) = windows.single() else {
-        return;
-    };
-    let Ok((camera, camera_transform)) = camera_query.single() else {
-        return;
-    };
+    if let Some(cursor_world) = canvas_cursor.world_position() {
+        let world_position = if grid_settings.snap_to_grid {
+            snap_to_grid_logic(cursor_world, grid_settings.grid_size)
+        } else {
+            cursor_world
+        };
 
-    if let Some(cursor_position) = window.cursor_position() {
-        if let Ok(mut world_position) =
-            camera.viewport_to_world_2d(camera_transform, cursor_position)
-        {
-            if grid_settings.snap_to_grid {
-                world_position = snap_to_grid_logic(world_position, grid_settings.grid_size);
-            }
+        if mouse_button.just_pressed(MouseButton::Right) {
+            let clicked_node = picking
+                .node_at(cursor_world, &node_query)
+                .map(|(entity, id, _)| (id, entity));
+
+            // Alt snaps the end of a new connection to the node lying
+            // closest to the cursor along a multiple of the snap angle
+            let alt_pressed =
+                keyboard.pressed(KeyCode::AltLeft) || keyboard.pressed(KeyCode::AltRight);
+            let connection_target = if alt_pressed {
+                connection_mode
+                    .start_node
+                    .and_then(|start_id| {
+                        let entity = *skill_tree_data.nodes.get(&start_id)?;
+                        let (_, start, _) = node_query.get(entity).ok()?;
+                        angle_snap_target(
+                            start_id,
+                            start.data.position,
+                            cursor_world,
+                            connection_settings.angle_snap_degrees,
+                            node_query
+                                .iter()
+                                .map(|(_, node, _)| (node.id, node.data.position)),
+                        )
+                        .1
+                    })
+                    .map(|(id, _)| id)
+            } else {
+                clicked_node.map(|(id, _)| id)
+            };
 
-            if mouse_button.just_pressed(MouseButton::Right) {
-                let mut clicked_node = None;
-                for (node, transform) in node_query.iter() {
-                    let distance = world_position.distance(transform.translation.xy());
-                    if distance < 30.0 {
-                        clicked_node = Some(node.id);
-                        break;
+            if let Some(template) = template_library
+                .placing_template()
+                .filter(|_| !connection_mode.active)
+            {
+                if clicked_node.is_some() {
+                    // Placing stays armed so the next click can go elsewhere
+                    toast.warning("Click empty canvas to place the template");
+                    return;
+                }
+                let mut node_data = mark_created(template.node.clone());
+                node_data.id = editor_state.next_node_id;
+                node_data.position = world_position;
+                let entity = spawn_node(&mut commands, &node_data, &node_images);
+                skill_tree_data.nodes.insert(node_data.id, entity);
+                editor_state.next_node_id += 1;
+                editor_state.dirty = true;
+                template_library.placing = None;
+            } else if stamp_mode.active && !connection_mode.active && clicked_node.is_none() {
+                let source = selected_node
+                    .entity
+                    .and_then(|entity| node_query.get(entity).ok())
+                    .map(|(_, node, _)| node.data.clone());
+                match source {
+                    Some(node_data) => {
+                        let mut node_data = mark_created(node_data);
+                        node_data.name = stamp_name(
+                            &node_data.name,
+                            node_query
+                                .iter()
+                                .map(|(_, node, _)| node.data.name.as_str()),
+                        );
+                        node_data.id = editor_state.next_node_id;
+                        node_data.position = world_position;
+                        let entity = spawn_node(&mut commands, &node_data, &node_images);
+                        skill_tree_data.nodes.insert(node_data.id, entity);
+                        editor_state.next_node_id += 1;
+                        editor_state.dirty = true;
                     }
+                    None => toast.warning("Select a node to stamp copies of"),
+                }
+            } else if !connection_mode.active {
+                context_menu.port = clicked_node
+                    .and_then(|(_, entity)| node_query.get(entity).ok())
+                    .and_then(|(_, node, transform)| port_near(node, transform, cursor_world));
+                let target = if let Some((id, entity)) = clicked_node {
+                    ContextMenuTarget::Node { id, entity }
+                } else if let Some(index) =
+                    picking.connection_at(cursor_world, &skill_tree_data, &node_query)
+                {
+                    ContextMenuTarget::Connection(index)
+                } else {
+                    ContextMenuTarget::Canvas
+                };
+                context_menu.target = Some(target);
+                context_menu.world_position = world_position;
+                context_menu.screen_position = canvas_cursor.menu_position(world_position);
+            } else if let Some(node_id) = connection_target {
+                let start_id = connection_mode.start_node.unwrap_or(node_id);
+                if start_id == node_id {
+                    toast.warning("Cannot connect a node to itself");
+                    connection_mode.active = false;
+                    connection_mode.start_node = None;
+                    return;
                 }
 
-                if let Some(node_id) = clicked_node {
-                    if connection_mode.active && connection_mode.start_node.is_some() {
-                        let start_id = connection_mode.start_node.unwrap();
-                        if start_id != node_id {
-                            // Check if connection already exists (in either direction)
-                            let connection_exists =
-                                skill_tree_data.connections.iter().any(|conn| {
-                                    (conn.from_id == start_id && conn.to_id == node_id)
-                                        || (conn.from_id == node_id && conn.to_id == start_id)
-                                });
-
-                            if !connection_exists {
-                                skill_tree_data.connections.push(ConnectionData {
-                                    from_id: start_id,
-                                    to_id: node_id,
-                                    curve_type: CurveType::Straight,
-                                });
-                                editor_state.dirty = true;
-                            } else {
-                                info!(
-                                    "Connection already exists between nodes {} and {}",
-                                    start_id, node_id
-                                );
-                            }
-                        }
-                        connection_mode.active = false;
-                        connection_mode.start_node = None;
-                    } else {
-                        connection_mode.active = true;
-                        connection_mode.start_node = Some(node_id);
-                    }
-                } else if !connection_mode.active {
-                    let node_data = SkillNodeData {
-                        id: editor_state.next_node_id,
-                        name: format!("Node {}", editor_state.next_node_id),
-                        description: "Node description".to_string(),
-                        image_name: "default_node.png".to_string(),
-                        position: world_position,
-                        node_type: NodeType::Normal,
-                        stats: vec![],
-                    };
+                // Check if connection already exists (in either direction)
+                let connection_exists = skill_tree_data.connections.iter().any(|conn| {
+                    (conn.from_id == start_id && conn.to_id == node_id)
+                        || (conn.from_id == node_id && conn.to_id == start_id)
+                });
 
-                    let entity = spawn_node(&mut commands, &node_data, &node_images);
-                    skill_tree_data.nodes.insert(node_data.id, entity);
-                    editor_state.next_node_id += 1;
+                if !connection_exists {
+                    let to_port = skill_tree_data
+                        .nodes
+                        .get(&node_id)
+                        .and_then(|&entity| node_query.get(entity).ok())
+                        .and_then(|(_, node, transform)| port_near(node, transform, cursor_world));
+                    skill_tree_data.connections.push(ConnectionData {
+                        from_id: start_id,
+                        to_id: node_id,
+                        curve_type: CurveType::Straight,
+                        enabled: true,
+                        from_port: connection_mode.start_port,
+                        to_port,
+                    });
                     editor_state.dirty = true;
                 } else {
-                    connection_mode.active = false;
-                    connection_mode.start_node = None;
+                    info!(
+                        "Connection already exists between nodes {} and {}",
+                        start_id, node_id
+                    );
+                    toast.warning(format!(
+                        "Nodes {} and {} are already connected",
+                        start_id, node_id
+                    ));
                 }
+                connection_mode.active = false;
+                connection_mode.start_node = None;
+            } else {
+                connection_mode.active = false;
+                connection_mode.start_node = None;
             }
         }
     }
 }
 
-fn handle_node_selection(
+/// Starts dragging an end of the selected connection when its grab handle is
+/// pressed, and reconnects that end to the node it's released over.
+/// Releasing over empty canvas or the same node leaves the connection as is.
+#[allow(clippy::too_many_arguments)]
+fn handle_connection_endpoint_drag(
     mouse_button: Res<ButtonInput<MouseButton>>,
-    windows: Query<&Window>,
-    camera_query: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    canvas_cursor: CanvasCursor,
     node_query: Query<(Entity, &SkillNode, &Transform)>,
-    mut selected_node: ResMut<SelectedNode>,
-    mut selected_connection: ResMut<SelectedConnection>,
-    mut drag_state: ResMut<DragState>,
+    selected_connection: Res<SelectedConnection>,
+    mut skill_tree_data: ResMut<SkillTreeData>,
+    mut editor_state: ResMut<EditorState>,
+    mut connection_drag: ResMut<ConnectionDragState>,
     egui_input_state: Res<EguiInputState>,
     keyboard: Res<ButtonInput<KeyCode>>,
+    mut toast: ResMut<TimedToast>,
+    picking: CanvasPicking,
 ) {
-    if !mouse_button.just_pressed(MouseButton::Left) {
-        return;
-    }
+    let cursor_world = canvas_cursor.world_position();
 
-    if egui_input_state.wants_pointer_input {
+    if connection_drag.dragging {
+        if !mouse_button.just_released(MouseButton::Left) {
+            return;
+        }
+        connection_drag.dragging = false;
+
+        let Some((target_entity, target_id, _)) =
+            cursor_world.and_then(|position| picking.node_at(position, &node_query))
+        else {
+            return;
+        };
+        // Dropping on another port of the same node moves the end there
+        let target_port = node_query
+            .get(target_entity)
+            .ok()
+            .zip(cursor_world)
+            .and_then(|((_, node, transform), cursor)| port_near(node, transform, cursor));
+        let index = connection_drag.index;
+        let Some(connection) = skill_tree_data.connections.get(index) else {
+            return;
+        };
+        let (moving_id, fixed_id) = if connection_drag.moving_from {
+            (connection.from_id, connection.to_id)
+        } else {
+            (connection.to_id, connection.from_id)
+        };
+        let moving_port = if connection_drag.moving_from {
+            connection.from_port
+        } else {
+            connection.to_port
+        };
+        if target_id == moving_id && target_port == moving_port {
+            return;
+        }
+        if target_id == fixed_id {
+            toast.warning("Cannot connect a node to itself");
+            return;
+        }
+        let connection_exists =
+            skill_tree_data
+                .connections
+                .iter()
+                .enumerate()
+                .any(|(other, conn)| {
+                    other != index
+                        && ((conn.from_id == fixed_id && conn.to_id == target_id)
+                            || (conn.from_id == target_id && conn.to_id == fixed_id))
+                });
+        if connection_exists && target_id != moving_id {
+            toast.warning(format!(
+                "Nodes {} and {} are already connected",
+                fixed_id, target_id
+            ));
+            return;
+        }
+
+        let connection = &mut skill_tree_data.connections[index];
+        if connection_drag.moving_from {
+            connection.from_id = target_id;
+            connection.from_port = target_port;
+        } else {
+            connection.to_id = target_id;
+            connection.to_port = target_port;
+        }
+        editor_state.dirty = true;
         return;
     }
 
     let shift_pressed =
         keyboard.pressed(KeyCode::ShiftLeft) || keyboard.pressed(KeyCode::ShiftRight);
-    if shift_pressed {
+    if !mouse_button.just_pressed(MouseButton::Left)
+        || egui_input_state.wants_pointer_input
+        || shift_pressed
+    {
         return;
     }
-
-    let Ok(window) = windows.single() else {
+    let (Some(cursor_world), Some(index)) = (cursor_world, selected_connection.index) else {
         return;
     };
-
-    let Ok((camera, camera_transform)) = camera_query.single() else {
+    let Some(connection) = skill_tree_data.connections.get(index) else {
         return;
     };
+    let position = |id, port| {
+        node_query
+            .iter()
+            .find(|(_, node, _)| node.id == id)
+            .map(|(_, node, transform)| attach_point(node, transform, port))
+    };
+    let (Some(from), Some(to)) = (
+        position(connection.from_id, connection.from_port),
+        position(connection.to_id, connection.to_port),
+    ) else {
+        return;
+    };
+    // Handles are drawn on the bundled, shifted line
+    let offset = picking.layout.offset(index);
+    let (from, to) = (from + offset, to + offset);
+
+    let [from_handle, to_handle] = endpoint_handles(from, to, &connection.curve_type);
+    // Leave some slack around the drawn circle so the handle is easy to grab
+    let grab_radius = ENDPOINT_HANDLE_RADIUS * 1.5;
+    if cursor_world.distance(from_handle) < grab_radius {
+        *connection_drag = ConnectionDragState {
+            dragging: true,
+            index,
+            moving_from: true,
+        };
+    } else if cursor_world.distance(to_handle) < grab_radius {
+        *connection_drag = ConnectionDragState {
+            dragging: true,
+            index,
+            moving_from: false,
+        };
+    }
+}
 
-    if let Some(cursor_position) = window.cursor_position() {
-        if let Ok(world_position) = camera.viewport_to_world_2d(camera_transform, cursor_position) {
-            let mut closest_node = None;
-            let mut closest_distance = f32::MAX;
-
-            for (entity, node, transform) in node_query.iter() {
-                let distance = world_position.distance(transform.translation.xy());
-                if distance < 30.0 && distance < closest_distance {
-                    closest_distance = distance;
-                    closest_node = Some((entity, node.id, transform.translation.xy()));
-                }
-            }
-
-            if let Some((entity, id, node_pos)) = closest_node {
-                selected_node.entity = Some(entity);
-                selected_node.id = Some(id);
-                selected_connection.index = None;
-                drag_state.dragging = true;
-                drag_state.offset = node_pos - world_position;
-            } else {
-                selected_node.entity = None;
-                selected_node.id = None;
+/// Grab handles of a connection, just outside its `from` and `to` nodes
+fn endpoint_handles(from: Vec2, to: Vec2, curve_type: &CurveType) -> [Vec2; 2] {
+    let mut reversed = curve_type.clone();
+    reversed.reverse();
+    [
+        point_along_connection(from, to, curve_type, ENDPOINT_HANDLE_DISTANCE),
+        point_along_connection(to, from, &reversed, ENDPOINT_HANDLE_DISTANCE),
+    ]
+}
+
+/// Point `distance` along a connection from `start`, following the arc when
+/// it is valid
+fn point_along_connection(start: Vec2, end: Vec2, curve_type: &CurveType, distance: f32) -> Vec2 {
+    match curve_type {
+        CurveType::Arc {
+            radius, clockwise, ..
+        } => {
+            if let Some((center, start_angle, _)) =
+                calculate_arc_center(start, end, *radius, *clockwise)
+            {
+                let sweep = distance / radius;
+                let angle = if *clockwise {
+                    start_angle - sweep
+                } else {
+                    start_angle + sweep
+                };
+                return center + Vec2::from_angle(angle) * radius;
             }
         }
+        CurveType::CatmullRom { waypoints } => {
+            return point_along_polyline(&catmull_rom_polyline(start, end, waypoints), distance);
+        }
+        CurveType::Straight => {}
     }
+    start + (end - start).normalize_or_zero() * distance
 }
 
-fn handle_connection_selection(
+/// Shape handles of a connection. A curve has one on each waypoint, followed
+/// by one halfway along each span, which adds a waypoint there when dragged.
+/// A straight connection only has the one halfway along, and arcs have none.
+fn waypoint_handles(from: Vec2, to: Vec2, curve_type: &CurveType) -> Vec<Vec2> {
+    match curve_type {
+        CurveType::Straight => vec![(from + to) * 0.5],
+        CurveType::Arc { .. } => Vec::new(),
+        CurveType::CatmullRom { waypoints } => {
+            let points = curve_points(from, to, waypoints);
+            let span_midpoints =
+                (0..points.len() - 1).map(|span| catmull_rom_point(&points, span, 0.5));
+            waypoints.iter().copied().chain(span_midpoints).collect()
+        }
+    }
+}
+
+/// Waypoint moved by dragging `handle`, as numbered by `waypoint_handles`.
+/// A straight connection becomes a curve through `position`, and a span
+/// handle adds a waypoint at `position`.
+fn grab_waypoint(curve_type: &mut CurveType, handle: usize, position: Vec2) -> Option<usize> {
+    match curve_type {
+        CurveType::Straight => {
+            *curve_type = CurveType::CatmullRom {
+                waypoints: vec![position],
+            };
+            Some(0)
+        }
+        CurveType::Arc { .. } => None,
+        CurveType::CatmullRom { waypoints } if handle < waypoints.len() => Some(handle),
+        CurveType::CatmullRom { waypoints } => {
+            let span = (handle - waypoints.len()).min(waypoints.len());
+            waypoints.insert(span, position);
+            Some(span)
+        }
+    }
+}
+
+/// Drags the shape handles of the selected connection. The connection only
+/// changes once the handle actually moves, so clicking the middle of a
+/// straight connection leaves it straight.
+#[allow(clippy::too_many_arguments)]
+fn handle_connection_waypoint_drag(
     mouse_button: Res<ButtonInput<MouseButton>>,
-    windows: Query<&Window>,
-    camera_query: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
-    skill_tree_data: Res<SkillTreeData>,
-    node_query: Query<(&SkillNode, &Transform)>,
-    mut selected_connection: ResMut<SelectedConnection>,
-    mut selected_node: ResMut<SelectedNode>,
+    canvas_cursor: CanvasCursor,
+    node_query: Query<(Entity, &SkillNode, &Transform)>,
+    selected_connection: Res<SelectedConnection>,
+    mut skill_tree_data: ResMut<SkillTreeData>,
+    mut editor_state: ResMut<EditorState>,
+    mut handle_drag: ResMut<ConnectionHandleDragState>,
+    connection_drag: Res<ConnectionDragState>,
     egui_input_state: Res<EguiInputState>,
     keyboard: Res<ButtonInput<KeyCode>>,
+    layout: ConnectionLayout,
 ) {
-    if !mouse_button.just_pressed(MouseButton::Left) {
-        return;
-    }
+    let cursor_world = canvas_cursor.world_position();
 
-    if egui_input_state.wants_pointer_input {
+    if handle_drag.dragging {
+        if !mouse_button.pressed(MouseButton::Left) {
+            handle_drag.dragging = false;
+            return;
+        }
+        let Some(cursor_world) = cursor_world else {
+            return;
+        };
+        if handle_drag.waypoint.is_none() && cursor_world == handle_drag.grab_position {
+            return;
+        }
+        let Some(connection) = skill_tree_data.connections.get_mut(handle_drag.index) else {
+            handle_drag.dragging = false;
+            return;
+        };
+        let waypoint = match handle_drag.waypoint {
+            Some(waypoint) => waypoint,
+            None => {
+                let Some(waypoint) =
+                    grab_waypoint(&mut connection.curve_type, handle_drag.handle, cursor_world)
+                else {
+                    handle_drag.dragging = false;
+                    return;
+                };
+                handle_drag.waypoint = Some(waypoint);
+                waypoint
+            }
+        };
+        if let CurveType::CatmullRom { waypoints } = &mut connection.curve_type
+            && let Some(position) = waypoints.get_mut(waypoint)
+            && *position != cursor_world
+        {
+            *position = cursor_world;
+            editor_state.dirty = true;
+        }
         return;
     }
 
     let shift_pressed =
         keyboard.pressed(KeyCode::ShiftLeft) || keyboard.pressed(KeyCode::ShiftRight);
-    if shift_pressed {
+    if !mouse_button.just_pressed(MouseButton::Left)
+        || egui_input_state.wants_pointer_input
+        || shift_pressed
+        || connection_drag.dragging
+    {
         return;
     }
+    let (Some(cursor_world), Some(index)) = (cursor_world, selected_connection.index) else {
+        return;
+    };
+    let Some(connection) = skill_tree_data.connections.get(index) else {
+        return;
+    };
+    let position = |id, port| {
+        node_query
+            .iter()
+            .find(|(_, node, _)| node.id == id)
+            .map(|(_, node, transform)| attach_point(node, transform, port))
+    };
+    let (Some(from), Some(to)) = (
+        position(connection.from_id, connection.from_port),
+        position(connection.to_id, connection.to_port),
+    ) else {
+        return;
+    };
+    let offset = layout.offset(index);
+    let (from, to) = (from + offset, to + offset);
+
+    let grab_radius = WAYPOINT_HANDLE_RADIUS * 1.5;
+    let Some(handle) = waypoint_handles(from, to, &connection.curve_type)
+        .iter()
+        .position(|handle| cursor_world.distance(*handle) < grab_radius)
+    else {
+        return;
+    };
+    *handle_drag = ConnectionHandleDragState {
+        dragging: true,
+        index,
+        handle,
+        grab_position: cursor_world,
+        waypoint: None,
+    };
+}
+
+/// Name for a stamped copy of the node named `name`: its name without any
+/// number on the end, numbered one past the highest copy in `existing`. The
+/// unnumbered original counts as the first.
+fn stamp_name<'a>(name: &str, existing: impl IntoIterator<Item = &'a str>) -> String {
+    let base = match name.rsplit_once(' ') {
+        Some((base, number)) if number.parse::<u32>().is_ok() => base,
+        _ => name,
+    };
+    let highest = existing
+        .into_iter()
+        .filter_map(|existing| {
+            if existing == base {
+                return Some(1);
+            }
+            existing
+                .strip_prefix(base)?
+                .strip_prefix(' ')?
+                .parse::<u32>()
+                .ok()
+        })
+        .max()
+        .unwrap_or(1);
+    format!("{} {}", base, highest + 1)
+}
+
+/// Spawns a new default node at `position` and registers it in the tree
+fn create_node_at(
+    commands: &mut Commands,
+    editor_state: &mut EditorState,
+    skill_tree_data: &mut SkillTreeData,
+    node_images: &NodeImages,
+    position: Vec2,
+) {
+    let node_data = SkillNodeData {
+        id: editor_state.next_node_id,
+        name: format!("Node {}", editor_state.next_node_id),
+        description: "Node description".to_string(),
+        image_name: NodeImages::PLACEHOLDER.to_string(),
+        position,
+        node_type: NodeType::default(),
+        stats: vec![],
+        cost: 1,
+        tier: 0,
+        z_order: 0.0,
+        created_at: 0,
+        modified_at: 0,
+        ports: vec![],
+    };
+    let node_data = mark_created(node_data);
 
-    let Ok(window) = windows.single() else {
+    let entity = spawn_node(commands, &node_data, node_images);
+    skill_tree_data.nodes.insert(node_data.id, entity);
+    editor_state.next_node_id += 1;
+    editor_state.dirty = true;
+}
+
+/// Despawns a node, dropping its connections and any selection of it
+fn delete_node(
+    commands: &mut Commands,
+    skill_tree_data: &mut SkillTreeData,
+    selected_node: &mut SelectedNode,
+    multi_selected: &mut MultiSelectedNodes,
+    node_id: u32,
+    entity: Entity,
+) {
+    skill_tree_data
+        .connections
+        .retain(|conn| conn.from_id != node_id && conn.to_id != node_id);
+    skill_tree_data.nodes.remove(&node_id);
+    multi_selected.nodes.remove(&node_id);
+    commands.entity(entity).despawn();
+    if selected_node.id == Some(node_id) {
+        selected_node.entity = None;
+        selected_node.id = None;
+    }
+}
+
+/// World position a connection end attaches to: the given port of the node,
+/// or its center
+fn attach_point(node: &SkillNode, transform: &Transform, port: Option<u32>) -> Vec2 {
+    transform.translation.xy() + node.data.port_offset(port)
+}
+
+/// The node's port nearest `world_position`, if one is within
+/// `PORT_SNAP_DISTANCE`
+fn port_near(node: &SkillNode, transform: &Transform, world_position: Vec2) -> Option<u32> {
+    let local = world_position - transform.translation.xy();
+    node.data
+        .ports
+        .iter()
+        .map(|port| (port.id, port.local_offset.distance(local)))
+        .filter(|(_, distance)| *distance <= PORT_SNAP_DISTANCE)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(id, _)| id)
+}
+
+/// Marks the ports of the selected node, and of every node while a
+/// connection is being made, so there's something to aim at
+fn draw_ports(
+    mut gizmos: Gizmos,
+    node_query: Query<(&SkillNode, &Transform, &Visibility)>,
+    selected_node: Res<SelectedNode>,
+    connection_mode: Res<ConnectionMode>,
+    connection_drag: Res<ConnectionDragState>,
+) {
+    let connecting = connection_mode.active || connection_drag.dragging;
+    for (node, transform, visibility) in node_query.iter() {
+        if *visibility == Visibility::Hidden || !(connecting || selected_node.id == Some(node.id)) {
+            continue;
+        }
+        for port in &node.data.ports {
+            gizmos.circle_2d(
+                transform.translation.xy() + port.local_offset,
+                PORT_RADIUS,
+                PORT_COLOR,
+            );
+        }
+    }
+}
+
+/// Shows the placement preview at the snapped cursor position while it is
+/// over empty canvas and right-clicking would offer a new node there
+#[allow(clippy::too_many_arguments)]
+fn update_placement_ghost(
+    canvas_cursor: CanvasCursor,
+    egui_input_state: Res<EguiInputState>,
+    connection_mode: Res<ConnectionMode>,
+    hovered_node: Res<HoveredNode>,
+    drag_state: Res<DragState>,
+    context_menu: Res<ContextMenuState>,
+    png_export_state: Res<PngExportState>,
+    grid_settings: Res<GridSettings>,
+    connection_drag: Res<ConnectionDragState>,
+    handle_drag: Res<ConnectionHandleDragState>,
+    mut ghost_query: Query<(&mut Transform, &mut Visibility), With<GhostNodeSprite>>,
+) {
+    let Ok((mut transform, mut visibility)) = ghost_query.single_mut() else {
         return;
     };
 
-    let Ok((camera, camera_transform)) = camera_query.single() else {
+    let blocked = egui_input_state.wants_pointer_input
+        || connection_mode.active
+        || hovered_node.entity.is_some()
+        || drag_state.dragging
+        || connection_drag.dragging
+        || handle_drag.dragging
+        || context_menu.target.is_some()
+        || png_export_state.job.is_some();
+    let cursor_world = canvas_cursor.world_position().filter(|_| !blocked);
+
+    let Some(mut position) = cursor_world else {
+        visibility.set_if_neq(Visibility::Hidden);
         return;
     };
+    if grid_settings.snap_to_grid {
+        position = snap_to_grid_logic(position, grid_settings.grid_size);
+    }
+    transform.translation = position.extend(transform.translation.z);
+    visibility.set_if_neq(Visibility::Inherited);
+}
 
-    if let Some(cursor_position) = window.cursor_position() {
-        if let Ok(world_position) = camera.viewport_to_world_2d(camera_transform, cursor_position) {
-            // Check if we're clicking on a node first
-            for (_, transform) in node_query.iter() {
-                let distance = world_position.distance(transform.translation.xy());
-                if distance < 30.0 {
-                    return; // Clicking on a node, don't select connection
-                }
-            }
+fn update_cursor_world_pos(
+    canvas_cursor: CanvasCursor,
+    mut cursor_world_pos: ResMut<CursorWorldPos>,
+) {
+    // Read every frame rather than on cursor movement, so panning with the
+    // keyboard or zooming keeps it current
+    let position = canvas_cursor.world_position();
+    if cursor_world_pos.0 != position {
+        cursor_world_pos.0 = position;
+    }
+}
 
-            // Check connections
-            for (index, connection) in skill_tree_data.connections.iter().enumerate() {
-                let mut from_pos = None;
-                let mut to_pos = None;
+fn update_hovered_node(
+    canvas_cursor: CanvasCursor,
+    node_query: Query<(Entity, &SkillNode, &Transform)>,
+    mut hovered_node: ResMut<HoveredNode>,
+    egui_input_state: Res<EguiInputState>,
+    picking: CanvasPicking,
+) {
+    let hovered = canvas_cursor
+        .world_position()
+        .filter(|_| !egui_input_state.wants_pointer_input)
+        .and_then(|world_position| picking.node_at(world_position, &node_query));
+
+    let (entity, id) = hovered.map_or((None, None), |(entity, id, _)| (Some(entity), Some(id)));
+    if hovered_node.entity != entity {
+        hovered_node.entity = entity;
+        hovered_node.id = id;
+    }
+}
 
-                for (node, transform) in node_query.iter() {
-                    if node.id == connection.from_id {
-                        from_pos = Some(transform.translation.xy());
-                    }
-                    if node.id == connection.to_id {
-                        to_pos = Some(transform.translation.xy());
-                    }
-                }
+/// Connection that clicking would select, set without clicking. Nodes take
+/// clicks first, so nothing is hovered while the cursor is over one.
+fn update_hovered_connection(
+    canvas_cursor: CanvasCursor,
+    skill_tree_data: Res<SkillTreeData>,
+    node_query: Query<(Entity, &SkillNode, &Transform)>,
+    mut hovered_connection: ResMut<HoveredConnection>,
+    hovered_node: Res<HoveredNode>,
+    egui_input_state: Res<EguiInputState>,
+    picking: CanvasPicking,
+) {
+    let index = canvas_cursor
+        .world_position()
+        .filter(|_| !egui_input_state.wants_pointer_input && hovered_node.entity.is_none())
+        .and_then(|world_position| {
+            picking.connection_at(world_position, &skill_tree_data, &node_query)
+        });
+    if hovered_connection.index != index {
+        hovered_connection.index = index;
+    }
+}
 
-                if let (Some(from), Some(to)) = (from_pos, to_pos) {
-                    let distance = match &connection.curve_type {
-                        CurveType::Straight => point_to_line_distance(world_position, from, to),
-                        CurveType::Arc { radius, clockwise } => {
-                            // If arc is invalid, fall back to straight line distance
-                            point_to_arc_distance(world_position, from, to, *radius, *clockwise)
-                                .unwrap_or_else(|| point_to_line_distance(world_position, from, to))
-                        }
-                    };
+#[allow(clippy::too_many_arguments)]
+fn handle_node_selection(
+    mouse_button: Res<ButtonInput<MouseButton>>,
+    canvas_cursor: CanvasCursor,
+    node_query: Query<(Entity, &SkillNode, &Transform)>,
+    selection: Selection,
+    mut drag_state: ResMut<DragState>,
+    egui_input_state: Res<EguiInputState>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+    mut double_click: ResMut<DoubleClickState>,
+    mut node_edit_dialog: ResMut<NodeEditDialog>,
+    connection_drag: Res<ConnectionDragState>,
+    handle_drag: Res<ConnectionHandleDragState>,
+    picking: CanvasPicking,
+) {
+    let Selection {
+        mut selected_node,
+        mut selected_connection,
+        mut multi_selected,
+    } = selection;
+    if !mouse_button.just_pressed(MouseButton::Left)
+        || connection_drag.dragging
+        || handle_drag.dragging
+    {
+        return;
+    }
 
-                    if distance < 10.0 {
-                        selected_connection.index = Some(index);
-                        selected_node.entity = None;
-                        selected_node.id = None;
-                        return;
-                    }
+    if egui_input_state.wants_pointer_input {
+        return;
+    }
+
+    let shift_pressed =
+        keyboard.pressed(KeyCode::ShiftLeft) || keyboard.pressed(KeyCode::ShiftRight);
+    if shift_pressed {
+        return;
+    }
+
+    if let Some(world_position) = canvas_cursor.world_position() {
+        let closest_node = picking.node_at(world_position, &node_query);
+
+        let ctrl_pressed =
+            keyboard.pressed(KeyCode::ControlLeft) || keyboard.pressed(KeyCode::ControlRight);
+
+        if let Some((entity, id, node_pos)) = closest_node {
+            if ctrl_pressed {
+                // Ctrl+click toggles nodes in the group, starting it from
+                // the current primary selection
+                if multi_selected.nodes.is_empty()
+                    && let Some(primary_id) = selected_node.id
+                {
+                    multi_selected.nodes.insert(primary_id);
+                }
+                if !multi_selected.nodes.remove(&id) {
+                    multi_selected.nodes.insert(id);
                 }
+            } else if !multi_selected.nodes.contains(&id) {
+                multi_selected.nodes.clear();
             }
 
-            // Didn't click on anything
+            let now = time.elapsed_secs_f64();
+            let is_double_click = double_click.last_click.is_some_and(|(last_id, last_time)| {
+                last_id == id && now - last_time <= DoubleClickState::THRESHOLD
+            });
+            if is_double_click && !ctrl_pressed {
+                double_click.last_click = None;
+                if let Ok((_, node, _)) = node_query.get(entity) {
+                    node_edit_dialog.node = Some((entity, node.data.clone()));
+                }
+            } else {
+                double_click.last_click = Some((id, now));
+            }
+
+            selected_node.entity = Some(entity);
+            selected_node.id = Some(id);
             selected_connection.index = None;
+            drag_state.dragging = true;
+            drag_state.offset = node_pos - world_position;
+            drag_state.group_offsets = if multi_selected.nodes.contains(&id) {
+                node_query
+                    .iter()
+                    .filter(|(_, node, _)| node.id != id && multi_selected.nodes.contains(&node.id))
+                    .map(|(_, node, _)| (node.id, node.data.position - node_pos))
+                    .collect()
+            } else {
+                HashMap::new()
+            };
+        } else {
+            selected_node.entity = None;
+            selected_node.id = None;
+            multi_selected.nodes.clear();
+            double_click.last_click = None;
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
+fn handle_connection_selection(
+    mouse_button: Res<ButtonInput<MouseButton>>,
+    canvas_cursor: CanvasCursor,
+    skill_tree_data: Res<SkillTreeData>,
+    node_query: Query<(Entity, &SkillNode, &Transform)>,
+    mut selected_connection: ResMut<SelectedConnection>,
+    mut selected_node: ResMut<SelectedNode>,
+    egui_input_state: Res<EguiInputState>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    connection_drag: Res<ConnectionDragState>,
+    handle_drag: Res<ConnectionHandleDragState>,
+    picking: CanvasPicking,
+) {
+    if !mouse_button.just_pressed(MouseButton::Left)
+        || connection_drag.dragging
+        || handle_drag.dragging
+    {
+        return;
+    }
+
+    if egui_input_state.wants_pointer_input {
+        return;
+    }
+
+    let shift_pressed =
+        keyboard.pressed(KeyCode::ShiftLeft) || keyboard.pressed(KeyCode::ShiftRight);
+    if shift_pressed {
+        return;
+    }
+
+    if let Some(world_position) = canvas_cursor.world_position() {
+        // Clicking on a node, don't select a connection
+        if picking.node_at(world_position, &node_query).is_some() {
+            return;
+        }
+
+        if let Some(index) = picking.connection_at(world_position, &skill_tree_data, &node_query) {
+            selected_connection.index = Some(index);
+            selected_node.entity = None;
+            selected_node.id = None;
+            return;
+        }
+
+        // Didn't click on anything
+        selected_connection.index = None;
+    }
+}
+
+/// Rebuilds the spatial hash whenever a node moves, spawns or despawns
+fn rebuild_spatial_hash(
+    mut spatial_hash: ResMut<SpatialHashGrid>,
+    node_query: Query<(Entity, &SkillNode, &Transform)>,
+    changed_query: Query<(), (With<SkillNode>, Changed<Transform>)>,
+    mut removed_nodes: RemovedComponents<SkillNode>,
+) {
+    let nodes_removed = removed_nodes.read().count() > 0;
+    if changed_query.is_empty() && !nodes_removed {
+        return;
+    }
+
+    spatial_hash.cells.clear();
+    for (entity, node, transform) in node_query.iter() {
+        spatial_hash.insert(transform.translation.xy(), entity, node.id);
+    }
+}
+
+/// Distance from `point` to the nearest segment of `polyline`
+fn polyline_distance(point: Vec2, polyline: &[Vec2]) -> f32 {
+    polyline
+        .windows(2)
+        .map(|segment| point_to_line_distance(point, segment[0], segment[1]))
+        .fold(f32::MAX, f32::min)
+}
+
 fn point_to_line_distance(point: Vec2, line_start: Vec2, line_end: Vec2) -> f32 {
     let line_vec = line_end - line_start;
     let point_vec = point - line_start;
@@ -459,10 +1997,10 @@ fn point_to_arc_distance(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn handle_node_dragging(
     mouse_button: Res<ButtonInput<MouseButton>>,
-    windows: Query<&Window>,
-    camera_query: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    canvas_cursor: CanvasCursor,
     mut node_query: Query<(&mut Transform, &mut SkillNode)>,
     selected_node: Res<SelectedNode>,
     mut drag_state: ResMut<DragState>,
@@ -492,86 +2030,664 @@ fn handle_node_dragging(
         return;
     }
 
-    let Ok(window) = windows.single() else {
-        return;
-    };
-    let Ok((camera, camera_transform)) = camera_query.single() else {
-        return;
-    };
-
-    if let Some(entity) = selected_node.entity {
-        if let Some(cursor_position) = window.cursor_position() {
-            if let Ok(world_position) =
-                camera.viewport_to_world_2d(camera_transform, cursor_position)
-            {
-                if let Ok((mut transform, mut node)) = node_query.get_mut(entity) {
-                    let mut new_position = world_position + drag_state.offset;
-                    if grid_settings.snap_to_grid {
-                        new_position = snap_to_grid_logic(new_position, grid_settings.grid_size);
-                    }
-                    transform.translation = new_position.extend(0.0);
-                    node.data.position = new_position;
-                    editor_state.dirty = true;
+    if let Some(entity) = selected_node.entity
+        && let Some(world_position) = canvas_cursor.world_position()
+        && let Ok((mut transform, mut node)) = node_query.get_mut(entity)
+    {
+        let raw_position = world_position + drag_state.offset;
+        let new_position = if grid_settings.snap_to_grid {
+            snap_to_grid_logic(raw_position, grid_settings.grid_size)
+        } else {
+            raw_position
+        };
+        drag_state.raw_position = raw_position;
+        drag_state.snap_delta = new_position - raw_position;
+        let moved = new_position != node.data.position;
+        transform.translation = new_position.extend(transform.translation.z);
+        node.data.position = new_position;
+        editor_state.dirty = true;
+
+        // The rest of a multi-selection keeps its offset from the
+        // dragged node, so only the dragged node is snapped
+        if moved && !drag_state.group_offsets.is_empty() {
+            for (mut transform, mut node) in node_query.iter_mut() {
+                if let Some(offset) = drag_state.group_offsets.get(&node.id) {
+                    node.data.position = new_position + *offset;
+                    transform.translation = node.data.position.extend(transform.translation.z);
                 }
             }
         }
     }
 }
 
+/// Dashed line from where a dragged node would be without snapping to where
+/// snapping puts it
+fn draw_snap_offset(mut gizmos: Gizmos, drag_state: Res<DragState>) {
+    if drag_state.dragging && drag_state.snap_delta != Vec2::ZERO {
+        draw_dashed_line(
+            &mut gizmos,
+            drag_state.raw_position,
+            drag_state.raw_position + drag_state.snap_delta,
+            SNAP_OFFSET_COLOR,
+        );
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn handle_keyboard_shortcuts(
     mut commands: Commands,
     keyboard: Res<ButtonInput<KeyCode>>,
     mut selected_node: ResMut<SelectedNode>,
     mut selected_connection: ResMut<SelectedConnection>,
+    mut multi_selected: ResMut<MultiSelectedNodes>,
     mut skill_tree_data: ResMut<SkillTreeData>,
     egui_input_state: Res<EguiInputState>,
     mut editor_state: ResMut<EditorState>,
+    mut compare_tree: ResMut<CompareTree>,
+    mut inline_rename: ResMut<InlineRename>,
+    mut editor_camera: ResMut<EditorCamera>,
+    mut template_library: ResMut<TemplateLibrary>,
+    mut toast: ResMut<TimedToast>,
+    node_query: Query<&SkillNode>,
+    mut stamp_mode: ResMut<StampMode>,
 ) {
     if egui_input_state.wants_keyboard_input {
         return;
     }
 
+    if keyboard.just_pressed(KeyCode::F11) {
+        editor_state.toggle_presentation_mode(&mut toast);
+    }
+
+    if keyboard.just_pressed(KeyCode::NumpadDecimal) {
+        editor_camera.reset_view();
+    }
+    if keyboard.just_pressed(KeyCode::Numpad1) {
+        editor_camera.reset_zoom();
+    }
+
+    if keyboard.just_pressed(KeyCode::F2)
+        && let Some(node) = selected_node
+            .entity
+            .and_then(|entity| node_query.get(entity).ok().map(|node| (entity, node)))
+    {
+        inline_rename.node = Some((node.0, node.1.data.name.clone()));
+        inline_rename.focused = false;
+    }
+
+    if keyboard.just_pressed(KeyCode::Escape) {
+        template_library.placing = None;
+        stamp_mode.active = false;
+    }
+
+    if keyboard.just_pressed(KeyCode::Escape) && compare_tree.is_active() {
+        compare_tree.close();
+    }
+
     if keyboard.just_pressed(KeyCode::Backspace) || keyboard.just_pressed(KeyCode::Delete) {
         if let Some(node_id_to_delete) = selected_node.id {
             if let Some(entity_to_delete) = selected_node.entity {
-                skill_tree_data.connections.retain(|conn| {
-                    conn.from_id != node_id_to_delete && conn.to_id != node_id_to_delete
-                });
-                skill_tree_data.nodes.remove(&node_id_to_delete);
+                delete_node(
+                    &mut commands,
+                    &mut skill_tree_data,
+                    &mut selected_node,
+                    &mut multi_selected,
+                    node_id_to_delete,
+                    entity_to_delete,
+                );
+                editor_state.dirty = true;
+            }
+        } else if let Some(connection_index) = selected_connection.index
+            && connection_index < skill_tree_data.connections.len()
+        {
+            skill_tree_data.connections.remove(connection_index);
+            selected_connection.index = None;
+            editor_state.dirty = true;
+        }
+    }
+}
 
-                commands.entity(entity_to_delete).despawn();
+/// Tab and Shift+Tab cycle keyboard focus through nodes in id order, the
+/// arrow keys move it to the nearest node in that direction, Space selects
+/// the focused node and Enter opens its edit dialog
+#[allow(clippy::too_many_arguments)]
+fn handle_keyboard_navigation(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    egui_input_state: Res<EguiInputState>,
+    mut keyboard_navigation: ResMut<KeyboardNavigation>,
+    node_query: Query<(Entity, &SkillNode)>,
+    tier_filter: Res<TierFilter>,
+    mut selected_node: ResMut<SelectedNode>,
+    mut selected_connection: ResMut<SelectedConnection>,
+    mut multi_selected: ResMut<MultiSelectedNodes>,
+    mut node_edit_dialog: ResMut<NodeEditDialog>,
+    mut editor_camera: ResMut<EditorCamera>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+) {
+    let mut nodes: Vec<(Entity, u32, Vec2)> = node_query
+        .iter()
+        .filter(|(_, node)| !tier_filter.hides(node.data.tier))
+        .map(|(entity, node)| (entity, node.id, node.data.position))
+        .collect();
+    nodes.sort_by_key(|&(_, id, _)| id);
+
+    // Focus is dropped when its node is deleted or filtered out
+    let focused = keyboard_navigation
+        .focused
+        .and_then(|id| nodes.iter().position(|&(_, node_id, _)| node_id == id));
+    if focused.is_none() && keyboard_navigation.focused.is_some() {
+        keyboard_navigation.focused = None;
+    }
+    if egui_input_state.wants_keyboard_input || nodes.is_empty() {
+        return;
+    }
 
-                selected_node.entity = None;
-                selected_node.id = None;
-                editor_state.dirty = true;
+    if keyboard.just_pressed(KeyCode::Escape) {
+        keyboard_navigation.focused = None;
+        return;
+    }
+
+    let mut next = None;
+    if keyboard.just_pressed(KeyCode::Tab) {
+        let shift_pressed =
+            keyboard.pressed(KeyCode::ShiftLeft) || keyboard.pressed(KeyCode::ShiftRight);
+        next = Some(match (focused, shift_pressed) {
+            (None, false) => 0,
+            (None, true) => nodes.len() - 1,
+            (Some(index), false) => (index + 1) % nodes.len(),
+            (Some(index), true) => (index + nodes.len() - 1) % nodes.len(),
+        });
+    }
+
+    if let Some(index) = focused {
+        let direction = [
+            (KeyCode::ArrowLeft, Vec2::NEG_X),
+            (KeyCode::ArrowRight, Vec2::X),
+            (KeyCode::ArrowUp, Vec2::Y),
+            (KeyCode::ArrowDown, Vec2::NEG_Y),
+        ]
+        .into_iter()
+        .find(|(key, _)| keyboard.just_pressed(*key))
+        .map(|(_, direction)| direction);
+        if let Some(direction) = direction {
+            let origin = nodes[index].2;
+            let min_cos = FOCUS_DIRECTION_CONE.to_radians().cos();
+            next = nodes
+                .iter()
+                .enumerate()
+                .filter(|&(other, &(_, _, position))| {
+                    let offset = position - origin;
+                    other != index
+                        && offset != Vec2::ZERO
+                        && offset.normalize().dot(direction) >= min_cos
+                })
+                .min_by(|(_, a), (_, b)| {
+                    a.2.distance_squared(origin)
+                        .total_cmp(&b.2.distance_squared(origin))
+                })
+                .map(|(other, _)| other)
+                .or(next);
+        }
+
+        let (entity, id, _) = nodes[index];
+        if keyboard.just_pressed(KeyCode::Space) {
+            selected_node.entity = Some(entity);
+            selected_node.id = Some(id);
+            selected_connection.index = None;
+            multi_selected.nodes.clear();
+        }
+        if keyboard.just_pressed(KeyCode::Enter)
+            && let Ok((_, node)) = node_query.get(entity)
+        {
+            node_edit_dialog.node = Some((entity, node.data.clone()));
+        }
+    }
+
+    if let Some(index) = next {
+        let (_, id, position) = nodes[index];
+        keyboard_navigation.focused = Some(id);
+        // Recenter on the newly focused node when it's near or past the edge
+        let viewport = windows.single().map_or(Vec2::ONE, |window| window.size());
+        let half_extent = viewport * editor_camera.zoom * FOCUS_VISIBLE_FRACTION / 2.0;
+        let offset = (position - editor_camera.pan_offset).abs();
+        if offset.x > half_extent.x || offset.y > half_extent.y {
+            editor_camera.pan_offset = position;
+        }
+    }
+}
+
+/// Outlines the keyboard-focused node with a rectangle, drawn over any
+/// selection tint so focus stays visible on a selected node
+fn draw_focus_outline(
+    mut gizmos: Gizmos,
+    keyboard_navigation: Res<KeyboardNavigation>,
+    node_query: Query<(&SkillNode, &Transform)>,
+    node_type_styles: Res<NodeTypeStyles>,
+) {
+    let Some(focused) = keyboard_navigation.focused else {
+        return;
+    };
+    if let Some((node, transform)) = node_query.iter().find(|(node, _)| node.id == focused) {
+        let size = node_type_styles.node_size(&node.data) + 2.0 * FOCUS_OUTLINE_PADDING;
+        gizmos.rect_2d(
+            transform.translation.xy(),
+            Vec2::splat(size),
+            FOCUS_OUTLINE_COLOR,
+        );
+    }
+}
+
+fn compute_reachability(
+    mut reachability: ResMut<ReachabilitySet>,
+    skill_tree_data: Res<SkillTreeData>,
+    node_query: Query<&SkillNode>,
+) {
+    if !reachability.enabled {
+        return;
+    }
+
+    let start_ids = node_query
+        .iter()
+        .filter(|node| node.data.node_type.is_builtin(BuiltinNodeType::Start))
+        .map(|node| node.id);
+    let adjacency = graph::undirected_adjacency(&skill_tree_data.connections);
+    reachability.reachable = graph::reachable_from(start_ids, &adjacency);
+}
+
+fn compute_centrality(
+    mut centrality: ResMut<CentralityColoring>,
+    skill_tree_data: Res<SkillTreeData>,
+) {
+    if !centrality.enabled || (!centrality.is_changed() && !skill_tree_data.is_changed()) {
+        return;
+    }
+
+    let node_ids: Vec<u32> = skill_tree_data.nodes.keys().copied().collect();
+    let adjacency = graph::undirected_adjacency(&skill_tree_data.connections);
+    centrality.values =
+        graph::approximate_betweenness(&node_ids, &adjacency, CentralityColoring::SAMPLES);
+}
+
+fn compute_dependency_highlight(
+    mut dependency_highlight: ResMut<DependencyHighlight>,
+    skill_tree_data: Res<SkillTreeData>,
+    selected_node: Res<SelectedNode>,
+) {
+    let Some(selected_id) = selected_node.id else {
+        if dependency_highlight.show_predecessors || dependency_highlight.show_successors {
+            *dependency_highlight = DependencyHighlight::default();
+        }
+        return;
+    };
+    if !dependency_highlight.is_changed()
+        && !selected_node.is_changed()
+        && !skill_tree_data.is_changed()
+    {
+        return;
+    }
+
+    let trace = |enabled: bool, adjacency: HashMap<u32, Vec<u32>>| {
+        if !enabled {
+            return HashSet::new();
+        }
+        let mut found = graph::reachable_from([selected_id], &adjacency);
+        found.remove(&selected_id);
+        found
+    };
+    dependency_highlight.predecessors = trace(
+        dependency_highlight.show_predecessors,
+        graph::reverse_adjacency(&skill_tree_data.connections),
+    );
+    dependency_highlight.successors = trace(
+        dependency_highlight.show_successors,
+        graph::directed_adjacency(&skill_tree_data.connections),
+    );
+}
+
+fn compute_start_paths(
+    mut start_paths: ResMut<StartPaths>,
+    skill_tree_data: Res<SkillTreeData>,
+    selected_node: Res<SelectedNode>,
+    node_query: Query<&SkillNode>,
+    changed_nodes: Query<(), Changed<SkillNode>>,
+) {
+    if !start_paths.enabled {
+        return;
+    }
+    if !start_paths.is_changed()
+        && !selected_node.is_changed()
+        && !skill_tree_data.is_changed()
+        && changed_nodes.is_empty()
+    {
+        return;
+    }
+
+    start_paths.paths.clear();
+    start_paths.total = 0;
+    start_paths.target = selected_node.id.filter(|&id| {
+        node_query
+            .iter()
+            .any(|node| node.id == id && !node.data.node_type.is_builtin(BuiltinNodeType::Start))
+    });
+    let Some(target) = start_paths.target else {
+        return;
+    };
+
+    let start_ids: Vec<u32> = node_query
+        .iter()
+        .filter(|node| node.data.node_type.is_builtin(BuiltinNodeType::Start))
+        .map(|node| node.id)
+        .collect();
+    let adjacency = graph::undirected_adjacency(&skill_tree_data.connections);
+    let paths = graph::shortest_simple_paths(&start_ids, target, &adjacency, StartPaths::MAX_SHOWN);
+
+    let costs: HashMap<u32, u32> = node_query
+        .iter()
+        .map(|node| (node.id, node.data.cost))
+        .collect();
+    start_paths.total =
+        graph::count_simple_paths(&start_ids, target, &adjacency, StartPaths::MAX_SEARCHED);
+    start_paths.paths = paths
+        .into_iter()
+        .map(|nodes| StartPath {
+            cost: nodes.iter().filter_map(|id| costs.get(id)).sum(),
+            nodes,
+        })
+        .collect();
+}
+
+/// Draws each path from `StartPaths` in its own hue, offset sideways so
+/// paths sharing an edge stay visible
+fn draw_start_paths(
+    mut gizmos: Gizmos,
+    start_paths: Res<StartPaths>,
+    node_query: Query<&SkillNode>,
+) {
+    if !start_paths.enabled || start_paths.paths.is_empty() {
+        return;
+    }
+
+    let positions: HashMap<u32, Vec2> = node_query
+        .iter()
+        .map(|node| (node.id, node.data.position))
+        .collect();
+    let middle = (start_paths.paths.len() - 1) as f32 / 2.0;
+    for (index, path) in start_paths.paths.iter().enumerate() {
+        let color = StartPaths::color(index);
+        let offset = (index as f32 - middle) * PATH_OFFSET;
+        for pair in path.nodes.windows(2) {
+            let (Some(&from), Some(&to)) = (positions.get(&pair[0]), positions.get(&pair[1]))
+            else {
+                continue;
+            };
+            let normal = (to - from).normalize_or_zero().perp() * offset;
+            gizmos.line_2d(from + normal, to + normal, color);
+        }
+    }
+}
+
+fn detect_cycles(mut cycle_warnings: ResMut<CycleWarnings>, skill_tree_data: Res<SkillTreeData>) {
+    if !skill_tree_data.is_changed() {
+        return;
+    }
+
+    let node_ids: Vec<u32> = skill_tree_data.nodes.keys().copied().collect();
+    let adjacency = graph::directed_adjacency(&skill_tree_data.connections);
+    let cycles = graph::find_cycles(&node_ids, &adjacency);
+    cycle_warnings.nodes = cycles.iter().flatten().copied().collect();
+    cycle_warnings.cycles = cycles;
+}
+
+fn compute_topology_metrics(
+    mut metrics: ResMut<TopologyMetrics>,
+    skill_tree_data: Res<SkillTreeData>,
+    cycle_warnings: Res<CycleWarnings>,
+    node_query: Query<&SkillNode>,
+    changed_nodes: Query<(), Changed<SkillNode>>,
+) {
+    if !skill_tree_data.is_changed() && changed_nodes.is_empty() {
+        return;
+    }
+
+    let node_ids: Vec<u32> = skill_tree_data.nodes.keys().copied().collect();
+    let adjacency = graph::undirected_adjacency(&skill_tree_data.connections);
+    let degree = |id: &u32| adjacency.get(id).map_or(0, Vec::len);
+    let start_ids = node_query
+        .iter()
+        .filter(|node| node.data.node_type.is_builtin(BuiltinNodeType::Start))
+        .map(|node| node.id);
+
+    metrics.node_count = node_ids.len();
+    metrics.connection_count = skill_tree_data.connections.len();
+    metrics.average_degree = if node_ids.is_empty() {
+        0.0
+    } else {
+        2.0 * metrics.connection_count as f32 / node_ids.len() as f32
+    };
+    metrics.max_degree = node_ids.iter().map(degree).max().unwrap_or(0);
+    metrics.isolated_nodes = node_ids.iter().filter(|id| degree(id) == 0).count();
+    metrics.longest_path = graph::longest_path_from(
+        start_ids,
+        &graph::directed_adjacency(&skill_tree_data.connections),
+    );
+    metrics.cycles = cycle_warnings.cycles.len();
+    metrics.components = graph::connected_components(&node_ids, &adjacency);
+}
+
+fn validate_nodes(
+    mut validation_warnings: ResMut<ValidationWarnings>,
+    skill_tree_data: Res<SkillTreeData>,
+    node_query: Query<&SkillNode>,
+    changed_nodes: Query<(), Changed<SkillNode>>,
+) {
+    if !skill_tree_data.is_changed() && changed_nodes.is_empty() {
+        return;
+    }
+
+    let mut nodes: Vec<&SkillNode> = node_query.iter().collect();
+    nodes.sort_by_key(|node| node.id);
+
+    validation_warnings.messages.clear();
+    for node in nodes {
+        for stat in &node.data.stats {
+            if stat.violates_bounds() {
+                validation_warnings.messages.push(format!(
+                    "Node {} ({}): {} value {} is outside its min/max",
+                    node.id, node.data.name, stat.stat_name, stat.value
+                ));
             }
-        } else if let Some(connection_index) = selected_connection.index {
-            if connection_index < skill_tree_data.connections.len() {
-                skill_tree_data.connections.remove(connection_index);
-                selected_connection.index = None;
-                editor_state.dirty = true;
+            if let Some(condition) = stat.condition
+                && !skill_tree_data.nodes.contains_key(&condition.node_id())
+            {
+                validation_warnings.messages.push(format!(
+                    "Node {} ({}): {} depends on missing node {}",
+                    node.id,
+                    node.data.name,
+                    stat.stat_name,
+                    condition.node_id()
+                ));
             }
         }
     }
 }
 
+fn validate_start_nodes(
+    mut validation_warnings: ResMut<ValidationWarnings>,
+    skill_tree_data: Res<SkillTreeData>,
+    node_query: Query<&SkillNode>,
+    changed_nodes: Query<(), Changed<SkillNode>>,
+) {
+    if !skill_tree_data.is_changed() && changed_nodes.is_empty() {
+        return;
+    }
+
+    let start_count = node_query
+        .iter()
+        .filter(|node| node.data.node_type.is_builtin(BuiltinNodeType::Start))
+        .count();
+    validation_warnings.start_nodes = match start_count {
+        0 => Some("The tree has no Start node".to_string()),
+        1 => None,
+        n => Some(format!("The tree has {} Start nodes", n)),
+    };
+}
+
+/// Sprite depth for a node's z-order, clamped for files saved before it
+/// was bounded
+fn node_depth(z_order: f32) -> f32 {
+    NODE_Z_BASE + z_order.clamp(-SkillNodeData::MAX_Z_ORDER, SkillNodeData::MAX_Z_ORDER)
+}
+
+/// Moves node sprites when their position or z-order is edited outside of
+/// dragging, and lifts the selected node just above nodes sharing its z-order
+fn sync_node_transforms(
+    mut node_query: Query<(Ref<SkillNode>, &mut Transform)>,
+    selected_node: Res<SelectedNode>,
+) {
+    let selection_changed = selected_node.is_changed();
+    for (node, mut transform) in node_query.iter_mut() {
+        if !node.is_changed() && !selection_changed {
+            continue;
+        }
+        let lift = if selected_node.id == Some(node.id) {
+            SELECTED_Z_LIFT
+        } else {
+            0.0
+        };
+        let translation = node
+            .data
+            .position
+            .extend(node_depth(node.data.z_order) + lift);
+        if transform.translation != translation {
+            transform.translation = translation;
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn update_node_visuals(
-    mut node_query: Query<(&SkillNode, &mut Sprite)>,
+    mut node_query: Query<(&SkillNode, &mut Sprite, &mut Visibility)>,
     selected_node: Res<SelectedNode>,
+    multi_selected: Res<MultiSelectedNodes>,
     connection_mode: Res<ConnectionMode>,
+    dependency_highlight: Res<DependencyHighlight>,
+    reachability: Res<ReachabilitySet>,
+    cycle_warnings: Res<CycleWarnings>,
+    compare_tree: Res<CompareTree>,
+    node_type_styles: Res<NodeTypeStyles>,
+    node_type_colors: Res<NodeTypeColors>,
+    tier_filter: Res<TierFilter>,
+    node_images: Res<NodeImages>,
+    centrality: Res<CentralityColoring>,
+    theme_settings: Res<ThemeSettings>,
 ) {
-    for (node, mut sprite) in node_query.iter_mut() {
+    for (node, mut sprite, mut visibility) in node_query.iter_mut() {
+        let image = node_images.get(&node.data.image_name);
+        if sprite.image != image {
+            sprite.image = image;
+        }
+
         let is_selected = selected_node.id == Some(node.id);
         let is_connection_start =
             connection_mode.active && connection_mode.start_node == Some(node.id);
 
-        sprite.color = if is_connection_start {
-            Color::srgb(0.3, 0.5, 0.8)
-        } else if is_selected {
-            Color::srgb(0.3, 0.8, 0.4)
-        } else {
-            Color::srgb(1.0, 1.0, 1.0)
-        };
+        let highlight = if is_connection_start {
+            Some(Color::srgb(0.3, 0.5, 0.8))
+        } else if is_selected {
+            Some(Color::srgb(0.3, 0.8, 0.4))
+        } else if multi_selected.nodes.contains(&node.id) {
+            Some(Color::srgb(0.6, 0.9, 0.65))
+        } else if dependency_highlight.predecessors.contains(&node.id) {
+            Some(Color::srgb(0.95, 0.6, 0.2))
+        } else if dependency_highlight.successors.contains(&node.id) {
+            Some(Color::srgb(0.3, 0.55, 0.95))
+        } else if compare_tree.diff.only_current.contains(&node.id) {
+            Some(Color::srgb(0.4, 0.9, 0.4))
+        } else if compare_tree.diff.changed.contains(&node.id) {
+            Some(Color::srgb(0.95, 0.85, 0.3))
+        } else if cycle_warnings.nodes.contains(&node.id) {
+            Some(Color::srgb(0.9, 0.3, 0.3))
+        } else {
+            None
+        };
+        let high_contrast = theme_settings.high_contrast;
+        sprite.color = match highlight {
+            Some(_) if high_contrast => HIGH_CONTRAST_HIGHLIGHT,
+            None if reachability.is_unreachable(node.id) => {
+                if high_contrast {
+                    HIGH_CONTRAST_DIM
+                } else {
+                    Color::srgb(0.25, 0.25, 0.25)
+                }
+            }
+            None if high_contrast => Color::WHITE,
+            _ => {
+                let color = highlight
+                    .or_else(|| centrality.node_color(node.id))
+                    .unwrap_or_else(|| {
+                        node_type_colors
+                            .tint(&node.data.node_type)
+                            .unwrap_or_else(|| node_type_styles.tint(&node.data.node_type))
+                    });
+                remap_color_for_cvd(color, theme_settings.colorblind_mode)
+            }
+        };
+        sprite.custom_size = Some(Vec2::splat(node_type_styles.node_size(&node.data)));
+        visibility.set_if_neq(if tier_filter.hides(node.data.tier) {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        });
+    }
+}
+
+/// Re-diffs the open tree against the compare tree after edits, and keeps a
+/// ghost sprite for every node that only exists in the compare tree
+fn update_tree_diff(
+    mut commands: Commands,
+    mut compare_tree: ResMut<CompareTree>,
+    skill_tree_data: Res<SkillTreeData>,
+    node_query: Query<&SkillNode>,
+    changed_nodes: Query<(), Changed<SkillNode>>,
+    ghost_query: Query<Entity, With<GhostNode>>,
+    node_images: Res<NodeImages>,
+) {
+    if !compare_tree.is_active() {
+        for entity in ghost_query.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+
+    if !compare_tree.is_changed() && !skill_tree_data.is_changed() && changed_nodes.is_empty() {
+        return;
+    }
+
+    let diff = diff::diff_nodes(
+        node_query.iter().map(|node| &node.data),
+        &compare_tree.nodes,
+    );
+    let ghosts_stale = diff.only_compare != compare_tree.diff.only_compare
+        || ghost_query.iter().len() != diff.only_compare.len();
+    compare_tree.bypass_change_detection().diff = diff;
+
+    if ghosts_stale {
+        for entity in ghost_query.iter() {
+            commands.entity(entity).despawn();
+        }
+        for id in &compare_tree.diff.only_compare {
+            let node_data = &compare_tree.nodes[id];
+            commands.spawn((
+                GhostNode,
+                Transform::from_translation(node_data.position.extend(-0.5)),
+                Sprite {
+                    custom_size: Some(Vec2::splat(60.0)),
+                    image: node_images.get(&node_data.image_name),
+                    color: Color::srgba(0.9, 0.3, 0.3, 0.45),
+                    ..default()
+                },
+            ));
+        }
     }
 }
 
@@ -605,49 +2721,522 @@ fn calculate_arc_center(
     Some((center, start_angle, end_angle))
 }
 
+/// Angle swept when travelling from `start_angle` to `end_angle` in the given direction
+fn arc_angle_range(start_angle: f32, end_angle: f32, clockwise: bool) -> f32 {
+    let angle_range = if clockwise {
+        if start_angle < end_angle {
+            start_angle - end_angle + std::f32::consts::TAU
+        } else {
+            start_angle - end_angle
+        }
+    } else if end_angle < start_angle {
+        end_angle - start_angle + std::f32::consts::TAU
+    } else {
+        end_angle - start_angle
+    };
+
+    angle_range.abs()
+}
+
+/// Points a curve passes through: the `from` node, its waypoints, then the
+/// `to` node
+fn curve_points(from: Vec2, to: Vec2, waypoints: &[Vec2]) -> Vec<Vec2> {
+    let mut points = Vec::with_capacity(waypoints.len() + 2);
+    points.push(from);
+    points.extend_from_slice(waypoints);
+    points.push(to);
+    points
+}
+
+/// Point `t` of the way along span `span` of a uniform Catmull-Rom spline
+/// through `points`. The end points stand in for the missing neighbours, so
+/// the curve runs all the way to them.
+fn catmull_rom_point(points: &[Vec2], span: usize, t: f32) -> Vec2 {
+    let last = points.len() - 1;
+    let p0 = points[span.saturating_sub(1)];
+    let p1 = points[span];
+    let p2 = points[(span + 1).min(last)];
+    let p3 = points[(span + 2).min(last)];
+    let t2 = t * t;
+    let t3 = t2 * t;
+    0.5 * (2.0 * p1
+        + (p2 - p0) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+}
+
+/// A curve connection as a line strip, for drawing and hit testing
+fn catmull_rom_polyline(from: Vec2, to: Vec2, waypoints: &[Vec2]) -> Vec<Vec2> {
+    let points = curve_points(from, to, waypoints);
+    let mut polyline = vec![from];
+    for span in 0..points.len() - 1 {
+        polyline.extend(
+            (1..=CURVE_SEGMENTS)
+                .map(|i| catmull_rom_point(&points, span, i as f32 / CURVE_SEGMENTS as f32)),
+        );
+    }
+    polyline
+}
+
+/// Point `distance` along a line strip from its start, stopping at its end
+fn point_along_polyline(polyline: &[Vec2], distance: f32) -> Vec2 {
+    let mut remaining = distance;
+    for segment in polyline.windows(2) {
+        let length = segment[0].distance(segment[1]);
+        if remaining <= length && length > 0.0 {
+            return segment[0].lerp(segment[1], remaining / length);
+        }
+        remaining -= length;
+    }
+    polyline.last().copied().unwrap_or_default()
+}
+
+/// Part of the world visible in the window, derived from the editor camera
+fn visible_world_rect(window: &Window, editor_camera: &EditorCamera) -> Rect {
+    let half_size = Vec2::new(window.width(), window.height()) * 0.5 * editor_camera.zoom;
+    Rect::from_center_half_size(editor_camera.pan_offset, half_size)
+}
+
+impl VisibleCanvas<'_, '_> {
+    fn rect(&self) -> Option<Rect> {
+        let main = self
+            .primary_window
+            .single()
+            .ok()
+            .map(|window| visible_world_rect(window, &self.editor_camera));
+        let viewport = self
+            .secondary_viewport
+            .window
+            .and_then(|window| self.windows.get(window).ok())
+            .map(|window| visible_world_rect(window, &self.secondary_viewport.camera));
+        match (main, viewport) {
+            (Some(main), Some(viewport)) => Some(main.union(viewport)),
+            (main, viewport) => main.or(viewport),
+        }
+    }
+}
+
+/// Point halfway along a connection, on the arc or curve when it has one
+fn connection_midpoint(from: Vec2, to: Vec2, curve_type: &CurveType) -> Vec2 {
+    let (radius, clockwise) = match curve_type {
+        CurveType::Straight => return (from + to) * 0.5,
+        CurveType::Arc {
+            radius, clockwise, ..
+        } => (*radius, *clockwise),
+        CurveType::CatmullRom { waypoints } => {
+            let polyline = catmull_rom_polyline(from, to, waypoints);
+            let length: f32 = polyline
+                .windows(2)
+                .map(|segment| segment[0].distance(segment[1]))
+                .sum();
+            return point_along_polyline(&polyline, length * 0.5);
+        }
+    };
+    let Some((center, start_angle, end_angle)) = calculate_arc_center(from, to, radius, clockwise)
+    else {
+        return (from + to) * 0.5;
+    };
+    let half_range = arc_angle_range(start_angle, end_angle, clockwise) * 0.5;
+    let angle = if clockwise {
+        start_angle - half_range
+    } else {
+        start_angle + half_range
+    };
+    center + Vec2::from_angle(angle) * radius
+}
+
+/// Conservative world-space bounds of a connection, used for culling
+fn connection_bounds(from: Vec2, to: Vec2, curve_type: &CurveType) -> Rect {
+    match curve_type {
+        CurveType::Straight => Rect::from_corners(from, to),
+        CurveType::Arc {
+            radius, clockwise, ..
+        } => match calculate_arc_center(from, to, *radius, *clockwise) {
+            Some((center, _, _)) => Rect::from_center_half_size(center, Vec2::splat(*radius)),
+            None => Rect::from_corners(from, to),
+        },
+        // A Catmull-Rom span stays close to its control points, but can
+        // overshoot them slightly on sharp turns
+        CurveType::CatmullRom { waypoints } => waypoints
+            .iter()
+            .fold(Rect::from_corners(from, to), |bounds, waypoint| {
+                bounds.union_point(*waypoint)
+            })
+            .inflate(CULL_MARGIN),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn draw_connections(
     mut gizmos: Gizmos,
     skill_tree_data: Res<SkillTreeData>,
     node_query: Query<(&SkillNode, &Transform)>,
     selected_connection: Res<SelectedConnection>,
+    selected_node: Res<SelectedNode>,
+    hovered_connection: Res<HoveredConnection>,
+    visible_canvas: VisibleCanvas,
+    png_export_state: Res<PngExportState>,
+    mut editor_stats: ResMut<EditorStats>,
+    reachability: Res<ReachabilitySet>,
+    tier_filter: Res<TierFilter>,
+    layout: ConnectionLayout,
+    time: Res<Time>,
+    grid_settings: Res<GridSettings>,
+    theme_settings: Res<ThemeSettings>,
 ) {
+    // The PNG export camera can frame the whole tree, so nothing is culled while it renders
+    let cull_rect = visible_canvas
+        .rect()
+        .filter(|_| png_export_state.job.is_none())
+        .map(|rect| rect.inflate(CULL_MARGIN));
+    editor_stats.culled_connections = 0;
+    editor_stats.drawn_connections = 0;
+
+    // Where a connection attaches to one of its nodes, and that node's tier
+    let endpoint = |id: u32, port: Option<u32>| {
+        let entity = *skill_tree_data.nodes.get(&id)?;
+        let (node, transform) = node_query.get(entity).ok()?;
+        Some((attach_point(node, transform, port), node.data.tier))
+    };
     for (index, connection) in skill_tree_data.connections.iter().enumerate() {
-        let mut from_pos = None;
-        let mut to_pos = None;
+        let from_end = endpoint(connection.from_id, connection.from_port);
+        let to_end = endpoint(connection.to_id, connection.to_port);
+        let tiers = from_end.iter().chain(&to_end).map(|&(_, tier)| tier);
+        let max_tier = tiers.clone().max().unwrap_or(0);
+        let hidden = tiers.clone().any(|tier| tier_filter.hides(tier));
+        if hidden || (!connection.enabled && grid_settings.hide_disabled) {
+            continue;
+        }
 
-        for (node, transform) in node_query.iter() {
-            if node.id == connection.from_id {
-                from_pos = Some(transform.translation.xy());
-            }
-            if node.id == connection.to_id {
-                to_pos = Some(transform.translation.xy());
+        if let (Some((from, _)), Some((to, _))) = (from_end, to_end) {
+            let offset = layout.offset(index);
+            let (from, to) = (from + offset, to + offset);
+            if let Some(cull_rect) = cull_rect {
+                // Inflate so horizontal and vertical lines don't have empty bounds
+                let bounds = connection_bounds(from, to, &connection.curve_type).inflate(1.0);
+                if cull_rect.intersect(bounds).is_empty() {
+                    editor_stats.culled_connections += 1;
+                    continue;
+                }
             }
-        }
+            editor_stats.drawn_connections += 1;
 
-        if let (Some(from), Some(to)) = (from_pos, to_pos) {
             let is_selected = selected_connection.index == Some(index);
+            let is_incident = selected_node
+                .id
+                .is_some_and(|id| connection.from_id == id || connection.to_id == id);
+            let is_unreachable = reachability.is_unreachable(connection.from_id)
+                && reachability.is_unreachable(connection.to_id);
             let color = if is_selected {
                 Color::srgb(0.9, 0.7, 0.3)
+            } else if is_incident {
+                Color::srgb(0.3, 0.8, 0.4)
+            } else if is_unreachable {
+                Color::srgb(0.3, 0.27, 0.22)
+            } else {
+                // Brighten toward gold as the higher endpoint's tier rises
+                let t =
+                    max_tier.min(SkillNodeData::MAX_TIER) as f32 / SkillNodeData::MAX_TIER as f32;
+                Color::srgb(0.7 + 0.3 * t, 0.6 + 0.25 * t, 0.4)
+            };
+            let color = if !theme_settings.high_contrast {
+                remap_color_for_cvd(color, theme_settings.colorblind_mode)
+            } else if is_selected || is_incident {
+                HIGH_CONTRAST_HIGHLIGHT
+            } else if is_unreachable {
+                HIGH_CONTRAST_DIM
+            } else {
+                Color::WHITE
+            };
+            let color = if hovered_connection.index == Some(index) && !is_selected {
+                color.mix(&Color::WHITE, HOVER_BRIGHTEN)
             } else {
-                Color::srgb(0.7, 0.6, 0.4)
+                color
             };
 
+            if is_selected {
+                for handle in endpoint_handles(from, to, &connection.curve_type) {
+                    gizmos.circle_2d(handle, ENDPOINT_HANDLE_RADIUS, color);
+                }
+                for handle in waypoint_handles(from, to, &connection.curve_type) {
+                    gizmos.circle_2d(handle, WAYPOINT_HANDLE_RADIUS, color);
+                }
+            }
+
+            let routed = layout.route(index);
+
+            if !connection.enabled {
+                let polyline = match routed {
+                    Some(path) => path.clone(),
+                    None => connection_polyline(from, to, &connection.curve_type),
+                };
+                draw_dashed_polyline(
+                    &mut gizmos,
+                    &polyline,
+                    color.with_alpha(DISABLED_CONNECTION_ALPHA),
+                );
+                continue;
+            }
+
             match &connection.curve_type {
-                CurveType::Straight => {
-                    gizmos.line_2d(from, to, color);
+                CurveType::Straight => match routed {
+                    Some(path) => gizmos.linestrip_2d(path.iter().copied(), color),
+                    None => gizmos.line_2d(from, to, color),
+                },
+                CurveType::Arc {
+                    radius,
+                    clockwise,
+                    animated: true,
+                } => {
+                    // Dim the arc so the pulse stands out, falling back to
+                    // the usual dashed line when the arc is invalid
+                    let dimmed = color.with_alpha(ANIMATED_ARC_ALPHA);
+                    if draw_arc(&mut gizmos, from, to, *radius, *clockwise, dimmed) {
+                        draw_arc_pulse(
+                            &mut gizmos,
+                            from,
+                            to,
+                            *radius,
+                            *clockwise,
+                            time.elapsed_secs(),
+                        );
+                    } else {
+                        draw_dashed_line(&mut gizmos, from, to, color);
+                    }
                 }
-                CurveType::Arc { radius, clockwise } => {
+                CurveType::Arc {
+                    radius, clockwise, ..
+                } => {
                     // Try to draw arc, fall back to straight line if invalid
                     if !draw_arc(&mut gizmos, from, to, *radius, *clockwise, color) {
                         // Arc is invalid, draw dashed line to indicate issue
                         draw_dashed_line(&mut gizmos, from, to, color);
                     }
                 }
+                CurveType::CatmullRom { waypoints } => {
+                    gizmos.linestrip_2d(catmull_rom_polyline(from, to, waypoints), color);
+                }
             }
         }
     }
 }
 
+/// Path from `from` to `to` that bends around every circle the straight line
+/// would cross. Each blocking circle, nearest first, gets two waypoints on the
+/// side the line already passes: the corners of a square around the circle
+/// grown by `ROUTE_CLEARANCE`, so the path follows its tangent lines around
+/// it and continues toward `to`.
+fn route_around_nodes(
+    from: Vec2,
+    to: Vec2,
+    obstacles: impl Iterator<Item = (Vec2, f32)>,
+) -> Vec<Vec2> {
+    let mut path = vec![from];
+    let Some(direction) = (to - from).try_normalize() else {
+        path.push(to);
+        return path;
+    };
+    let normal = direction.perp();
+    let length = from.distance(to);
+
+    let mut blocking: Vec<(f32, Vec2, f32)> = obstacles
+        .filter_map(|(center, radius)| {
+            let along = (center - from).dot(direction);
+            let across = (center - from).dot(normal);
+            (along > 0.0 && along < length && across.abs() < radius).then_some((
+                along,
+                center,
+                radius + ROUTE_CLEARANCE,
+            ))
+        })
+        .collect();
+    blocking.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    for (along, center, radius) in blocking {
+        // Skip circles a previous detour already stepped past
+        if path
+            .last()
+            .is_some_and(|last| (*last - from).dot(direction) >= along)
+        {
+            continue;
+        }
+        let side = if (center - from).dot(normal) > 0.0 {
+            -normal
+        } else {
+            normal
+        };
+        let offset = center + side * radius;
+        path.push(offset - direction * radius);
+        path.push(offset + direction * radius);
+    }
+    path.push(to);
+    path
+}
+
+/// Nodes that moved or whose data changed since the last run
+type ChangedNode = Or<(Changed<SkillNode>, Changed<Transform>)>;
+
+/// Routes every visible straight connection around the nodes it would cross,
+/// starting from its bundled position, whenever the tree or the layout changes
+#[allow(clippy::too_many_arguments)]
+fn route_connections(
+    mut routes: ResMut<ConnectionRoutes>,
+    connection_settings: Res<ConnectionSettings>,
+    skill_tree_data: Res<SkillTreeData>,
+    bundles: Res<ConnectionBundles>,
+    tier_filter: Res<TierFilter>,
+    node_type_styles: Res<NodeTypeStyles>,
+    node_query: Query<(&SkillNode, &Transform)>,
+    changed_nodes: Query<(), ChangedNode>,
+) {
+    if !connection_settings.is_changed()
+        && !skill_tree_data.is_changed()
+        && !bundles.is_changed()
+        && !tier_filter.is_changed()
+        && !node_type_styles.is_changed()
+        && changed_nodes.is_empty()
+    {
+        return;
+    }
+
+    routes.paths.clear();
+    if !connection_settings.route_around_nodes {
+        return;
+    }
+
+    // Nodes as circles that straight connections are routed around
+    let obstacles: Vec<(u32, Vec2, f32)> = node_query
+        .iter()
+        .filter(|(node, _)| !tier_filter.hides(node.data.tier))
+        .map(|(node, transform)| {
+            (
+                node.id,
+                transform.translation.xy(),
+                node_type_styles.node_size(&node.data) / 2.0,
+            )
+        })
+        .collect();
+
+    let endpoint = |id: u32, port: Option<u32>| {
+        let entity = *skill_tree_data.nodes.get(&id)?;
+        let (node, transform) = node_query.get(entity).ok()?;
+        (!tier_filter.hides(node.data.tier)).then(|| attach_point(node, transform, port))
+    };
+    for (index, connection) in skill_tree_data.connections.iter().enumerate() {
+        if !matches!(connection.curve_type, CurveType::Straight) {
+            continue;
+        }
+        let (Some(from), Some(to)) = (
+            endpoint(connection.from_id, connection.from_port),
+            endpoint(connection.to_id, connection.to_port),
+        ) else {
+            continue;
+        };
+        let offset = bundles.offsets.get(&index).copied().unwrap_or_default();
+        let blocking = obstacles
+            .iter()
+            .filter(|(id, ..)| *id != connection.from_id && *id != connection.to_id)
+            .map(|&(_, center, radius)| (center, radius));
+        let path = route_around_nodes(from + offset, to + offset, blocking);
+        if path.len() > 2 {
+            routes.paths.insert(index, path);
+        }
+    }
+}
+
+/// Groups connections by their pair of endpoints, whichever way they point,
+/// and spreads each group of two or more across the line between the nodes
+fn bundle_connections(
+    mut bundles: ResMut<ConnectionBundles>,
+    grid_settings: Res<GridSettings>,
+    skill_tree_data: Res<SkillTreeData>,
+    node_query: Query<&Transform, With<SkillNode>>,
+    changed_nodes: Query<(), Changed<SkillNode>>,
+) {
+    if !grid_settings.is_changed() && !skill_tree_data.is_changed() && changed_nodes.is_empty() {
+        return;
+    }
+
+    bundles.offsets.clear();
+    if !grid_settings.bundle_parallel {
+        return;
+    }
+
+    let mut groups: HashMap<(u32, u32), Vec<usize>> = HashMap::new();
+    for (index, connection) in skill_tree_data.connections.iter().enumerate() {
+        let pair = (
+            connection.from_id.min(connection.to_id),
+            connection.from_id.max(connection.to_id),
+        );
+        groups.entry(pair).or_default().push(index);
+    }
+
+    let position = |id: u32| {
+        let entity = *skill_tree_data.nodes.get(&id)?;
+        node_query
+            .get(entity)
+            .ok()
+            .map(|transform| transform.translation.xy())
+    };
+    for ((low, high), indices) in groups {
+        if indices.len() < 2 {
+            continue;
+        }
+        let (Some(low), Some(high)) = (position(low), position(high)) else {
+            continue;
+        };
+        // Measured from the lower id's node, so both directions agree
+        let normal = (high - low).normalize_or_zero().perp();
+        let middle = (indices.len() - 1) as f32 / 2.0;
+        for (slot, index) in indices.into_iter().enumerate() {
+            bundles
+                .offsets
+                .insert(index, normal * (slot as f32 - middle) * BUNDLE_SPACING);
+        }
+    }
+}
+
+/// Draws the bright segment of an animated arc, `elapsed` seconds into its
+/// trip. It starts over from the `from` end after reaching the `to` end.
+fn draw_arc_pulse(
+    gizmos: &mut Gizmos,
+    start: Vec2,
+    end: Vec2,
+    radius: f32,
+    clockwise: bool,
+    elapsed: f32,
+) {
+    let Some((center, start_angle, end_angle)) =
+        calculate_arc_center(start, end, radius, clockwise)
+    else {
+        return;
+    };
+    let angle_range = arc_angle_range(start_angle, end_angle, clockwise);
+    if angle_range <= 0.0 {
+        return;
+    }
+    let width = angle_range * ARC_PULSE_FRACTION;
+    // Travel past the end by the pulse width, so the pulse slides off the
+    // arc before starting over
+    let head = (elapsed * ARC_PULSE_SPEED / radius) % (angle_range + width);
+    let (from_offset, to_offset) = ((head - width).max(0.0), head.min(angle_range));
+    if to_offset <= from_offset {
+        return;
+    }
+
+    let segments = ((ARC_SEGMENTS as f32 * ARC_PULSE_FRACTION).ceil() as u32).max(2);
+    let points = (0..=segments).map(|i| {
+        let offset = from_offset + (to_offset - from_offset) * i as f32 / segments as f32;
+        let angle = if clockwise {
+            start_angle - offset
+        } else {
+            start_angle + offset
+        };
+        center + Vec2::from_angle(angle) * radius
+    });
+    gizmos.linestrip_2d(points, ARC_PULSE_COLOR);
+}
+
 fn draw_arc(
     gizmos: &mut Gizmos,
     start: Vec2,
@@ -656,44 +3245,320 @@ fn draw_arc(
     clockwise: bool,
     color: Color,
 ) -> bool {
-    if let Some((center, start_angle, end_angle)) =
-        calculate_arc_center(start, end, radius, clockwise)
-    {
-        let mut angle_range = if clockwise {
-            if start_angle < end_angle {
-                start_angle - end_angle + std::f32::consts::TAU
-            } else {
-                start_angle - end_angle
-            }
-        } else if end_angle < start_angle {
-            end_angle - start_angle + std::f32::consts::TAU
+    match arc_points(start, end, radius, clockwise) {
+        Some(points) => {
+            gizmos.linestrip_2d(points, color);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Points along an arc from `start` to `end`, or `None` when the radius is
+/// too small to reach
+fn arc_points(start: Vec2, end: Vec2, radius: f32, clockwise: bool) -> Option<Vec<Vec2>> {
+    let (center, start_angle, end_angle) = calculate_arc_center(start, end, radius, clockwise)?;
+    let angle_range = arc_angle_range(start_angle, end_angle, clockwise);
+
+    let segments = (ARC_SEGMENTS as f32 * (angle_range / std::f32::consts::TAU)).ceil() as u32;
+    let segments = segments.max(4);
+
+    let mut points = vec![start];
+    for i in 1..=segments {
+        let t = i as f32 / segments as f32;
+        let angle = if clockwise {
+            start_angle - angle_range * t
         } else {
-            end_angle - start_angle
+            start_angle + angle_range * t
         };
+        points.push(center + Vec2::new(angle.cos(), angle.sin()) * radius);
+    }
+    Some(points)
+}
 
-        angle_range = angle_range.abs();
+/// A connection's shape as a line strip. Invalid arcs come out straight,
+/// as they are drawn.
+fn connection_polyline(from: Vec2, to: Vec2, curve_type: &CurveType) -> Vec<Vec2> {
+    match curve_type {
+        CurveType::Straight => vec![from, to],
+        CurveType::Arc {
+            radius, clockwise, ..
+        } => arc_points(from, to, *radius, *clockwise).unwrap_or_else(|| vec![from, to]),
+        CurveType::CatmullRom { waypoints } => catmull_rom_polyline(from, to, waypoints),
+    }
+}
 
-        let segments = (ARC_SEGMENTS as f32 * (angle_range / std::f32::consts::TAU)).ceil() as u32;
-        let segments = segments.max(4);
+/// Draws a line strip as dashes, splitting its length into an odd number of
+/// equal parts and drawing every other one, so both ends get a dash
+fn draw_dashed_polyline(gizmos: &mut Gizmos, polyline: &[Vec2], color: Color) {
+    let length: f32 = polyline
+        .windows(2)
+        .map(|segment| segment[0].distance(segment[1]))
+        .sum();
+    let parts = (length / DISABLED_DASH_LENGTH).round() as u32 | 1;
+    let part_length = length / parts as f32;
+    for part in (0..parts).step_by(2) {
+        let start = part as f32 * part_length;
+        gizmos.line_2d(
+            point_along_polyline(polyline, start),
+            point_along_polyline(polyline, start + part_length),
+            color,
+        );
+    }
+}
 
-        let mut prev_point = start;
+/// Direction from `start` towards `cursor` snapped to the nearest multiple
+/// of `increment_degrees`, and the node closest to the cursor that lies along
+/// that direction, as (id, position)
+fn angle_snap_target(
+    start_id: u32,
+    start: Vec2,
+    cursor: Vec2,
+    increment_degrees: f32,
+    nodes: impl IntoIterator<Item = (u32, Vec2)>,
+) -> (Vec2, Option<(u32, Vec2)>) {
+    let increment = increment_degrees.to_radians();
+    let angle = (cursor - start).to_angle();
+    let direction = Vec2::from_angle((angle / increment).round() * increment);
+    let tolerance = ConnectionSettings::ANGLE_SNAP_TOLERANCE.to_radians();
+
+    let target = nodes
+        .into_iter()
+        .filter(|&(id, position)| {
+            id != start_id
+                && position != start
+                && direction.angle_to(position - start).abs() <= tolerance
+        })
+        .min_by(|(_, a), (_, b)| {
+            a.distance_squared(cursor)
+                .total_cmp(&b.distance_squared(cursor))
+        });
+    (direction, target)
+}
 
-        for i in 1..=segments {
-            let t = i as f32 / segments as f32;
-            let angle = if clockwise {
-                start_angle - angle_range * t
-            } else {
-                start_angle + angle_range * t
-            };
+/// Stamps `modified_at` on nodes whose data changed since last frame, by
+/// comparing against a copy of each node. Like `emit_tree_events`, this
+/// catches every way of editing a node. Copies are kept per entity, so
+/// nodes respawned by a load aren't mistaken for edited ones.
+fn track_node_modifications(
+    mut changed_nodes: Query<(Entity, &mut SkillNode), Changed<SkillNode>>,
+    mut removed_nodes: RemovedComponents<SkillNode>,
+    mut previous: Local<HashMap<Entity, SkillNodeData>>,
+) {
+    for entity in removed_nodes.read() {
+        previous.remove(&entity);
+    }
+    for (entity, mut node) in &mut changed_nodes {
+        let edited = previous.get(&entity).is_some_and(|before| {
+            let mut before = before.clone();
+            before.modified_at = node.data.modified_at;
+            before != node.data
+        });
+        if edited {
+            node.data.modified_at = unix_timestamp();
+        }
+        previous.insert(entity, node.data.clone());
+    }
+}
 
-            let point = center + Vec2::new(angle.cos(), angle.sin()) * radius;
-            gizmos.line_2d(prev_point, point, color);
-            prev_point = point;
+/// What `emit_tree_events` saw last frame
+#[derive(Default)]
+struct TreeEventSnapshot {
+    /// Compared by entity, so a load that reuses ids still replaces them
+    nodes: HashMap<u32, Entity>,
+    positions: HashMap<Entity, Vec2>,
+    connections: Vec<(Entity, Entity)>,
+}
+
+/// Sends the node and connection lifecycle events by diffing the tree
+/// against last frame, so every way of editing it is covered: mouse,
+/// keyboard, the side panel, paste, merges and imports. Loading or clearing
+/// a tree sends events for all of its nodes and connections too.
+#[allow(clippy::too_many_arguments)]
+fn emit_tree_events(
+    skill_tree_data: Res<SkillTreeData>,
+    changed_nodes: Query<(Entity, &SkillNode), Changed<SkillNode>>,
+    mut snapshot: Local<TreeEventSnapshot>,
+    mut node_created: EventWriter<NodeCreatedEvent>,
+    mut node_deleted: EventWriter<NodeDeletedEvent>,
+    mut node_moved: EventWriter<NodeMovedEvent>,
+    mut connection_created: EventWriter<ConnectionCreatedEvent>,
+    mut connection_deleted: EventWriter<ConnectionDeletedEvent>,
+) {
+    for (entity, node) in &changed_nodes {
+        // A node that was just spawned has no previous position
+        let previous = snapshot.positions.insert(entity, node.data.position);
+        if previous.is_some_and(|position| position != node.data.position) {
+            node_moved.write(NodeMovedEvent {
+                id: node.id,
+                new_pos: node.data.position,
+            });
+        }
+    }
+
+    if !skill_tree_data.is_changed() {
+        return;
+    }
+
+    let mut deleted: Vec<(u32, Entity)> = snapshot
+        .nodes
+        .iter()
+        .filter(|&(id, entity)| skill_tree_data.nodes.get(id) != Some(entity))
+        .map(|(&id, &entity)| (id, entity))
+        .collect();
+    deleted.sort_unstable();
+    for (id, entity) in deleted {
+        snapshot.nodes.remove(&id);
+        snapshot.positions.remove(&entity);
+        node_deleted.write(NodeDeletedEvent(id));
+    }
+    let mut created: Vec<(u32, Entity)> = skill_tree_data
+        .nodes
+        .iter()
+        .filter(|&(id, _)| !snapshot.nodes.contains_key(id))
+        .map(|(&id, &entity)| (id, entity))
+        .collect();
+    created.sort_unstable();
+    snapshot.nodes.extend(created.iter().copied());
+    node_created.write_batch(created.into_iter().map(|(id, _)| NodeCreatedEvent(id)));
+
+    // Endpoints as entities, so connections between replaced nodes count
+    // as new even when their ids are unchanged
+    let entity_of = |id: u32| {
+        skill_tree_data
+            .nodes
+            .get(&id)
+            .copied()
+            .unwrap_or(Entity::PLACEHOLDER)
+    };
+    let current: Vec<(Entity, Entity)> = skill_tree_data
+        .connections
+        .iter()
+        .map(|conn| (entity_of(conn.from_id), entity_of(conn.to_id)))
+        .collect();
+    if current == snapshot.connections {
+        return;
+    }
+    // Counted as multisets so duplicate connections are matched one for one
+    let mut unmatched: HashMap<(Entity, Entity), usize> = HashMap::new();
+    for &pair in &current {
+        *unmatched.entry(pair).or_default() += 1;
+    }
+    for (index, pair) in snapshot.connections.iter().enumerate() {
+        match unmatched.get_mut(pair) {
+            Some(count) if *count > 0 => *count -= 1,
+            _ => {
+                connection_deleted.write(ConnectionDeletedEvent { index });
+            }
         }
-        true
+    }
+    let mut previous: HashMap<(Entity, Entity), usize> = HashMap::new();
+    for &pair in &snapshot.connections {
+        *previous.entry(pair).or_default() += 1;
+    }
+    for (pair, conn) in current.iter().zip(&skill_tree_data.connections) {
+        match previous.get_mut(pair) {
+            Some(count) if *count > 0 => *count -= 1,
+            _ => {
+                connection_created.write(ConnectionCreatedEvent {
+                    from_id: conn.from_id,
+                    to_id: conn.to_id,
+                });
+            }
+        }
+    }
+    snapshot.connections = current;
+}
+
+/// Traces tree edits at debug level, e.g. with `RUST_LOG=skill_tree_editor=debug`.
+/// Moves are sent every frame of a drag, so they're only logged at trace.
+fn log_tree_events(
+    mut node_created: EventReader<NodeCreatedEvent>,
+    mut node_deleted: EventReader<NodeDeletedEvent>,
+    mut node_moved: EventReader<NodeMovedEvent>,
+    mut connection_created: EventReader<ConnectionCreatedEvent>,
+    mut connection_deleted: EventReader<ConnectionDeletedEvent>,
+) {
+    for event in node_created.read() {
+        debug!("Node {} created", event.0);
+    }
+    for event in node_deleted.read() {
+        debug!("Node {} deleted", event.0);
+    }
+    for event in node_moved.read() {
+        trace!("Node {} moved to {}", event.id, event.new_pos);
+    }
+    for event in connection_created.read() {
+        debug!("Connection {} -> {} created", event.from_id, event.to_id);
+    }
+    for event in connection_deleted.read() {
+        debug!("Connection at index {} deleted", event.index);
+    }
+}
+
+/// Dashed line to the cursor from the connection mode's start node, or from
+/// the fixed end of a connection being reconnected. With Alt held in
+/// connection mode it also draws the snapped angle and ends at the node it
+/// would connect to.
+#[allow(clippy::too_many_arguments)]
+fn draw_connection_preview(
+    mut gizmos: Gizmos,
+    connection_mode: Res<ConnectionMode>,
+    connection_drag: Res<ConnectionDragState>,
+    skill_tree_data: Res<SkillTreeData>,
+    canvas_cursor: CanvasCursor,
+    node_query: Query<&SkillNode>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    connection_settings: Res<ConnectionSettings>,
+) {
+    let start_end = if connection_mode.active {
+        connection_mode
+            .start_node
+            .map(|id| (id, connection_mode.start_port))
+    } else if connection_drag.dragging {
+        skill_tree_data
+            .connections
+            .get(connection_drag.index)
+            .map(|connection| {
+                if connection_drag.moving_from {
+                    (connection.to_id, connection.to_port)
+                } else {
+                    (connection.from_id, connection.from_port)
+                }
+            })
     } else {
-        false
+        None
+    };
+    let Some((start_id, start)) = start_end.and_then(|(id, port)| {
+        let node = node_query.iter().find(|node| node.id == id)?;
+        Some((node.id, node.data.position + node.data.port_offset(port)))
+    }) else {
+        return;
+    };
+    let Some(cursor) = canvas_cursor.world_position() else {
+        return;
+    };
+
+    let alt_pressed = keyboard.pressed(KeyCode::AltLeft) || keyboard.pressed(KeyCode::AltRight);
+    if connection_mode.active && alt_pressed {
+        let (direction, target) = angle_snap_target(
+            start_id,
+            start,
+            cursor,
+            connection_settings.angle_snap_degrees,
+            node_query.iter().map(|node| (node.id, node.data.position)),
+        );
+        let guide_length = target
+            .map_or(0.0, |(_, position)| start.distance(position))
+            .max((cursor - start).dot(direction));
+        gizmos.line_2d(start, start + direction * guide_length, ANGLE_GUIDE_COLOR);
+        if let Some((_, position)) = target {
+            draw_dashed_line(&mut gizmos, start, position, CONNECTION_PREVIEW_COLOR);
+        }
+        return;
     }
+
+    draw_dashed_line(&mut gizmos, start, cursor, CONNECTION_PREVIEW_COLOR);
 }
 
 fn draw_dashed_line(gizmos: &mut Gizmos, start: Vec2, end: Vec2, color: Color) {
@@ -722,32 +3587,21 @@ fn draw_dashed_line(gizmos: &mut Gizmos, start: Vec2, end: Vec2, color: Color) {
 fn draw_grid(
     mut gizmos: Gizmos,
     grid_settings: Res<GridSettings>,
-    camera_query: Query<(&Transform), With<Camera2d>>,
-    windows: Query<&Window>,
-    editor_camera: Res<EditorCamera>,
+    visible_canvas: VisibleCanvas,
+    png_export_state: Res<PngExportState>,
+    mut editor_stats: ResMut<EditorStats>,
 ) {
-    if !grid_settings.snap_to_grid {
+    editor_stats.grid_lines = 0;
+    if !grid_settings.snap_to_grid || png_export_state.hides_grid() {
         return;
     }
 
-    let Ok(window) = windows.single() else {
-        return;
-    };
-    let Ok(camera_transform) = camera_query.single() else {
+    // Visible bounds in world space, across both windows
+    let Some(bounds) = visible_canvas.rect() else {
         return;
     };
-
-    // Get the viewport dimensions in world space
-    let window_size = Vec2::new(window.width(), window.height());
-    let half_width = window_size.x * 0.5 * editor_camera.zoom;
-    let half_height = window_size.y * 0.5 * editor_camera.zoom;
-
-    // Calculate visible bounds in world space
-    let camera_pos = camera_transform.translation.xy();
-    let min_x = camera_pos.x - half_width;
-    let max_x = camera_pos.x + half_width;
-    let min_y = camera_pos.y - half_height;
-    let max_y = camera_pos.y + half_height;
+    let (min_x, min_y) = bounds.min.into();
+    let (max_x, max_y) = bounds.max.into();
 
     let grid_size = grid_settings.grid_size;
     let color = Color::srgba(0.3, 0.3, 0.3, 0.2);
@@ -759,6 +3613,7 @@ fn draw_grid(
     let end_x = ((max_x + buffer) / grid_size).ceil() as i32;
     let start_y = ((min_y - buffer) / grid_size).floor() as i32;
     let end_y = ((max_y + buffer) / grid_size).ceil() as i32;
+    editor_stats.grid_lines = (end_x - start_x + 1 + end_y - start_y + 1).max(0) as u32;
 
     // Draw vertical lines
     for i in start_x..=end_x {
@@ -792,12 +3647,140 @@ pub fn spawn_node(
                 id: node_data.id,
                 data: node_data.clone(),
             },
-            Transform::from_translation(node_data.position.extend(0.0)),
+            Transform::from_translation(node_data.position.extend(node_depth(node_data.z_order))),
+            node_sprite(node_data, node_images),
+        ))
+        .id()
+}
+
+/// Node sprites, kept apart from the outline sprites behind them
+type NodeSprite = (With<SkillNode>, Without<NodeOutline>);
+
+/// Keeps a `NodeOutline` sprite behind every node while high contrast mode is
+/// on, sized a little larger than the node and tinted black
+fn update_node_outlines(
+    mut commands: Commands,
+    theme_settings: Res<ThemeSettings>,
+    node_query: Query<(Entity, &Sprite), NodeSprite>,
+    mut outline_query: Query<(&ChildOf, &mut Sprite, &mut Visibility), With<NodeOutline>>,
+) {
+    if !theme_settings.high_contrast {
+        for (_, _, mut visibility) in outline_query.iter_mut() {
+            visibility.set_if_neq(Visibility::Hidden);
+        }
+        return;
+    }
+
+    let mut outlined = HashSet::new();
+    for (child_of, mut outline, mut visibility) in outline_query.iter_mut() {
+        let Ok((entity, sprite)) = node_query.get(child_of.parent()) else {
+            continue;
+        };
+        outlined.insert(entity);
+        // Same image so the outline follows the node's shape
+        if outline.image != sprite.image {
+            outline.image = sprite.image.clone();
+        }
+        let size = sprite
+            .custom_size
+            .map(|size| size + Vec2::splat(2.0 * HIGH_CONTRAST_OUTLINE_WIDTH));
+        if outline.custom_size != size {
+            outline.custom_size = size;
+        }
+        visibility.set_if_neq(Visibility::Inherited);
+    }
+
+    for (entity, sprite) in node_query.iter() {
+        if outlined.contains(&entity) {
+            continue;
+        }
+        commands.entity(entity).with_child((
+            NodeOutline,
             Sprite {
-                custom_size: Some(Vec2::splat(60.0)),
-                image: node_images.skill_node.clone(),
+                image: sprite.image.clone(),
+                custom_size: sprite
+                    .custom_size
+                    .map(|size| size + Vec2::splat(2.0 * HIGH_CONTRAST_OUTLINE_WIDTH)),
+                color: Color::BLACK,
                 ..default()
             },
-        ))
-        .id()
+            Transform::from_xyz(0.0, 0.0, -0.05),
+        ));
+    }
+}
+
+/// Applies high contrast mode's thicker gizmo lines and larger UI text, or
+/// restores the defaults, whenever the theme changes
+fn apply_high_contrast(
+    theme_settings: Res<ThemeSettings>,
+    mut gizmo_config_store: ResMut<GizmoConfigStore>,
+    mut contexts: EguiContexts,
+) {
+    if !theme_settings.is_changed() {
+        return;
+    }
+
+    let (line_scale, font_scale) = if theme_settings.high_contrast {
+        (HIGH_CONTRAST_LINE_SCALE, HIGH_CONTRAST_FONT_SCALE)
+    } else {
+        (1.0, 1.0)
+    };
+    let (config, _) = gizmo_config_store.config_mut::<DefaultGizmoConfigGroup>();
+    config.line.width = GizmoLineConfig::default().width * line_scale;
+
+    let text_styles = egui::Style::default().text_styles;
+    contexts.ctx_mut().style_mut(|style| {
+        style.text_styles = text_styles
+            .into_iter()
+            .map(|(text_style, mut font_id)| {
+                font_id.size *= font_scale;
+                (text_style, font_id)
+            })
+            .collect();
+    });
+}
+
+/// While File > Print Layout is open, tiles the tree's bounds with pages and
+/// draws each page's printable area. The lines show up in the rendered pages
+/// too, marking where neighboring pages meet.
+fn update_print_layout(
+    mut gizmos: Gizmos,
+    editor_state: Res<EditorState>,
+    mut print_layout: ResMut<PrintLayout>,
+    node_query: Query<&SkillNode>,
+    node_type_styles: Res<NodeTypeStyles>,
+) {
+    if !editor_state.show_print_layout {
+        return;
+    }
+
+    let bounds = node_query
+        .iter()
+        .map(|node| {
+            Rect::from_center_size(
+                node.data.position,
+                Vec2::splat(node_type_styles.node_size(&node.data)),
+            )
+        })
+        .reduce(|a, b| a.union(b));
+    let Some(bounds) = bounds else {
+        print_layout.pages.clear();
+        print_layout.columns = 0;
+        return;
+    };
+    print_layout.lay_out(bounds);
+
+    for page in &print_layout.pages {
+        gizmos.rect_2d(page.center(), page.size(), PRINT_PAGE_COLOR);
+    }
+}
+
+/// Sprite of a freshly spawned node, before `update_node_visuals` styles it
+pub fn node_sprite(node_data: &SkillNodeData, node_images: &NodeImages) -> Sprite {
+    Sprite {
+        custom_size: Some(Vec2::splat(node_data.node_type.size())),
+        image: node_images.get(&node_data.image_name),
+        color: Color::srgb_from_array(node_data.node_type.tint()),
+        ..default()
+    }
 }
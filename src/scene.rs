@@ -0,0 +1,163 @@
+use crate::components::*;
+use crate::fs::rotate_backups;
+use crate::node_sprite;
+use crate::storage;
+use bevy::prelude::*;
+use bevy::scene::serde::SceneDeserializer;
+use serde::de::DeserializeSeed;
+use std::path::{Path, PathBuf};
+
+/// Double extension of skill trees saved as Bevy scenes
+pub const SCENE_EXTENSION: &str = "scn.ron";
+
+pub fn is_scene_file(path: &Path) -> bool {
+    path.to_str().is_some_and(|path| {
+        path.to_ascii_lowercase()
+            .ends_with(&format!(".{}", SCENE_EXTENSION))
+    })
+}
+
+/// `path` with any `.ron` or `.scn.ron` swapped for `.scn.ron`
+pub fn with_scene_extension(path: &str) -> PathBuf {
+    let lower = path.to_ascii_lowercase();
+    let stem_len = [".scn.ron", ".ron"]
+        .iter()
+        .find(|suffix| lower.ends_with(*suffix))
+        .map_or(path.len(), |suffix| path.len() - suffix.len());
+    PathBuf::from(format!("{}.{}", &path[..stem_len], SCENE_EXTENSION))
+}
+
+/// The parts of `SkillTreeData` that aren't entities, stored in scene files
+/// as a resource. Only present in the world while a scene is being saved or
+/// has just been spawned.
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource, Default)]
+pub struct SceneTreeData {
+    pub connections: Vec<ConnectionData>,
+    pub tree_name: String,
+    pub tree_description: String,
+}
+
+/// Saves the tree as a `DynamicScene` once commands are applied. Every node
+/// entity is captured with its `SkillNode` and `Transform`. Sprites are left
+/// out since their image handles only mean something to the running asset
+/// server, and are rebuilt from the node data on load.
+pub fn queue_scene_save(
+    commands: &mut Commands,
+    path: PathBuf,
+    skill_tree_data: &SkillTreeData,
+    max_backups: u32,
+) {
+    // Taken now, as the tree may be cleared before the command runs
+    let mut ids: Vec<&u32> = skill_tree_data.nodes.keys().collect();
+    ids.sort_unstable();
+    let entities: Vec<Entity> = ids.iter().map(|id| skill_tree_data.nodes[*id]).collect();
+    let tree_data = SceneTreeData {
+        connections: skill_tree_data.connections.clone(),
+        tree_name: skill_tree_data.tree_name.clone(),
+        tree_description: skill_tree_data.tree_description.clone(),
+    };
+
+    commands.queue(move |world: &mut World| {
+        world.insert_resource(tree_data);
+        let scene = DynamicSceneBuilder::from_world(world)
+            .deny_all()
+            .allow_component::<SkillNode>()
+            .allow_component::<Transform>()
+            .allow_resource::<SceneTreeData>()
+            .extract_entities(entities.into_iter())
+            .extract_resources()
+            .build();
+        world.remove_resource::<SceneTreeData>();
+
+        let result = scene
+            .serialize(&world.resource::<AppTypeRegistry>().read())
+            .map_err(|e| e.to_string())
+            .and_then(|contents| {
+                if let Err(e) = rotate_backups(&path, max_backups) {
+                    warn!("Failed to back up {}: {}", path.display(), e);
+                }
+                storage::write(&path, contents).map_err(|e| e.to_string())
+            });
+        match result {
+            Ok(()) => {
+                info!("Skill tree scene saved to {}", path.display());
+                storage::offer_download(&path);
+            }
+            Err(e) => {
+                error!(
+                    "Failed to save skill tree scene to {}: {}",
+                    path.display(),
+                    e
+                );
+                world.resource_mut::<TimedToast>().error(format!(
+                    "Failed to save {}: {}",
+                    path.display(),
+                    e
+                ));
+            }
+        }
+    });
+}
+
+/// Parses a scene file and hands it to the `SceneSpawner`. The nodes appear
+/// once the spawner runs, and `attach_scene_nodes` finishes them off.
+pub fn queue_scene_load(commands: &mut Commands, path: PathBuf) {
+    commands.queue(move |world: &mut World| match read_scene(world, &path) {
+        Ok(scene) => {
+            let handle = world.resource_mut::<Assets<DynamicScene>>().add(scene);
+            world.resource_mut::<SceneSpawner>().spawn_dynamic(handle);
+            info!("Skill tree scene loaded from {}", path.display());
+        }
+        Err(e) => {
+            error!("Failed to load skill tree scene {}: {}", path.display(), e);
+            world.resource_mut::<TimedToast>().error(format!(
+                "Failed to load {}: {}",
+                path.display(),
+                e
+            ));
+        }
+    });
+}
+
+fn read_scene(world: &World, path: &Path) -> Result<DynamicScene, Box<dyn std::error::Error>> {
+    let contents = storage::read_to_string(path)?;
+    let registry = world.resource::<AppTypeRegistry>().read();
+    let mut deserializer = ron::de::Deserializer::from_str(&contents)?;
+    let scene = SceneDeserializer {
+        type_registry: &registry,
+    }
+    .deserialize(&mut deserializer)?;
+    Ok(scene)
+}
+
+/// Nodes a scene just spawned, which don't have a sprite yet
+type NewSceneNode = (Added<SkillNode>, Without<Sprite>);
+
+/// Gives nodes spawned from a scene their sprites and registers them with
+/// the tree, then takes over the scene's connections and tree details
+pub fn attach_scene_nodes(
+    mut commands: Commands,
+    new_nodes: Query<(Entity, &SkillNode), NewSceneNode>,
+    scene_tree_data: Option<Res<SceneTreeData>>,
+    mut skill_tree_data: ResMut<SkillTreeData>,
+    mut editor_state: ResMut<EditorState>,
+    mut camera_transition: ResMut<CameraTransition>,
+    node_images: Res<NodeImages>,
+) {
+    for (entity, node) in new_nodes.iter() {
+        commands
+            .entity(entity)
+            .insert(node_sprite(&node.data, &node_images));
+        skill_tree_data.nodes.insert(node.id, entity);
+        editor_state.next_node_id = editor_state.next_node_id.max(node.id + 1);
+    }
+
+    if let Some(scene_tree_data) = scene_tree_data {
+        skill_tree_data.connections = scene_tree_data.connections.clone();
+        skill_tree_data.tree_name = scene_tree_data.tree_name.clone();
+        skill_tree_data.tree_description = scene_tree_data.tree_description.clone();
+        commands.remove_resource::<SceneTreeData>();
+        camera_transition.pending = true;
+    }
+}
@@ -0,0 +1,89 @@
+/// Score for every matched character
+const MATCH_SCORE: i32 = 1;
+/// Extra score when a match directly follows the previous one
+const CONSECUTIVE_BONUS: i32 = 5;
+/// Extra score when a match starts a word
+const WORD_START_BONUS: i32 = 8;
+
+/// A fuzzy match of a query against some text
+pub struct FuzzyMatch {
+    pub score: i32,
+    /// Char indices into the text of each matched query character
+    pub indices: Vec<usize>,
+}
+
+/// Matches `query` as a case-insensitive subsequence of `text`, preferring
+/// consecutive runs and word starts. Returns `None` when some query
+/// character can't be matched in order.
+///
+/// `best[i][j]` is the best score with query char `i` matched at text char
+/// `j`. Keeping a running best over earlier columns makes it O(n·m).
+pub fn fuzzy_match(query: &str, text: &str) -> Option<FuzzyMatch> {
+    let query: Vec<char> = query.chars().flat_map(char::to_lowercase).collect();
+    let text: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = text
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+    if query.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            indices: Vec::new(),
+        });
+    }
+
+    let (m, n) = (query.len(), text.len());
+    let mut best = vec![vec![None::<i32>; n]; m];
+    // Column of the previous query char's match that `best[i][j]` built on
+    let mut from = vec![vec![0usize; n]; m];
+
+    for i in 0..m {
+        // Best of `best[i - 1][k]` for k < j - 1, with its column
+        let mut running: Option<(i32, usize)> = None;
+        for j in 0..n {
+            if i > 0
+                && j >= 2
+                && let Some(score) = best[i - 1][j - 2]
+                && running.is_none_or(|(best_score, _)| score > best_score)
+            {
+                running = Some((score, j - 2));
+            }
+            if lower[j] != query[i] {
+                continue;
+            }
+
+            let word_start = j == 0 || !text[j - 1].is_alphanumeric();
+            let gain = MATCH_SCORE + if word_start { WORD_START_BONUS } else { 0 };
+
+            if i == 0 {
+                best[i][j] = Some(gain);
+                continue;
+            }
+            let consecutive = (j >= 1)
+                .then(|| best[i - 1][j - 1])
+                .flatten()
+                .map(|score| (score + CONSECUTIVE_BONUS, j - 1));
+            let candidate = match (consecutive, running) {
+                (Some(a), Some(b)) => Some(if a.0 >= b.0 { a } else { b }),
+                (a, b) => a.or(b),
+            };
+            if let Some((score, k)) = candidate {
+                best[i][j] = Some(score + gain);
+                from[i][j] = k;
+            }
+        }
+    }
+
+    let (mut j, score) = best[m - 1]
+        .iter()
+        .enumerate()
+        .filter_map(|(j, score)| score.map(|score| (j, score)))
+        .max_by_key(|&(_, score)| score)?;
+
+    let mut indices = vec![0; m];
+    for i in (0..m).rev() {
+        indices[i] = j;
+        j = from[i][j];
+    }
+    Some(FuzzyMatch { score, indices })
+}
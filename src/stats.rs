@@ -0,0 +1,103 @@
+use crate::components::*;
+use std::collections::{BTreeMap, HashSet};
+
+/// Every modifier for one stat name, folded per modifier type
+#[derive(Clone, Copy, Debug)]
+pub struct StatTotal {
+    pub base: f32,
+    pub flat: f32,
+    pub multiplier: f32,
+    pub percentage: f32,
+    /// Tightest bounds set by any of the stat's modifiers
+    pub min: Option<f32>,
+    pub max: Option<f32>,
+}
+
+impl Default for StatTotal {
+    fn default() -> Self {
+        Self {
+            base: 0.0,
+            flat: 0.0,
+            multiplier: 1.0,
+            percentage: 0.0,
+            min: None,
+            max: None,
+        }
+    }
+}
+
+impl StatTotal {
+    pub fn add(&mut self, modifier: &StatModifier) {
+        match modifier.modifier_type {
+            ModifierType::Base => self.base += modifier.value,
+            ModifierType::Flat => self.flat += modifier.value,
+            ModifierType::Multiplicative => self.multiplier *= modifier.value,
+            ModifierType::Percentage => self.percentage += modifier.value,
+        }
+        if let Some(min) = modifier.min_value {
+            self.min = Some(self.min.map_or(min, |current| current.max(min)));
+        }
+        if let Some(max) = modifier.max_value {
+            self.max = Some(self.max.map_or(max, |current| current.min(max)));
+        }
+    }
+
+    /// Layered as Base, then Flat, then Multiplicative, then Percentage
+    pub fn total(&self) -> f32 {
+        (self.base + self.flat) * self.multiplier * (1.0 + self.percentage / 100.0)
+    }
+
+    /// `total` limited to the stat's bounds
+    pub fn clamped_total(&self) -> f32 {
+        let mut total = self.total();
+        if let Some(max) = self.max {
+            total = total.min(max);
+        }
+        if let Some(min) = self.min {
+            total = total.max(min);
+        }
+        total
+    }
+
+    pub fn is_clamped(&self) -> bool {
+        self.clamped_total() != self.total()
+    }
+}
+
+/// One-line description of a modifier, e.g. `+5% Fire Damage` or `10 Life`
+pub fn format_modifier(modifier: &StatModifier) -> String {
+    let name = &modifier.stat_name;
+    let value = modifier.value;
+    match modifier.modifier_type {
+        ModifierType::Base => format!("Base {value} {name}"),
+        ModifierType::Flat => format!("{value} {name}"),
+        ModifierType::Multiplicative => format!("×{value} {name}"),
+        ModifierType::Percentage => format!("{value:+}% {name}"),
+    }
+}
+
+/// Totals per stat name across `nodes`, sorted by name. Every node in `nodes`
+/// counts as allocated when checking stat conditions.
+pub fn aggregate_stats<'a>(
+    nodes: impl IntoIterator<Item = &'a SkillNodeData>,
+) -> BTreeMap<String, StatTotal> {
+    let nodes: Vec<&SkillNodeData> = nodes.into_iter().collect();
+    let allocated: HashSet<u32> = nodes.iter().map(|node| node.id).collect();
+
+    let mut totals: BTreeMap<String, StatTotal> = BTreeMap::new();
+    let modifiers = nodes
+        .iter()
+        .flat_map(|node| &node.stats)
+        .filter(|modifier| {
+            modifier
+                .condition
+                .is_none_or(|condition| condition.is_met(&allocated))
+        });
+    for modifier in modifiers {
+        totals
+            .entry(modifier.stat_name.clone())
+            .or_default()
+            .add(modifier);
+    }
+    totals
+}
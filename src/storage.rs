@@ -0,0 +1,288 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// Everything the editor reads and writes goes through here. Native builds use
+// the file system. `std::fs` isn't available in the browser, so web builds
+// keep files in `localStorage` instead, keyed by their path, and hand them to
+// the user through browser downloads and uploads.
+#[cfg(not(target_arch = "wasm32"))]
+pub use native::*;
+#[cfg(target_arch = "wasm32")]
+pub use web::*;
+
+pub fn read_to_string(path: impl AsRef<Path>) -> io::Result<String> {
+    String::from_utf8(read(path)?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use super::*;
+    use std::fs;
+
+    /// Files live on disk, so there's nothing to upload or download
+    pub const BROWSER_STORAGE: bool = false;
+
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    pub fn write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+        fs::write(path, contents)
+    }
+
+    pub fn copy(from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Result<()> {
+        fs::copy(from, to).map(|_| ())
+    }
+
+    pub fn rename(from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    pub fn remove_file(path: impl AsRef<Path>) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    /// Whether `path` is an existing file
+    pub fn exists(path: impl AsRef<Path>) -> bool {
+        path.as_ref().is_file()
+    }
+
+    pub fn modified(path: impl AsRef<Path>) -> Option<SystemTime> {
+        fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+
+    /// Whether both paths name the same file once links and `..` are resolved
+    pub fn same_file(a: impl AsRef<Path>, b: impl AsRef<Path>) -> io::Result<bool> {
+        Ok(fs::canonicalize(a)? == fs::canonicalize(b)?)
+    }
+
+    /// Files directly inside `dir`
+    pub fn list_files(dir: impl AsRef<Path>) -> Vec<PathBuf> {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file())
+            .collect()
+    }
+
+    /// Hands a written file to the user. It's already on disk, so this does
+    /// nothing natively.
+    pub fn offer_download(_path: impl AsRef<Path>) {}
+
+    pub fn request_upload(_extensions: &[&str]) {}
+
+    pub fn take_uploads() -> Vec<PathBuf> {
+        Vec::new()
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod web {
+    use super::*;
+    use base64::Engine;
+    use base64::engine::general_purpose::STANDARD;
+    use std::sync::Mutex;
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen::closure::Closure;
+    use web_sys::{Blob, HtmlAnchorElement, HtmlInputElement, Storage, Url};
+
+    /// Files live in `localStorage`, and reach the user as downloads
+    pub const BROWSER_STORAGE: bool = true;
+
+    /// Prefix of every `localStorage` key, so the editor's files don't mix
+    /// with anything else stored for the page
+    const KEY_PREFIX: &str = "skill_tree_editor/";
+
+    /// Files uploaded since the last `take_uploads`. The upload finishes in a
+    /// browser callback, outside any system.
+    static UPLOADS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+    fn js_error(value: wasm_bindgen::JsValue) -> io::Error {
+        io::Error::other(format!("{:?}", value))
+    }
+
+    fn storage() -> io::Result<Storage> {
+        web_sys::window()
+            .ok_or_else(|| io::Error::other("No browser window"))?
+            .local_storage()
+            .map_err(js_error)?
+            .ok_or_else(|| io::Error::other("localStorage is unavailable"))
+    }
+
+    /// Path as stored, relative to the working directory with `/` separators
+    fn normalize(path: &Path) -> String {
+        let path = path.to_string_lossy().replace('\\', "/");
+        let mut path = path.as_str();
+        while let Some(rest) = path.strip_prefix("./") {
+            path = rest;
+        }
+        if path == "." {
+            String::new()
+        } else {
+            path.to_string()
+        }
+    }
+
+    fn key(path: &Path) -> String {
+        format!("{}{}", KEY_PREFIX, normalize(path))
+    }
+
+    fn not_found(path: &Path) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} is not in browser storage", path.display()),
+        )
+    }
+
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Vec<u8>> {
+        let path = path.as_ref();
+        let encoded = storage()?
+            .get_item(&key(path))
+            .map_err(js_error)?
+            .ok_or_else(|| not_found(path))?;
+        STANDARD
+            .decode(encoded)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Stored as base64 since `localStorage` only holds strings, and files
+    /// such as PNG exports are binary
+    pub fn write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+        storage()?
+            .set_item(&key(path.as_ref()), &STANDARD.encode(contents))
+            .map_err(js_error)
+    }
+
+    pub fn copy(from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Result<()> {
+        write(to, read(from)?)
+    }
+
+    pub fn rename(from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Result<()> {
+        copy(&from, to)?;
+        remove_file(from)
+    }
+
+    pub fn remove_file(path: impl AsRef<Path>) -> io::Result<()> {
+        storage()?
+            .remove_item(&key(path.as_ref()))
+            .map_err(js_error)
+    }
+
+    pub fn exists(path: impl AsRef<Path>) -> bool {
+        storage()
+            .ok()
+            .and_then(|storage| storage.get_item(&key(path.as_ref())).ok().flatten())
+            .is_some()
+    }
+
+    /// `localStorage` keeps no timestamps
+    pub fn modified(_path: impl AsRef<Path>) -> Option<SystemTime> {
+        None
+    }
+
+    pub fn same_file(a: impl AsRef<Path>, b: impl AsRef<Path>) -> io::Result<bool> {
+        Ok(normalize(a.as_ref()) == normalize(b.as_ref()))
+    }
+
+    pub fn list_files(dir: impl AsRef<Path>) -> Vec<PathBuf> {
+        let dir = dir.as_ref();
+        let Ok(storage) = storage() else {
+            return Vec::new();
+        };
+        let mut prefix = key(dir);
+        if !prefix.ends_with('/') {
+            prefix.push('/');
+        }
+        let count = storage.length().unwrap_or(0);
+        (0..count)
+            .filter_map(|index| storage.key(index).ok().flatten())
+            .filter_map(|key| {
+                let name = key.strip_prefix(&prefix)?;
+                (!name.is_empty() && !name.contains('/')).then(|| dir.join(name))
+            })
+            .collect()
+    }
+
+    /// Starts a browser download of a stored file, named after its path
+    pub fn offer_download(path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        if let Err(e) = download(path) {
+            bevy::log::error!("Failed to download {}: {}", path.display(), e);
+        }
+    }
+
+    fn download(path: &Path) -> io::Result<()> {
+        let bytes = read(path)?;
+        let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(bytes.as_slice()));
+        let blob = Blob::new_with_u8_array_sequence(&parts).map_err(js_error)?;
+        let url = Url::create_object_url_with_blob(&blob).map_err(js_error)?;
+
+        let document = web_sys::window()
+            .and_then(|window| window.document())
+            .ok_or_else(|| io::Error::other("No browser document"))?;
+        let anchor: HtmlAnchorElement = document
+            .create_element("a")
+            .map_err(js_error)?
+            .dyn_into()
+            .map_err(|_| io::Error::other("Failed to create a link"))?;
+        anchor.set_href(&url);
+        anchor.set_download(&path.file_name().unwrap_or_default().to_string_lossy());
+        anchor.click();
+        Url::revoke_object_url(&url).map_err(js_error)
+    }
+
+    /// Opens the browser's file picker. The chosen file is copied into
+    /// storage under its name and reported by `take_uploads`.
+    pub fn request_upload(extensions: &[&str]) {
+        if let Err(e) = open_file_picker(extensions) {
+            bevy::log::error!("Failed to open the file picker: {}", e);
+        }
+    }
+
+    fn open_file_picker(extensions: &[&str]) -> io::Result<()> {
+        let document = web_sys::window()
+            .and_then(|window| window.document())
+            .ok_or_else(|| io::Error::other("No browser document"))?;
+        let input: HtmlInputElement = document
+            .create_element("input")
+            .map_err(js_error)?
+            .dyn_into()
+            .map_err(|_| io::Error::other("Failed to create a file input"))?;
+        input.set_type("file");
+        let accept: Vec<String> = extensions.iter().map(|ext| format!(".{}", ext)).collect();
+        input.set_accept(&accept.join(","));
+
+        let picker = input.clone();
+        let on_change = Closure::once(move || {
+            let Some(file) = picker.files().and_then(|files| files.get(0)) else {
+                return;
+            };
+            wasm_bindgen_futures::spawn_local(async move {
+                let name = PathBuf::from(file.name());
+                let result = wasm_bindgen_futures::JsFuture::from(file.array_buffer())
+                    .await
+                    .map_err(js_error)
+                    .and_then(|buffer| write(&name, js_sys::Uint8Array::new(&buffer).to_vec()));
+                match result {
+                    Ok(()) => UPLOADS.lock().unwrap().push(name),
+                    Err(e) => bevy::log::error!("Failed to upload {}: {}", name.display(), e),
+                }
+            });
+        });
+        input.set_onchange(Some(on_change.as_ref().unchecked_ref()));
+        // The input owns the callback from here on
+        on_change.forget();
+        input.click();
+        Ok(())
+    }
+
+    pub fn take_uploads() -> Vec<PathBuf> {
+        std::mem::take(&mut *UPLOADS.lock().unwrap())
+    }
+}
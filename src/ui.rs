@@ -1,26 +1,92 @@
-use super::spawn_node;
+use super::{
+    ASSETS_DIR, EditTools, Selection, connection_midpoint, create_node_at, delete_node, framing,
+    framing_axes, mark_created, open_secondary_viewport, snap_to_grid_logic, spawn_node,
+};
 use crate::components::*;
-use crate::fs::{load_skill_tree, save_skill_tree};
+use crate::export::csv::{export_csv, read_csv_nodes};
+use crate::export::dot::{export_dot, read_dot};
+use crate::export::svg::export_svg;
+use crate::fs::{
+    MAX_BACKUPS, SKILL_TREE_EXTENSIONS, SaveFormat, is_image_file, is_skill_tree_file,
+    list_backups, load_skill_tree, load_skill_tree_lenient, save_editor_config, save_skill_tree,
+    save_templates, write_skill_tree, write_skill_tree_binary,
+};
+use crate::graph;
+use crate::scene::{is_scene_file, queue_scene_load, queue_scene_save, with_scene_extension};
+use crate::search::fuzzy_match;
+use crate::stats;
+use crate::storage;
+use crate::validation::{self, ReportTarget, ValidationReport};
+use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin};
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use bevy_egui::{EguiContexts, egui};
-use std::path::PathBuf;
-use std::{fs, mem};
+use bevy_skill_tree::{first_start_node_id, migration};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::mem;
+use std::path::{Path, PathBuf};
 
-pub fn ui_system(
+/// How far from the original a duplicated node is placed
+const DUPLICATE_OFFSET: f32 = 40.0;
+const GRID_SIZE_PRESETS: [f32; 5] = [25.0, 50.0, 100.0, 150.0, 200.0];
+/// Added to the smallest radius that spans an arc's nodes when fixing it
+const ARC_FIX_MARGIN: f32 = 10.0;
+
+/// Settings saved to the editor config file along with `EditorState`
+#[derive(SystemParam)]
+pub struct SavedSettings<'w> {
+    node_type_colors: ResMut<'w, NodeTypeColors>,
+    node_type_hit_radius: ResMut<'w, NodeTypeHitRadius>,
+    connection_style_presets: ResMut<'w, ConnectionStylePresets>,
+    hot_reload: ResMut<'w, HotReloadSettings>,
+    topology_metrics: ResMut<'w, TopologyMetrics>,
+    theme_settings: ResMut<'w, ThemeSettings>,
+}
+
+/// Graph analyses the side panel toggles and reports on
+#[derive(SystemParam)]
+pub struct AnalysisViews<'w> {
+    reachability: ResMut<'w, ReachabilitySet>,
+    start_paths: ResMut<'w, StartPaths>,
+    centrality: Res<'w, CentralityColoring>,
+    dependency_highlight: ResMut<'w, DependencyHighlight>,
+    tier_filter: ResMut<'w, TierFilter>,
+    cycle_warnings: Res<'w, CycleWarnings>,
+    validation_warnings: Res<'w, ValidationWarnings>,
+}
+
+/// The editor UI systems, chained in the order egui needs to lay out their
+/// panels
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UiSystems;
+
+/// Menu bar along the top of the window
+#[allow(clippy::too_many_arguments)]
+pub fn menu_bar_ui(
     mut contexts: EguiContexts,
+    mut commands: Commands,
     mut editor_state: ResMut<EditorState>,
     mut skill_tree_data: ResMut<SkillTreeData>,
-    mut selected_node: ResMut<SelectedNode>,
-    mut selected_connection: ResMut<SelectedConnection>,
-    mut node_query: Query<&mut SkillNode>,
-    mut commands: Commands,
-    connection_mode: Res<ConnectionMode>,
+    selection: Selection,
+    node_query: Query<&mut SkillNode>,
     mut grid_settings: ResMut<GridSettings>,
     node_images: Res<NodeImages>,
+    mut toast: ResMut<TimedToast>,
+    mut editor_camera: ResMut<EditorCamera>,
+    mut camera_transition: ResMut<CameraTransition>,
+    mut secondary_viewport: ResMut<SecondaryViewport>,
+    mut hot_reload: ResMut<HotReloadSettings>,
+    mut centrality: ResMut<CentralityColoring>,
 ) {
+    let Selection {
+        mut selected_node,
+        mut selected_connection,
+        mut multi_selected,
+    } = selection;
     let ctx = contexts.ctx_mut();
+    let show_panels = !editor_state.presentation_mode;
 
-    egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
+    egui::TopBottomPanel::top("menu_bar").show_animated(ctx, show_panels, |ui| {
         egui::menu::bar(ui, |ui| {
             ui.menu_button("File", |ui| {
                 if ui.button("New").clicked() {
@@ -34,17 +100,23 @@ pub fn ui_system(
                             &mut skill_tree_data,
                             &mut selected_node,
                             &mut selected_connection,
+                            &mut multi_selected,
                         );
+                        open_tree_name_prompt(&mut editor_state);
                     }
                     ui.close_menu();
                 }
 
                 if ui.button("Save").clicked() {
                     if let Some(path) = editor_state.current_file_path.clone() {
-                        save_skill_tree(
-                            path.to_str().unwrap_or("skill_tree.ron"),
+                        save_tree_to_path(
+                            &path,
+                            &mut commands,
+                            &mut hot_reload,
                             &skill_tree_data,
                             &node_query,
+                            saved_camera_state(&editor_state, &editor_camera),
+                            editor_state.max_backups,
                         );
                         editor_state.dirty = false;
                     } else {
@@ -77,7 +149,100 @@ pub fn ui_system(
                     ui.close_menu();
                 }
 
+                if ui.button("Save As Scene...").clicked() {
+                    let current = editor_state
+                        .current_file_path
+                        .as_ref()
+                        .and_then(|p| p.file_name())
+                        .and_then(|os_str| os_str.to_str())
+                        .unwrap_or("untitled.ron")
+                        .to_string();
+                    editor_state.save_as_file_name_buffer = with_scene_extension(&current)
+                        .to_string_lossy()
+                        .into_owned();
+                    editor_state.show_save_as_dialog = true;
+                    editor_state.save_as_scene = true;
+                    editor_state.save_as_show_overwrite_prompt = false;
+                    editor_state.save_as_conflict_path = None;
+                    editor_state.next_action_after_save_as = NextActionAfterSaveAs::None;
+                    ui.close_menu();
+                }
+
+                let backups = editor_state
+                    .current_file_path
+                    .as_deref()
+                    .map(list_backups)
+                    .unwrap_or_default();
+                ui.add_enabled_ui(!backups.is_empty(), |ui| {
+                    ui.menu_button("Backups", |ui| {
+                        if editor_state.dirty {
+                            ui.label("Save or discard your changes to restore a backup");
+                        }
+                        for backup in backups {
+                            let name = backup.file_name().unwrap_or_default().to_string_lossy();
+                            let modified = storage::modified(&backup)
+                                .and_then(|time| time.elapsed().ok())
+                                .map(|age| format!(" ({} ago)", format_age(age)))
+                                .unwrap_or_default();
+                            if ui
+                                .add_enabled(
+                                    !editor_state.dirty,
+                                    egui::Button::new(format!("{}{}", name, modified)),
+                                )
+                                .clicked()
+                            {
+                                restore_backup(
+                                    &backup,
+                                    &mut commands,
+                                    &mut editor_state,
+                                    &mut skill_tree_data,
+                                    &mut selected_node,
+                                    &mut selected_connection,
+                                    &mut multi_selected,
+                                    &node_images,
+                                    &mut camera_transition,
+                                    &mut toast,
+                                );
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                });
+
+                ui.menu_button("Export", |ui| {
+                    if ui.button("SVG...").clicked() {
+                        open_export_dialog(&mut editor_state, ExportFormat::Svg);
+                        ui.close_menu();
+                    }
+                    if ui.button("PNG...").clicked() {
+                        open_export_dialog(&mut editor_state, ExportFormat::Png);
+                        ui.close_menu();
+                    }
+                    if ui.button("Binary...").clicked() {
+                        open_export_dialog(&mut editor_state, ExportFormat::Binary);
+                        ui.close_menu();
+                    }
+                    if ui.button("CSV (Nodes)...").clicked() {
+                        open_export_dialog(&mut editor_state, ExportFormat::Csv);
+                        ui.close_menu();
+                    }
+                    if ui.button("DOT (Graphviz)...").clicked() {
+                        open_export_dialog(&mut editor_state, ExportFormat::Dot);
+                        ui.close_menu();
+                    }
+                });
+
+                if ui
+                    .button("Print Layout...")
+                    .on_hover_text("Split the tree across printable pages")
+                    .clicked()
+                {
+                    editor_state.show_print_layout = true;
+                    ui.close_menu();
+                }
+
                 if ui.button("Load").clicked() {
+                    editor_state.pending_dropped_file = None;
                     if editor_state.dirty {
                         editor_state.show_unsaved_changes_on_load_dialog = true;
                         editor_state.next_action_after_save_as = NextActionAfterSaveAs::None;
@@ -86,6 +251,51 @@ pub fn ui_system(
                     }
                     ui.close_menu();
                 }
+
+                if ui.button("Merge From...").clicked() {
+                    open_load_dialog_sequence(&mut editor_state);
+                    editor_state.load_dialog_mode = LoadDialogMode::Merge;
+                    ui.close_menu();
+                }
+
+                if ui.button("Compare With...").clicked() {
+                    open_load_dialog_sequence(&mut editor_state);
+                    editor_state.load_dialog_mode = LoadDialogMode::Compare;
+                    ui.close_menu();
+                }
+
+                ui.menu_button("Import", |ui| {
+                    if ui.button("CSV (Update Nodes)...").clicked() {
+                        open_import_dialog(&mut editor_state, "csv", LoadDialogMode::CsvUpdate);
+                        ui.close_menu();
+                    }
+                    if ui
+                        .button("DOT (Graphviz)...")
+                        .on_hover_text("Nodes are appended with new ids, like Merge From")
+                        .clicked()
+                    {
+                        open_import_dialog(&mut editor_state, "dot", LoadDialogMode::DotImport);
+                        ui.close_menu();
+                    }
+                    if ui
+                        .button("Import and Merge Subtree...")
+                        .on_hover_text(
+                            "Nodes keep their ids, replacing existing nodes with the same id",
+                        )
+                        .clicked()
+                    {
+                        open_load_dialog_sequence(&mut editor_state);
+                        editor_state.load_dialog_mode = LoadDialogMode::MergeSubtree;
+                        ui.close_menu();
+                    }
+                });
+            });
+            ui.menu_button("Edit", |ui| {
+                if ui.button("Validate Tree").clicked() {
+                    editor_state.validation_report =
+                        Some(run_validation(&skill_tree_data, &node_query));
+                    ui.close_menu();
+                }
             });
             ui.menu_button("View", |ui| {
                 if ui
@@ -94,356 +304,1237 @@ pub fn ui_system(
                 {
                     ui.close_menu();
                 }
+                if ui
+                    .checkbox(&mut editor_state.detach_properties, "Detach Properties")
+                    .clicked()
+                {
+                    ui.close_menu();
+                }
+                if ui
+                    .checkbox(
+                        &mut grid_settings.bundle_parallel,
+                        "Bundle Parallel Connections",
+                    )
+                    .on_hover_text("Draw connections between the same two nodes side by side")
+                    .clicked()
+                {
+                    ui.close_menu();
+                }
+                if ui
+                    .checkbox(&mut grid_settings.hide_disabled, "Hide Disabled")
+                    .on_hover_text("Leave disabled connections off the canvas")
+                    .clicked()
+                {
+                    ui.close_menu();
+                }
+                if ui
+                    .checkbox(
+                        &mut editor_state.show_performance_overlay,
+                        "Performance Overlay",
+                    )
+                    .on_hover_text("Frame times and what the editor drew this frame")
+                    .clicked()
+                {
+                    ui.close_menu();
+                }
+                if ui
+                    .checkbox(&mut centrality.enabled, "Color by Centrality")
+                    .on_hover_text(
+                        "Color nodes by how many paths between other nodes pass through them",
+                    )
+                    .clicked()
+                {
+                    ui.close_menu();
+                }
+                if ui
+                    .add(
+                        egui::Button::new("Presentation Mode")
+                            .selected(editor_state.presentation_mode)
+                            .shortcut_text("F11"),
+                    )
+                    .on_hover_text("Hide the editor panels, leaving only the tree")
+                    .clicked()
+                {
+                    editor_state.toggle_presentation_mode(&mut toast);
+                    ui.close_menu();
+                }
+                if ui
+                    .add_enabled(
+                        secondary_viewport.window.is_none(),
+                        egui::Button::new("New Viewport"),
+                    )
+                    .on_hover_text("Open a second window onto the tree with its own camera")
+                    .clicked()
+                {
+                    open_secondary_viewport(&mut commands, &mut secondary_viewport, &editor_camera);
+                    ui.close_menu();
+                }
+                if ui
+                    .checkbox(&mut secondary_viewport.editable, "Edit in Viewport")
+                    .on_hover_text("Let clicks in the viewport window edit the tree")
+                    .clicked()
+                {
+                    ui.close_menu();
+                }
+                ui.menu_button("Grid Size", |ui| {
+                    for preset in GRID_SIZE_PRESETS {
+                        if ui
+                            .radio(grid_settings.grid_size == preset, preset.to_string())
+                            .clicked()
+                        {
+                            grid_settings.grid_size = preset;
+                            ui.close_menu();
+                        }
+                    }
+                    ui.separator();
+                    if ui
+                        .button("Fit Grid to Nodes")
+                        .on_hover_text("Use the median length of the connections")
+                        .clicked()
+                    {
+                        let positions: HashMap<u32, Vec2> = node_query
+                            .iter()
+                            .map(|node| (node.id, node.data.position))
+                            .collect();
+                        match median_connection_length(&skill_tree_data.connections, &positions) {
+                            Some(length) => {
+                                grid_settings.grid_size =
+                                    ((length / 10.0).round() * 10.0).clamp(10.0, 200.0);
+                                toast.info(format!("Grid size set to {}", grid_settings.grid_size));
+                            }
+                            None => toast.error("No connections to measure"),
+                        }
+                        ui.close_menu();
+                    }
+                });
+                ui.separator();
+                if ui
+                    .add(egui::Button::new("Reset View").shortcut_text("Numpad ."))
+                    .clicked()
+                {
+                    editor_camera.reset_view();
+                    ui.close_menu();
+                }
+                for (label, shortcut, axes) in [
+                    ("Fit Width", "Ctrl+Shift+W", Vec2::X),
+                    ("Fit Height", "Ctrl+Shift+H", Vec2::Y),
+                ] {
+                    if ui
+                        .add(egui::Button::new(label).shortcut_text(shortcut))
+                        .clicked()
+                    {
+                        let positions: Vec<Vec2> =
+                            node_query.iter().map(|node| node.data.position).collect();
+                        let viewport = ctx.screen_rect().size();
+                        if let Some((center, zoom)) =
+                            framing_axes(&positions, Vec2::new(viewport.x, viewport.y), axes)
+                        {
+                            editor_camera.pan_offset = center;
+                            editor_camera.target_zoom = zoom;
+                        }
+                        ui.close_menu();
+                    }
+                }
+                if ui
+                    .add(egui::Button::new("Reset Zoom Only").shortcut_text("Numpad 1"))
+                    .clicked()
+                {
+                    editor_camera.reset_zoom();
+                    ui.close_menu();
+                }
+                ui.separator();
+                if ui.button("Settings…").clicked() {
+                    editor_state.show_settings = true;
+                    ui.close_menu();
+                }
             });
         });
     });
+}
 
-    egui::SidePanel::left("properties_panel").show(ctx, |ui| {
-        ui.heading("Skill Tree Editor");
-        ui.separator();
-        ui.checkbox(&mut grid_settings.snap_to_grid, "Snap to Grid");
-        ui.add(egui::Slider::new(&mut grid_settings.grid_size, 10.0..=200.0).text("Grid Size"));
-        ui.separator();
+/// Status bar along the bottom of the window
+#[allow(clippy::too_many_arguments)]
+pub fn status_bar_ui(
+    mut contexts: EguiContexts,
+    editor_state: Res<EditorState>,
+    skill_tree_data: Res<SkillTreeData>,
+    node_query: Query<&SkillNode>,
+    grid_settings: Res<GridSettings>,
+    editor_stats: Res<EditorStats>,
+    mut editor_camera: ResMut<EditorCamera>,
+    cursor_world_pos: Res<CursorWorldPos>,
+) {
+    let ctx = contexts.ctx_mut();
+    let show_panels = !editor_state.presentation_mode;
 
-        if connection_mode.active {
-            ui.colored_label(egui::Color32::YELLOW, "Connection Mode Active");
+    egui::TopBottomPanel::bottom("status_bar").show_animated(ctx, show_panels, |ui| {
+        ui.horizontal(|ui| {
+            ui.label(format!("Nodes: {}", skill_tree_data.nodes.len()));
+            ui.separator();
             ui.label(format!(
-                "Starting from node: {:?}",
-                connection_mode.start_node
+                "Connections: {} ({} culled)",
+                skill_tree_data.connections.len(),
+                editor_stats.culled_connections
             ));
             ui.separator();
-        }
 
-        // Connection Properties
-        if let Some(connection_index) = selected_connection.index {
-            if let Some(connection) = skill_tree_data.connections.get_mut(connection_index) {
-                ui.heading("Connection Properties");
-                ui.label(format!(
-                    "From Node {} to Node {}",
-                    connection.from_id, connection.to_id
-                ));
-
-                ui.separator();
-                ui.label("Curve Type:");
-
-                let mut curve_type_changed = false;
-                let is_straight = matches!(connection.curve_type, CurveType::Straight);
+            let mut type_counts = [0; BuiltinNodeType::ALL.len()];
+            let mut custom = 0;
+            for node in node_query.iter() {
+                let NodeType::Builtin(builtin) = node.data.node_type else {
+                    custom += 1;
+                    continue;
+                };
+                let slot = match builtin {
+                    BuiltinNodeType::Normal => 0,
+                    BuiltinNodeType::Notable => 1,
+                    BuiltinNodeType::Keystone => 2,
+                    BuiltinNodeType::Start => 3,
+                    BuiltinNodeType::Mastery => 4,
+                    BuiltinNodeType::Ascendancy => 5,
+                    BuiltinNodeType::Socket => 6,
+                };
+                type_counts[slot] += 1;
+            }
+            let [
+                normal,
+                notable,
+                keystone,
+                start,
+                mastery,
+                ascendancy,
+                socket,
+            ] = type_counts;
+            ui.label(format!("N:{normal} No:{notable} K:{keystone}"))
+                .on_hover_text("Normal, Notable and Keystone node counts");
+            if mastery + ascendancy + socket > 0 {
+                ui.label(format!("M:{mastery} A:{ascendancy} So:{socket}"))
+                    .on_hover_text("Mastery, Ascendancy and Socket node counts");
+            }
+            if custom > 0 {
+                ui.label(format!("C:{custom}"))
+                    .on_hover_text("Nodes with a custom type");
+            }
+            if start == 0 {
+                ui.colored_label(egui::Color32::from_rgb(255, 200, 100), "S:0 ⚠")
+                    .on_hover_text("The tree has no Start node");
+            } else if start > 1 {
+                ui.colored_label(
+                    egui::Color32::from_rgb(255, 200, 100),
+                    format!("S:{start} ⚠"),
+                )
+                .on_hover_text("The tree has more than one Start node");
+            } else {
+                ui.label(format!("S:{start}"));
+            }
+            ui.separator();
 
-                if ui.radio(is_straight, "Straight").clicked() {
-                    connection.curve_type = CurveType::Straight;
-                    curve_type_changed = true;
+            let coordinates = match cursor_world_pos.0 {
+                Some(position) if grid_settings.snap_to_grid => {
+                    let snapped = snap_to_grid_logic(position, grid_settings.grid_size);
+                    format!("X: {:.0} Y: {:.0}", snapped.x, snapped.y)
                 }
+                Some(position) => format!("X: {:.1} Y: {:.1}", position.x, position.y),
+                None => "—".to_string(),
+            };
+            ui.label(coordinates)
+                .on_hover_text("Cursor position on the canvas");
+            ui.separator();
 
-                if ui.radio(!is_straight, "Arc").clicked() {
-                    connection.curve_type = CurveType::Arc {
-                        radius: 100.0,
-                        clockwise: false,
-                    };
-                    curve_type_changed = true;
+            let mut zoom_percent = editor_camera.target_zoom_percent();
+            let min_percent = 100.0 / EditorCamera::MAX_ZOOM;
+            let max_percent = 100.0 / EditorCamera::MIN_ZOOM;
+            if ui
+                .add(
+                    egui::DragValue::new(&mut zoom_percent)
+                        .range(min_percent..=max_percent)
+                        .max_decimals(0)
+                        .suffix("%"),
+                )
+                .on_hover_text("Zoom level")
+                .changed()
+            {
+                editor_camera.set_target_zoom_percent(zoom_percent);
+            }
+            for preset in EditorCamera::PRESETS_PERCENT {
+                if ui.small_button(format!("{preset}%")).clicked() {
+                    editor_camera.set_target_zoom_percent(preset);
                 }
+            }
+        });
+    });
+}
 
-                if let CurveType::Arc {
-                    ref mut radius,
-                    ref mut clockwise,
-                } = connection.curve_type
-                {
-                    ui.separator();
-                    ui.label("Arc Properties:");
-
-                    if ui
-                        .add(egui::Slider::new(radius, 30.0..=500.0).text("Radius"))
-                        .changed()
-                    {
-                        curve_type_changed = true;
-                    }
-
-                    if ui.checkbox(clockwise, "Clockwise").changed() {
-                        curve_type_changed = true;
-                    }
+/// Properties of the selection, in the side panel or in the detached
+/// Properties window
+#[allow(clippy::too_many_arguments)]
+pub fn properties_panel_ui(
+    mut contexts: EguiContexts,
+    mut commands: Commands,
+    mut editor_state: ResMut<EditorState>,
+    mut skill_tree_data: ResMut<SkillTreeData>,
+    selection: Selection,
+    mut node_query: Query<&mut SkillNode>,
+    mut grid_settings: ResMut<GridSettings>,
+    mut node_images: ResMut<NodeImages>,
+    mut toast: ResMut<TimedToast>,
+    asset_server: Res<AssetServer>,
+    mut editor_camera: ResMut<EditorCamera>,
+    saved_settings: SavedSettings,
+    analysis_views: AnalysisViews,
+    edit_tools: EditTools,
+) {
+    let Selection {
+        mut selected_node,
+        mut selected_connection,
+        mut multi_selected,
+    } = selection;
+    let SavedSettings {
+        node_type_colors,
+        node_type_hit_radius,
+        mut connection_style_presets,
+        hot_reload,
+        topology_metrics,
+        theme_settings,
+    } = saved_settings;
+    let AnalysisViews {
+        mut reachability,
+        mut start_paths,
+        centrality,
+        mut dependency_highlight,
+        mut tier_filter,
+        cycle_warnings,
+        validation_warnings,
+    } = analysis_views;
+    let EditTools {
+        connection_mode,
+        mut connection_settings,
+        mut stamp_mode,
+        mut template_library,
+    } = edit_tools;
+    let ctx = contexts.ctx_mut();
+    let show_panels = !editor_state.presentation_mode;
 
-                    // Check if arc is valid and show warning
-                    let mut from_pos = None;
-                    let mut to_pos = None;
-                    for node in node_query.iter() {
-                        if node.id == connection.from_id {
-                            from_pos = Some(node.data.position);
-                        }
-                        if node.id == connection.to_id {
-                            to_pos = Some(node.data.position);
+    let side_panel = egui::SidePanel::left("properties_panel")
+        .resizable(true)
+        .default_width(editor_state.side_panel_width)
+        .min_width(150.0)
+        .max_width(500.0)
+        .show_animated(ctx, show_panels, |ui| {
+            ui.heading("Skill Tree Editor");
+            ui.separator();
+            egui::CollapsingHeader::new("Tree Properties").show(ui, |ui| {
+                ui.label("Name:");
+                if ui
+                    .text_edit_singleline(&mut skill_tree_data.tree_name)
+                    .changed()
+                {
+                    editor_state.dirty = true;
+                }
+                ui.label("Description:");
+                if ui
+                    .text_edit_multiline(&mut skill_tree_data.tree_description)
+                    .changed()
+                {
+                    editor_state.dirty = true;
+                }
+            });
+            ui.separator();
+            ui.checkbox(&mut grid_settings.snap_to_grid, "Snap to Grid");
+            ui.add(egui::Slider::new(&mut grid_settings.grid_size, 10.0..=200.0).text("Grid Size"));
+            ui.add(
+                egui::Slider::new(
+                    &mut grid_settings.connection_selection_tolerance,
+                    GridSettings::CONNECTION_TOLERANCE_RANGE,
+                )
+                .text("Connection Tolerance"),
+            )
+            .on_hover_text("How close to a connection a click selects it, in screen pixels");
+            ui.horizontal(|ui| {
+                ui.label("Alt Angle Snap")
+                    .on_hover_text("Hold Alt in connection mode to only connect at these angles");
+                egui::ComboBox::from_id_salt("angle_snap")
+                    .selected_text(format!("{}°", connection_settings.angle_snap_degrees))
+                    .show_ui(ui, |ui| {
+                        for preset in ConnectionSettings::ANGLE_SNAP_PRESETS {
+                            ui.selectable_value(
+                                &mut connection_settings.angle_snap_degrees,
+                                preset,
+                                format!("{}°", preset),
+                            );
                         }
-                    }
+                    });
+            });
+            ui.checkbox(
+                &mut connection_settings.route_around_nodes,
+                "Route Around Nodes",
+            )
+            .on_hover_text("Bend straight connections around nodes they would pass through");
+            ui.separator();
+            ui.toggle_value(&mut reachability.enabled, "Show Reachability")
+                .on_hover_text("Dim nodes that cannot be reached from a Start node");
+            ui.toggle_value(&mut start_paths.enabled, "Show Paths from Start")
+                .on_hover_text("Highlight the paths from a Start node to the selected node");
+            if start_paths.enabled && start_paths.target.is_some() {
+                if start_paths.total == 0 {
+                    ui.label("No path from a Start node");
+                } else if start_paths.total > start_paths.paths.len() {
+                    let more = if start_paths.total >= StartPaths::MAX_SEARCHED {
+                        "+"
+                    } else {
+                        ""
+                    };
+                    ui.label(format!(
+                        "Showing {} of {}{} paths",
+                        start_paths.paths.len(),
+                        start_paths.total,
+                        more
+                    ));
+                }
+                for (index, path) in start_paths.paths.iter().enumerate() {
+                    let [r, g, b, _] = StartPaths::color(index).to_srgba().to_u8_array();
+                    ui.colored_label(
+                        egui::Color32::from_rgb(r, g, b),
+                        format!(
+                            "Path {}: {} hops, cost {}",
+                            index + 1,
+                            path.nodes.len() - 1,
+                            path.cost
+                        ),
+                    );
+                }
+            }
+            if centrality.enabled {
+                ui.label("Centrality");
+                show_centrality_legend(ui);
+            }
+            ui.horizontal(|ui| {
+                ui.label("Show only tier ≥");
+                ui.add(
+                    egui::DragValue::new(&mut tier_filter.min_tier)
+                        .range(0..=SkillNodeData::MAX_TIER),
+                );
+            });
+            ui.separator();
 
-                    if let (Some(from), Some(to)) = (from_pos, to_pos) {
-                        let distance = from.distance(to);
-                        let min_radius = distance / 2.0;
+            egui::CollapsingHeader::new("Search Nodes").show(ui, |ui| {
+                ui.text_edit_singleline(&mut editor_state.node_search_query);
+                let query = editor_state.node_search_query.trim();
+                if query.is_empty() {
+                    return;
+                }
 
-                        if *radius < min_radius {
-                            ui.colored_label(
-                                egui::Color32::from_rgb(255, 200, 100),
-                                format!("⚠ Radius too small! Minimum: {:.1}", min_radius),
-                            );
-                            ui.label("Arc will display as dashed line");
+                let mut matches: Vec<_> = node_query
+                    .iter()
+                    .filter_map(|node| {
+                        let found = fuzzy_match(query, &node.data.name)?;
+                        Some((found, node.id, node.data.name.clone(), node.data.position))
+                    })
+                    .collect();
+                matches.sort_by(|a, b| b.0.score.cmp(&a.0.score).then(a.1.cmp(&b.1)));
+                if matches.is_empty() {
+                    ui.label("No matching nodes");
+                }
 
-                            if ui.button("Auto-fix radius").clicked() {
-                                *radius = min_radius + 10.0; // Add a small buffer
-                                curve_type_changed = true;
+                egui::ScrollArea::vertical()
+                    .id_salt("node_search_results")
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        for (found, id, name, position) in matches {
+                            let label = highlighted_match(&name, &found.indices, ui.style());
+                            if ui
+                                .selectable_label(selected_node.id == Some(id), label)
+                                .clicked()
+                            {
+                                selected_node.entity = skill_tree_data.nodes.get(&id).copied();
+                                selected_node.id = Some(id);
+                                selected_connection.index = None;
+                                editor_camera.pan_offset = position;
                             }
                         }
-                    }
+                    });
+            });
+            ui.separator();
 
-                    ui.add_space(5.0);
-                    ui.label("Tips:");
-                    ui.label("• Larger radius = gentler curve");
-                    ui.label("• Toggle clockwise to flip the arc");
+            if !cycle_warnings.cycles.is_empty() {
+                ui.colored_label(egui::Color32::from_rgb(255, 100, 100), "⚠ Cycle detected");
+                for cycle in &cycle_warnings.cycles {
+                    let mut ids: Vec<String> = cycle.iter().map(u32::to_string).collect();
+                    ids.push(cycle[0].to_string());
+                    ui.label(ids.join(" → "));
                 }
+                ui.separator();
+            }
 
-                if curve_type_changed {
-                    editor_state.dirty = true;
+            if let Some(message) = &validation_warnings.start_nodes {
+                ui.colored_label(
+                    egui::Color32::from_rgb(255, 100, 100),
+                    format!("⚠ {message}"),
+                );
+                ui.separator();
+            }
+
+            if !validation_warnings.messages.is_empty() {
+                for message in &validation_warnings.messages {
+                    ui.colored_label(
+                        egui::Color32::from_rgb(255, 200, 100),
+                        format!("⚠ {message}"),
+                    );
                 }
+                ui.separator();
+            }
 
+            if connection_mode.active {
+                ui.colored_label(egui::Color32::YELLOW, "Connection Mode Active");
+                ui.label(format!(
+                    "Starting from node: {:?}",
+                    connection_mode.start_node
+                ));
+                ui.label(format!(
+                    "Hold Alt to snap to {}° angles",
+                    connection_settings.angle_snap_degrees
+                ));
                 ui.separator();
-                if ui.button("Delete Connection").clicked() {
-                    skill_tree_data.connections.remove(connection_index);
-                    selected_connection.index = None;
-                    editor_state.dirty = true;
-                }
             }
-        }
-        // Node Properties
-        else if let Some(entity) = selected_node.entity {
-            if let Ok(mut node) = node_query.get_mut(entity) {
-                ui.heading("Node Properties");
-                ui.label(format!("ID: {}", node.id));
-                ui.label("Name:");
-                if ui.text_edit_singleline(&mut node.data.name).changed() {
-                    editor_state.dirty = true;
-                }
-                ui.label("Description:");
-                if ui.text_edit_multiline(&mut node.data.description).changed() {
-                    editor_state.dirty = true;
+
+            if editor_state.detach_properties {
+                ui.label("Properties are shown in a floating window (View > Detach Properties)");
+            } else {
+                properties_ui(
+                    ui,
+                    &mut commands,
+                    &mut editor_state,
+                    &mut skill_tree_data,
+                    &mut selected_node,
+                    &mut selected_connection,
+                    &mut multi_selected,
+                    &mut node_query,
+                    &mut node_images,
+                    &mut toast,
+                    &asset_server,
+                    &mut template_library,
+                    &mut connection_style_presets,
+                );
+            }
+            if let Some(id) = selected_node.id {
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.toggle_value(
+                        &mut dependency_highlight.show_predecessors,
+                        "Show Predecessors",
+                    )
+                    .on_hover_text("Highlight every node leading into this one in orange");
+                    ui.toggle_value(&mut dependency_highlight.show_successors, "Show Successors")
+                        .on_hover_text("Highlight every node this one leads to in blue");
+                });
+                if dependency_highlight.show_predecessors {
+                    ui.label(format!(
+                        "{} predecessor(s) of Node {}",
+                        dependency_highlight.predecessors.len(),
+                        id
+                    ));
                 }
-                ui.label("Image Name:");
-                if ui.text_edit_singleline(&mut node.data.image_name).changed() {
-                    editor_state.dirty = true;
+                if dependency_highlight.show_successors {
+                    ui.label(format!(
+                        "{} successor(s) of Node {}",
+                        dependency_highlight.successors.len(),
+                        id
+                    ));
                 }
-
-                ui.label("Node Type:");
-                let mut node_type_changed = false;
-                egui::ComboBox::from_label("NodeType")
-                    .selected_text(format!("{:?}", node.data.node_type))
-                    .show_ui(ui, |ui| {
-                        if ui
-                            .selectable_value(&mut node.data.node_type, NodeType::Normal, "Normal")
-                            .clicked()
-                        {
-                            node_type_changed = true;
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.menu_button("Select by Type…", |ui| {
+                    let mut node_types: Vec<NodeType> = Vec::new();
+                    for node in node_query.iter() {
+                        if !node_types.contains(&node.data.node_type) {
+                            node_types.push(node.data.node_type.clone());
                         }
-                        if ui
-                            .selectable_value(
-                                &mut node.data.node_type,
-                                NodeType::Notable,
-                                "Notable",
-                            )
-                            .clicked()
-                        {
-                            node_type_changed = true;
+                    }
+                    node_types.sort_by(|a, b| a.name().cmp(b.name()));
+                    if node_types.is_empty() {
+                        ui.label("No nodes");
+                    }
+                    ui.small("Shift-click to add to the selection");
+                    for node_type in node_types {
+                        if !ui.button(node_type.name()).clicked() {
+                            continue;
                         }
+                        // Shift adds to the current group instead of replacing it
+                        if !ui.input(|i| i.modifiers.shift) {
+                            multi_selected.nodes.clear();
+                            selected_node.entity = None;
+                            selected_node.id = None;
+                            selected_connection.index = None;
+                        }
+                        let positions: Vec<Vec2> = node_query
+                            .iter()
+                            .filter(|node| node.data.node_type == node_type)
+                            .map(|node| {
+                                multi_selected.nodes.insert(node.id);
+                                node.data.position
+                            })
+                            .collect();
+                        frame_positions(&mut editor_camera, &positions, ctx.screen_rect().size());
+                        ui.close_menu();
+                    }
+                });
+                if !multi_selected.nodes.is_empty() {
+                    ui.label(format!("{} nodes selected", multi_selected.nodes.len()));
+                    if ui.button("Clear").clicked() {
+                        multi_selected.nodes.clear();
+                    }
+                }
+            });
+            ui.separator();
+            if ui
+                .selectable_label(stamp_mode.active, "Stamp Mode")
+                .on_hover_text(
+                    "Right-click empty canvas to place copies of the selected node (Esc to exit)",
+                )
+                .clicked()
+            {
+                stamp_mode.active = !stamp_mode.active;
+                if stamp_mode.active {
+                    template_library.placing = None;
+                }
+            }
+            if stamp_mode.active {
+                let label = match selected_node.id {
+                    Some(id) => format!("Right-click the canvas to stamp copies of node {}", id),
+                    None => "Select a node to stamp copies of".to_string(),
+                };
+                ui.colored_label(egui::Color32::YELLOW, label);
+            }
+            egui::CollapsingHeader::new("Templates").show(ui, |ui| {
+                if template_library.templates.is_empty() {
+                    ui.label("Use Save as Template on a node to add one");
+                }
+                let mut to_remove = None;
+                for index in 0..template_library.templates.len() {
+                    ui.horizontal(|ui| {
+                        let placing = template_library.placing == Some(index);
+                        let name = template_library.templates[index].name.clone();
                         if ui
-                            .selectable_value(
-                                &mut node.data.node_type,
-                                NodeType::Keystone,
-                                "Keystone",
-                            )
+                            .selectable_label(placing, name)
+                            .on_hover_text("Then right-click the canvas to place a copy")
                             .clicked()
                         {
-                            node_type_changed = true;
+                            template_library.placing = (!placing).then_some(index);
                         }
                         if ui
-                            .selectable_value(&mut node.data.node_type, NodeType::Start, "Start")
+                            .small_button("🗑")
+                            .on_hover_text("Delete template")
                             .clicked()
                         {
-                            node_type_changed = true;
+                            to_remove = Some(index);
                         }
                     });
-                if node_type_changed {
-                    editor_state.dirty = true;
                 }
-
+                if let Some(index) = to_remove {
+                    template_library.templates.remove(index);
+                    template_library.placing = None;
+                    write_templates(&template_library);
+                }
+                if let Some(template) = template_library.placing_template() {
+                    ui.colored_label(
+                        egui::Color32::YELLOW,
+                        format!(
+                            "Right-click the canvas to place '{}' (Esc to cancel)",
+                            template.name
+                        ),
+                    );
+                }
+            });
+            egui::CollapsingHeader::new("Stats Summary").show(ui, |ui| {
+                let start_ids = node_query
+                    .iter()
+                    .filter(|node| node.data.node_type.is_builtin(BuiltinNodeType::Start))
+                    .map(|node| node.id);
+                let reachable = graph::reachable_from(
+                    start_ids,
+                    &graph::undirected_adjacency(&skill_tree_data.connections),
+                );
+                let cost_of = |filter: &dyn Fn(u32) -> bool| -> u32 {
+                    node_query
+                        .iter()
+                        .filter(|node| filter(node.id))
+                        .map(|node| node.data.cost)
+                        .sum()
+                };
+                ui.label(format!("Total Cost (all nodes): {}", cost_of(&|_| true)));
+                ui.label(format!(
+                    "Reachable Cost: {}",
+                    cost_of(&|id| reachable.contains(&id))
+                ));
+                ui.label(format!(
+                    "Selected Cost: {}",
+                    cost_of(
+                        &|id| selected_node.id == Some(id) || multi_selected.nodes.contains(&id)
+                    )
+                ));
                 ui.separator();
-                ui.heading("Stats");
-                let mut stat_to_remove_idx = None;
-                for (i, stat) in node.data.stats.iter_mut().enumerate() {
-                    ui.horizontal(|ui| {
-                        if ui.text_edit_singleline(&mut stat.stat_name).changed() {
-                            editor_state.dirty = true;
-                        }
-                        if ui
-                            .add(egui::DragValue::new(&mut stat.value).speed(0.1))
-                            .changed()
-                        {
-                            editor_state.dirty = true;
-                        }
-
-                        let mut mod_type_changed = false;
-                        egui::ComboBox::from_id_salt(format!("mod_type_{i}"))
-                            .selected_text(format!("{:?}", stat.modifier_type))
-                            .show_ui(ui, |ui| {
-                                if ui
-                                    .selectable_value(
-                                        &mut stat.modifier_type,
-                                        ModifierType::Flat,
-                                        "Flat",
-                                    )
-                                    .clicked()
-                                {
-                                    mod_type_changed = true;
-                                }
-                                if ui
-                                    .selectable_value(
-                                        &mut stat.modifier_type,
-                                        ModifierType::Percentage,
-                                        "Percentage",
-                                    )
-                                    .clicked()
-                                {
-                                    mod_type_changed = true;
-                                }
-                            });
-                        if mod_type_changed {
-                            editor_state.dirty = true;
-                        }
 
-                        if ui.button("X").clicked() {
-                            stat_to_remove_idx = Some(i);
-                            editor_state.dirty = true;
-                        }
-                    });
+                let totals = stats::aggregate_stats(node_query.iter().map(|node| &node.data));
+                if totals.is_empty() {
+                    ui.label("No stats on any node");
                 }
-                if let Some(index) = stat_to_remove_idx {
-                    node.data.stats.remove(index);
+                for (stat_name, total) in &totals {
+                    let clamped_note = if total.is_clamped() { " (clamped)" } else { "" };
+                    ui.label(format!(
+                        "{}: {:.2}{}",
+                        stat_name,
+                        total.clamped_total(),
+                        clamped_note
+                    ))
+                    .on_hover_text(format!(
+                        "Base {:.2} + Flat {:.2}, ×{:.2}, +{:.1}%",
+                        total.base, total.flat, total.multiplier, total.percentage
+                    ));
                 }
-                if ui.button("Add Stat").clicked() {
-                    node.data.stats.push(StatModifier {
-                        stat_name: "New Stat".to_string(),
-                        value: 0.0,
-                        modifier_type: ModifierType::Flat,
+            });
+            ui.separator();
+            ui.heading("All Connections");
+
+            let duplicate_indices =
+                graph::duplicate_connection_indices(&skill_tree_data.connections);
+            if !duplicate_indices.is_empty() {
+                ui.colored_label(
+                    egui::Color32::from_rgb(255, 200, 100),
+                    format!("⚠ {} duplicate connection(s)", duplicate_indices.len()),
+                );
+                if ui.button("Fix Duplicates").clicked() {
+                    let to_remove: HashSet<usize> = duplicate_indices.iter().copied().collect();
+                    let mut index = 0;
+                    skill_tree_data.connections.retain(|_| {
+                        let keep = !to_remove.contains(&index);
+                        index += 1;
+                        keep
                     });
+                    selected_connection.index = None;
                     editor_state.dirty = true;
+                    toast.info(format!(
+                        "Removed {} duplicate connection(s)",
+                        duplicate_indices.len()
+                    ));
                 }
-                ui.separator();
-                if ui.button("Delete Node").clicked() {
-                    let node_id = node.id;
-                    skill_tree_data
-                        .connections
-                        .retain(|conn| conn.from_id != node_id && conn.to_id != node_id);
-                    skill_tree_data.nodes.remove(&node_id);
-                    commands.entity(entity).despawn();
-                    selected_node.entity = None;
-                    selected_node.id = None;
+            }
+
+            let positions: HashMap<u32, Vec2> = node_query
+                .iter()
+                .map(|node| (node.id, node.data.position))
+                .collect();
+            let invalid_arcs = invalid_arc_indices(&skill_tree_data.connections, &positions);
+            if !invalid_arcs.is_empty() {
+                ui.colored_label(
+                    egui::Color32::from_rgb(255, 200, 100),
+                    format!("⚠ {} arc(s) too tight for their nodes", invalid_arcs.len()),
+                );
+                if ui
+                    .button("Fix All Invalid Arcs")
+                    .on_hover_text("Widen each arc just enough to span its nodes")
+                    .clicked()
+                {
+                    for &index in &invalid_arcs {
+                        let connection = &mut skill_tree_data.connections[index];
+                        let half_chord = positions[&connection.from_id]
+                            .distance(positions[&connection.to_id])
+                            / 2.0;
+                        if let CurveType::Arc { radius, .. } = &mut connection.curve_type {
+                            *radius = half_chord + ARC_FIX_MARGIN;
+                        }
+                    }
                     editor_state.dirty = true;
+                    info!("Fixed the radius of {} arc(s)", invalid_arcs.len());
+                    toast.info(format!("Fixed {} arc(s)", invalid_arcs.len()));
                 }
             }
-        } else {
-            ui.label("No node or connection selected");
-            ui.separator();
-            ui.label("Right-click to create a node");
-            ui.label("Left-click to select nodes/connections");
-            ui.label("Right-click on nodes to connect");
-            ui.label("Middle mouse or Shift + Left Drag to pan");
-            ui.label("Scroll to zoom");
-            ui.label("Delete/Backspace to remove selected");
-        }
-        ui.separator();
-        ui.heading("All Connections");
 
-        // Use available height for the scroll area
-        let available_height = ui.available_height();
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut editor_state.connection_search_query)
+                        .hint_text("Node id")
+                        .desired_width(80.0),
+                );
+                egui::ComboBox::from_id_salt("connection_type_filter")
+                    .selected_text(editor_state.connection_type_filter.label())
+                    .show_ui(ui, |ui| {
+                        for filter in ConnectionTypeFilter::ALL {
+                            ui.selectable_value(
+                                &mut editor_state.connection_type_filter,
+                                filter,
+                                filter.label(),
+                            );
+                        }
+                    });
+            });
+            let connection_query = editor_state.connection_search_query.trim().to_string();
+            let type_filter = editor_state.connection_type_filter;
+            let connection_matches = |connection: &ConnectionData| {
+                type_filter.matches(&connection.curve_type)
+                    && (connection.from_id.to_string().contains(&connection_query)
+                        || connection.to_id.to_string().contains(&connection_query))
+            };
+            let shown = skill_tree_data
+                .connections
+                .iter()
+                .filter(|connection| connection_matches(connection))
+                .count();
+            ui.label(format!(
+                "Showing {} of {}",
+                shown,
+                skill_tree_data.connections.len()
+            ));
+
+            // Use available height for the scroll area
+            let available_height = ui.available_height();
 
-        egui::ScrollArea::vertical()
-            .max_height(available_height)
-            .show(ui, |ui| {
-                ui.set_min_width(ui.available_width());
+            egui::ScrollArea::vertical()
+                .max_height(available_height)
+                .show(ui, |ui| {
+                    ui.set_min_width(ui.available_width());
 
-                let mut connection_to_remove_idx = None;
-                for (i, connection) in skill_tree_data.connections.iter().enumerate() {
-                    // Use a full-width group to make the entire row scrollable
-                    ui.group(|ui| {
-                        ui.set_min_width(ui.available_width());
-                        ui.horizontal(|ui| {
-                            // Check if arc is valid
-                            let mut is_invalid_arc = false;
-                            if let CurveType::Arc { radius, .. } = &connection.curve_type {
-                                let mut from_pos = None;
-                                let mut to_pos = None;
-                                for node in node_query.iter() {
-                                    if node.id == connection.from_id {
-                                        from_pos = Some(node.data.position);
+                    let mut connection_to_remove_idx = None;
+                    for (i, connection) in skill_tree_data.connections.iter().enumerate() {
+                        if !connection_matches(connection) {
+                            continue;
+                        }
+                        // Use a full-width group to make the entire row scrollable
+                        ui.group(|ui| {
+                            ui.set_min_width(ui.available_width());
+                            ui.horizontal(|ui| {
+                                // Check if arc is valid
+                                let mut is_invalid_arc = false;
+                                if let CurveType::Arc { radius, .. } = &connection.curve_type {
+                                    let mut from_pos = None;
+                                    let mut to_pos = None;
+                                    for node in node_query.iter() {
+                                        if node.id == connection.from_id {
+                                            from_pos = Some(node.data.position);
+                                        }
+                                        if node.id == connection.to_id {
+                                            to_pos = Some(node.data.position);
+                                        }
                                     }
-                                    if node.id == connection.to_id {
-                                        to_pos = Some(node.data.position);
+                                    if let (Some(from), Some(to)) = (from_pos, to_pos) {
+                                        let distance = from.distance(to);
+                                        is_invalid_arc = *radius < distance / 2.0;
                                     }
                                 }
-                                if let (Some(from), Some(to)) = (from_pos, to_pos) {
-                                    let distance = from.distance(to);
-                                    is_invalid_arc = *radius < distance / 2.0;
-                                }
-                            }
 
-                            let connection_text = match &connection.curve_type {
-                                CurveType::Straight => {
-                                    format!("{} → {}", connection.from_id, connection.to_id)
-                                }
-                                CurveType::Arc { .. } => {
-                                    if is_invalid_arc {
-                                        format!("{} ⚠ {}", connection.from_id, connection.to_id)
-                                    } else {
-                                        format!("{} ⤷ {}", connection.from_id, connection.to_id)
+                                let connection_text = match &connection.curve_type {
+                                    CurveType::Straight => {
+                                        format!("{} → {}", connection.from_id, connection.to_id)
                                     }
-                                }
-                            };
+                                    CurveType::Arc { .. } => {
+                                        if is_invalid_arc {
+                                            format!("{} ⚠ {}", connection.from_id, connection.to_id)
+                                        } else {
+                                            format!("{} ⤷ {}", connection.from_id, connection.to_id)
+                                        }
+                                    }
+                                    CurveType::CatmullRom { .. } => {
+                                        format!("{} ∿ {}", connection.from_id, connection.to_id)
+                                    }
+                                };
+                                let connection_text = if connection.enabled {
+                                    connection_text
+                                } else {
+                                    format!("{} (disabled)", connection_text)
+                                };
 
-                            let selected = selected_connection.index == Some(i);
-                            let button = if selected {
-                                ui.add(
-                                    egui::Button::new(connection_text)
-                                        .fill(egui::Color32::from_rgb(60, 80, 100)),
-                                )
-                            } else {
-                                ui.button(connection_text)
-                            };
+                                let selected = selected_connection.index == Some(i);
+                                let button = if selected {
+                                    ui.add(
+                                        egui::Button::new(connection_text)
+                                            .fill(egui::Color32::from_rgb(60, 80, 100)),
+                                    )
+                                } else {
+                                    ui.button(connection_text)
+                                };
 
-                            if button.clicked() {
-                                selected_connection.index = Some(i);
-                                selected_node.entity = None;
-                                selected_node.id = None;
-                            }
+                                if button.clicked() {
+                                    selected_connection.index = Some(i);
+                                    selected_node.entity = None;
+                                    selected_node.id = None;
+                                }
 
-                            if ui.button("×").clicked() {
-                                connection_to_remove_idx = Some(i);
-                                editor_state.dirty = true;
-                            }
+                                if ui.button("×").clicked() {
+                                    connection_to_remove_idx = Some(i);
+                                    editor_state.dirty = true;
+                                }
+                            });
                         });
+                    }
+
+                    if let Some(index) = connection_to_remove_idx {
+                        skill_tree_data.connections.remove(index);
+                        if selected_connection.index == Some(index) {
+                            selected_connection.index = None;
+                        } else if selected_connection.index.is_some()
+                            && selected_connection.index.unwrap() > index
+                        {
+                            // Adjust selected index if a connection before it was removed
+                            selected_connection.index =
+                                Some(selected_connection.index.unwrap() - 1);
+                        }
+                    }
+
+                    ui.separator();
+                    egui::CollapsingHeader::new("Metrics").show(ui, |ui| {
+                        show_topology_metrics(ui, &topology_metrics);
                     });
-                }
+                    ui.add_space(10.0);
+                });
+        });
+
+    // Remember the width once a resize drag ends
+    if let Some(side_panel) = side_panel {
+        let side_panel_width = side_panel.response.rect.width();
+        if side_panel_width != editor_state.side_panel_width
+            && !ctx.input(|input| input.pointer.any_down())
+        {
+            editor_state.side_panel_width = side_panel_width;
+            write_editor_config(
+                &editor_state,
+                &node_type_colors,
+                &node_type_hit_radius,
+                &connection_style_presets,
+                &hot_reload,
+                &topology_metrics.thresholds,
+                &theme_settings,
+            );
+        }
+    }
+
+    if editor_state.detach_properties && show_panels {
+        let default_pos = editor_state
+            .properties_window_pos
+            .map_or(egui::pos2(280.0, 60.0), |pos| egui::pos2(pos.x, pos.y));
+        let response = egui::Window::new("Properties")
+            .default_pos(default_pos)
+            .default_width(260.0)
+            .resizable(true)
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    properties_ui(
+                        ui,
+                        &mut commands,
+                        &mut editor_state,
+                        &mut skill_tree_data,
+                        &mut selected_node,
+                        &mut selected_connection,
+                        &mut multi_selected,
+                        &mut node_query,
+                        &mut node_images,
+                        &mut toast,
+                        &asset_server,
+                        &mut template_library,
+                        &mut connection_style_presets,
+                    );
+                });
+            });
+        if let Some(response) = response {
+            let pos = response.response.rect.min;
+            editor_state.properties_window_pos = Some(Vec2::new(pos.x, pos.y));
+        }
+    }
+}
+
+/// Badges drawn over the canvas: the snap offset while dragging, the exit
+/// button in presentation mode and the tree size
+pub fn canvas_overlays_ui(
+    mut contexts: EguiContexts,
+    mut editor_state: ResMut<EditorState>,
+    skill_tree_data: Res<SkillTreeData>,
+    mut grid_settings: ResMut<GridSettings>,
+    drag_state: Res<DragState>,
+) {
+    let ctx = contexts.ctx_mut();
+
+    // How far grid snapping pulls a dragged node, beside the cursor
+    if let Some(pointer) = ctx
+        .pointer_hover_pos()
+        .filter(|_| drag_state.dragging && drag_state.snap_delta != Vec2::ZERO)
+    {
+        egui::Area::new(egui::Id::new("snap_offset"))
+            .order(egui::Order::Tooltip)
+            .fixed_pos(pointer + egui::vec2(16.0, 16.0))
+            .interactable(false)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.label(format!(
+                        "Δ({:.0}, {:.0})",
+                        drag_state.snap_delta.x, drag_state.snap_delta.y
+                    ));
+                });
+            });
+    }
 
-                if let Some(index) = connection_to_remove_idx {
-                    skill_tree_data.connections.remove(index);
-                    if selected_connection.index == Some(index) {
-                        selected_connection.index = None;
-                    } else if selected_connection.index.is_some()
-                        && selected_connection.index.unwrap() > index
+    if editor_state.presentation_mode {
+        egui::Area::new(egui::Id::new("exit_presentation"))
+            .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-10.0, 10.0))
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    // The grid is drawn while snapping is on
+                    ui.checkbox(&mut grid_settings.snap_to_grid, "Grid");
+                    if ui
+                        .button("Exit Presentation")
+                        .on_hover_text("F11")
+                        .clicked()
                     {
-                        // Adjust selected index if a connection before it was removed
-                        selected_connection.index = Some(selected_connection.index.unwrap() - 1);
+                        editor_state.presentation_mode = false;
                     }
-                }
+                });
+            });
+    }
 
-                ui.add_space(10.0);
+    // Tree size badge over the canvas, kept in presentation mode, with a dot
+    // while there are unsaved changes
+    egui::Area::new(egui::Id::new("tree_counts"))
+        .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-10.0, 40.0))
+        .interactable(false)
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    if editor_state.dirty {
+                        ui.colored_label(egui::Color32::YELLOW, "●");
+                    }
+                    ui.label(format!(
+                        "Nodes: {} | Connections: {}",
+                        skill_tree_data.nodes.len(),
+                        skill_tree_data.connections.len()
+                    ));
+                });
             });
-    });
+        });
+}
+
+/// Settings window, saving the editor config whenever a setting in it or a
+/// connection style preset changes
+pub fn settings_window_ui(
+    mut contexts: EguiContexts,
+    mut editor_state: ResMut<EditorState>,
+    node_type_styles: Res<NodeTypeStyles>,
+    saved_settings: SavedSettings,
+) {
+    let SavedSettings {
+        mut node_type_colors,
+        mut node_type_hit_radius,
+        mut connection_style_presets,
+        mut hot_reload,
+        mut topology_metrics,
+        mut theme_settings,
+    } = saved_settings;
+    let ctx = contexts.ctx_mut();
+
+    if editor_state.show_settings {
+        let mut open = true;
+        egui::Window::new("Settings")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.heading("Node Type Colors");
+                let mut custom_names: Vec<&String> = node_type_styles.styles.keys().collect();
+                custom_names.sort();
+                let node_types: Vec<NodeType> = BuiltinNodeType::ALL
+                    .into_iter()
+                    .map(NodeType::Builtin)
+                    .chain(custom_names.into_iter().cloned().map(NodeType::Custom))
+                    .collect();
+
+                let mut changed = false;
+                egui::Grid::new("node_type_colors").show(ui, |ui| {
+                    for node_type in &node_types {
+                        let name = node_type.name().to_string();
+                        ui.label(&name);
+                        let mut color =
+                            node_type_colors
+                                .colors
+                                .get(&name)
+                                .copied()
+                                .unwrap_or_else(|| {
+                                    node_type_styles.tint(node_type).to_srgba().to_f32_array()
+                                });
+                        if ui.color_edit_button_rgba_unmultiplied(&mut color).changed() {
+                            node_type_colors.colors.insert(name.clone(), color);
+                            changed = true;
+                        }
+                        if ui
+                            .add_enabled(
+                                node_type_colors.colors.contains_key(&name),
+                                egui::Button::new("Reset"),
+                            )
+                            .on_hover_text("Use the type's default style color")
+                            .clicked()
+                        {
+                            node_type_colors.colors.remove(&name);
+                            changed = true;
+                        }
+                        ui.end_row();
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Colorblind palette");
+                    egui::ComboBox::from_id_salt("colorblind_mode")
+                        .selected_text(theme_settings.colorblind_mode.label())
+                        .show_ui(ui, |ui| {
+                            for mode in ColorblindMode::ALL {
+                                changed |= ui
+                                    .selectable_value(
+                                        &mut theme_settings.colorblind_mode,
+                                        mode,
+                                        mode.label(),
+                                    )
+                                    .changed();
+                            }
+                        });
+                })
+                .response
+                .on_hover_text(
+                    "Remap node and connection colors to a palette that stays distinct \
+                     for this kind of color blindness",
+                );
+                changed |= ui
+                    .checkbox(&mut theme_settings.high_contrast, "High contrast")
+                    .on_hover_text(
+                        "White and yellow nodes and connections with black outlines, \
+                         thicker lines and larger text",
+                    )
+                    .changed();
+
+                ui.separator();
+                ui.heading("Click Radius");
+                egui::Grid::new("node_type_hit_radii").show(ui, |ui| {
+                    for node_type in &node_types {
+                        let name = node_type.name().to_string();
+                        ui.label(&name);
+                        let mut radius = node_type_hit_radius
+                            .radii
+                            .get(&name)
+                            .copied()
+                            .unwrap_or_else(|| node_type_styles.size(node_type) / 2.0);
+                        if ui
+                            .add(egui::DragValue::new(&mut radius).range(1.0..=200.0))
+                            .changed()
+                        {
+                            node_type_hit_radius.radii.insert(name.clone(), radius);
+                            changed = true;
+                        }
+                        if ui
+                            .add_enabled(
+                                node_type_hit_radius.radii.contains_key(&name),
+                                egui::Button::new("Reset"),
+                            )
+                            .on_hover_text("Use half the node's size, growing with its tier")
+                            .clicked()
+                        {
+                            node_type_hit_radius.radii.remove(&name);
+                            changed = true;
+                        }
+                        ui.end_row();
+                    }
+                });
+
+                ui.separator();
+                ui.heading("Metric Warnings");
+                ui.label("Metrics above these limits are flagged in the side panel");
+                let thresholds = &mut topology_metrics.thresholds;
+                egui::Grid::new("metric_thresholds").show(ui, |ui| {
+                    for (label, limit) in [
+                        ("Max degree", &mut thresholds.max_degree),
+                        ("Longest path", &mut thresholds.longest_path),
+                        ("Isolated nodes", &mut thresholds.isolated_nodes),
+                        ("Cycles", &mut thresholds.cycles),
+                        ("Components", &mut thresholds.components),
+                    ] {
+                        ui.label(label);
+                        changed |= ui.add(egui::DragValue::new(limit)).changed();
+                        ui.end_row();
+                    }
+                });
+
+                ui.separator();
+                ui.heading("Saving");
+                changed |= ui
+                    .add(
+                        egui::Slider::new(&mut editor_state.max_backups, 0..=MAX_BACKUPS)
+                            .text("Backups to keep"),
+                    )
+                    .on_hover_text(
+                        "Copies of a file kept as .bak, .bak.1, … each time Save replaces it",
+                    )
+                    .changed();
+                changed |= ui
+                    .checkbox(&mut editor_state.remember_camera, "Remember camera on save")
+                    .on_hover_text("Save the current view with the tree and return to it on load")
+                    .changed();
+                changed |= ui
+                    .checkbox(&mut hot_reload.enabled, "Reload files changed on disk")
+                    .on_hover_text("Offer to reload the open file when another program saves it")
+                    .changed();
+
+                if changed {
+                    write_editor_config(
+                        &editor_state,
+                        &node_type_colors,
+                        &node_type_hit_radius,
+                        &connection_style_presets,
+                        &hot_reload,
+                        &topology_metrics.thresholds,
+                        &theme_settings,
+                    );
+                }
+            });
+        editor_state.show_settings = open;
+    }
+
+    if connection_style_presets.needs_save {
+        connection_style_presets.needs_save = false;
+        write_editor_config(
+            &editor_state,
+            &node_type_colors,
+            &node_type_hit_radius,
+            &connection_style_presets,
+            &hot_reload,
+            &topology_metrics.thresholds,
+            &theme_settings,
+        );
+    }
+}
+
+/// Save, load and export dialogs, and the loads and prompts they queue
+#[allow(clippy::too_many_arguments)]
+pub fn file_dialogs_ui(
+    mut contexts: EguiContexts,
+    mut commands: Commands,
+    mut editor_state: ResMut<EditorState>,
+    mut skill_tree_data: ResMut<SkillTreeData>,
+    selection: Selection,
+    mut node_query: Query<&mut SkillNode>,
+    node_images: Res<NodeImages>,
+    mut toast: ResMut<TimedToast>,
+    mut png_export_state: ResMut<PngExportState>,
+    mut compare_tree: ResMut<CompareTree>,
+    editor_camera: Res<EditorCamera>,
+    mut camera_transition: ResMut<CameraTransition>,
+    mut hot_reload: ResMut<HotReloadSettings>,
+) {
+    let Selection {
+        mut selected_node,
+        mut selected_connection,
+        mut multi_selected,
+    } = selection;
+    let ctx = contexts.ctx_mut();
 
     if editor_state.show_save_as_dialog {
-        egui::Window::new("Save Skill Tree As...")
+        let title = if editor_state.save_as_scene {
+            "Save Skill Tree As Scene..."
+        } else {
+            "Save Skill Tree As..."
+        };
+        egui::Window::new(title)
             .collapsible(false)
             .resizable(false)
             .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
@@ -477,14 +1568,19 @@ pub fn ui_system(
                         editor_state.save_as_file_name_buffer.clone();
 
                     let mut attempt_save_action = |es: &mut EditorState, path_to_save: PathBuf| {
-                        save_skill_tree(
-                            path_to_save.to_str().unwrap_or_default(),
+                        save_tree_to_path(
+                            &path_to_save,
+                            &mut commands,
+                            &mut hot_reload,
                             &skill_tree_data,
                             &node_query,
+                            saved_camera_state(es, &editor_camera),
+                            es.max_backups,
                         );
                         es.current_file_path = Some(path_to_save.clone());
                         es.dirty = false;
                         es.show_save_as_dialog = false;
+                        es.save_as_scene = false;
                         es.save_as_show_overwrite_prompt = false;
                         es.save_as_conflict_path = None;
 
@@ -498,11 +1594,13 @@ pub fn ui_system(
                         && !save_as_file_name_buffer_clone.is_empty()
                     {
                         let mut path_for_saving = PathBuf::from(&save_as_file_name_buffer_clone);
-                        if path_for_saving.extension().is_none_or(|ext| ext != "ron") {
+                        if editor_state.save_as_scene {
+                            path_for_saving = with_scene_extension(&save_as_file_name_buffer_clone);
+                        } else if path_for_saving.extension().is_none_or(|ext| ext != "ron") {
                             path_for_saving.set_extension("ron");
                         }
 
-                        if path_for_saving.exists() {
+                        if storage::exists(&path_for_saving) {
                             editor_state.save_as_show_overwrite_prompt = true;
                             editor_state.save_as_conflict_path = Some(path_for_saving);
                         } else {
@@ -512,19 +1610,118 @@ pub fn ui_system(
 
                     if ui.button("Cancel").clicked() {
                         editor_state.show_save_as_dialog = false;
+                        editor_state.save_as_scene = false;
                         editor_state.save_as_show_overwrite_prompt = false;
                         editor_state.save_as_conflict_path = None;
                         editor_state.next_action_after_save_as = NextActionAfterSaveAs::None;
+                        editor_state.pending_dropped_file = None;
+                    }
+
+                    if editor_state.save_as_show_overwrite_prompt
+                        && let Some(path_to_overwrite) = editor_state.save_as_conflict_path.clone()
+                        && ui.button("Overwrite").clicked()
+                    {
+                        attempt_save_action(&mut editor_state, path_to_overwrite);
                     }
+                });
+            });
+    }
+
+    if editor_state.show_export_dialog {
+        let export_format = editor_state.export_format;
+        egui::Window::new(format!("Export {}", export_format.label()))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label("File name:");
+                if ui
+                    .text_edit_singleline(&mut editor_state.export_file_name_buffer)
+                    .changed()
+                {
+                    editor_state.export_show_overwrite_prompt = false;
+                }
+
+                let mut export_path = PathBuf::from(&editor_state.export_file_name_buffer);
+                if export_path
+                    .extension()
+                    .is_none_or(|ext| ext != export_format.extension())
+                {
+                    export_path.set_extension(export_format.extension());
+                }
+
+                if export_format == ExportFormat::Png {
+                    let settings = &mut editor_state.png_export_settings;
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.label("Resolution:");
+                        ui.add(egui::DragValue::new(&mut settings.width).range(16..=8192));
+                        ui.label("×");
+                        ui.add(egui::DragValue::new(&mut settings.height).range(16..=8192));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("DPI:");
+                        ui.add(egui::DragValue::new(&mut settings.dpi).range(1..=1200));
+                    });
+                    ui.checkbox(&mut settings.include_grid, "Include grid");
+                    ui.horizontal(|ui| {
+                        ui.label("Framing:");
+                        ui.radio_value(&mut settings.fit_all, true, "Fit all nodes");
+                        ui.radio_value(&mut settings.fit_all, false, "Current view");
+                    });
+                    ui.separator();
+                }
+
+                if editor_state.export_show_overwrite_prompt {
+                    ui.colored_label(
+                        egui::Color32::RED,
+                        format!("File '{}' already exists!", export_path.display()),
+                    );
+                }
 
-                    if editor_state.save_as_show_overwrite_prompt {
-                        if let Some(path_to_overwrite) = editor_state.save_as_conflict_path.clone()
+                ui.horizontal(|ui| {
+                    let confirm_label = if editor_state.export_show_overwrite_prompt {
+                        "Overwrite"
+                    } else {
+                        "Export"
+                    };
+                    if ui.button(confirm_label).clicked()
+                        && !editor_state.export_file_name_buffer.is_empty()
+                    {
+                        if storage::exists(&export_path)
+                            && !editor_state.export_show_overwrite_prompt
                         {
-                            if ui.button("Overwrite").clicked() {
-                                attempt_save_action(&mut editor_state, path_to_overwrite);
+                            editor_state.export_show_overwrite_prompt = true;
+                        } else if export_format == ExportFormat::Png {
+                            // Rendering takes a few frames, the result is reported by a toast
+                            png_export_state.requests.push_back(PngExportRequest {
+                                path: export_path.clone(),
+                                settings: editor_state.png_export_settings,
+                                view: None,
+                                stitch_tile: None,
+                            });
+                            editor_state.show_export_dialog = false;
+                        } else {
+                            match run_export(
+                                export_format,
+                                &export_path,
+                                &skill_tree_data,
+                                &node_query,
+                            ) {
+                                Ok(()) => toast.info(format!("Exported {}", export_path.display())),
+                                Err(e) => toast.error(format!(
+                                    "Failed to export {}: {}",
+                                    export_path.display(),
+                                    e
+                                )),
                             }
+                            editor_state.show_export_dialog = false;
                         }
                     }
+
+                    if ui.button("Cancel").clicked() {
+                        editor_state.show_export_dialog = false;
+                    }
                 });
             });
     }
@@ -540,12 +1737,17 @@ pub fn ui_system(
                 ui.horizontal(|ui| {
                     if ui.button("Save").clicked() {
                         if let Some(path) = editor_state.current_file_path.clone() {
-                            save_skill_tree(
-                                path.to_str().unwrap_or("skill_tree.ron"),
+                            save_tree_to_path(
+                                &path,
+                                &mut commands,
+                                &mut hot_reload,
                                 &skill_tree_data,
                                 &node_query,
+                                saved_camera_state(&editor_state, &editor_camera),
+                                editor_state.max_backups,
                             );
-                            perform_new_file_action(&mut commands, &mut editor_state, &mut skill_tree_data, &mut selected_node, &mut selected_connection);
+                            perform_new_file_action(&mut commands, &mut editor_state, &mut skill_tree_data, &mut selected_node, &mut selected_connection, &mut multi_selected);
+                            open_tree_name_prompt(&mut editor_state);
                             editor_state.show_unsaved_changes_on_new_dialog = false;
                         } else {
                             editor_state.next_action_after_save_as = NextActionAfterSaveAs::CreateNewFile;
@@ -563,7 +1765,8 @@ pub fn ui_system(
                         }
                     }
                     if ui.button("Don't Save").clicked() {
-                        perform_new_file_action(&mut commands, &mut editor_state, &mut skill_tree_data, &mut selected_node, &mut selected_connection);
+                        perform_new_file_action(&mut commands, &mut editor_state, &mut skill_tree_data, &mut selected_node, &mut selected_connection, &mut multi_selected);
+                        open_tree_name_prompt(&mut editor_state);
                         editor_state.show_unsaved_changes_on_new_dialog = false;
                     }
                     if ui.button("Cancel").clicked() {
@@ -585,17 +1788,25 @@ pub fn ui_system(
                 ui.horizontal(|ui| {
                     if ui.button("Save").clicked() {
                         if let Some(path) = editor_state.current_file_path.clone() {
-                            save_skill_tree(
-                                path.to_str().unwrap_or("skill_tree.ron"),
+                            save_tree_to_path(
+                                &path,
+                                &mut commands,
+                                &mut hot_reload,
                                 &skill_tree_data,
                                 &node_query,
+                                saved_camera_state(&editor_state, &editor_camera),
+                                editor_state.max_backups,
                             );
                             editor_state.dirty = false;
-                            open_load_dialog_sequence(&mut editor_state);
+                            continue_load_sequence(&mut editor_state);
                             editor_state.show_unsaved_changes_on_load_dialog = false;
                         } else {
                             editor_state.next_action_after_save_as =
-                                NextActionAfterSaveAs::ShowLoadDialog;
+                                if editor_state.pending_dropped_file.is_some() {
+                                    NextActionAfterSaveAs::LoadDroppedFile
+                                } else {
+                                    NextActionAfterSaveAs::ShowLoadDialog
+                                };
                             editor_state.save_as_file_name_buffer = editor_state
                                 .current_file_path
                                 .as_ref()
@@ -611,29 +1822,68 @@ pub fn ui_system(
                     }
                     if ui.button("Don't Save").clicked() {
                         editor_state.dirty = false;
-                        open_load_dialog_sequence(&mut editor_state);
+                        continue_load_sequence(&mut editor_state);
                         editor_state.show_unsaved_changes_on_load_dialog = false;
                     }
                     if ui.button("Cancel").clicked() {
                         editor_state.show_unsaved_changes_on_load_dialog = false;
                         editor_state.next_action_after_save_as = NextActionAfterSaveAs::None;
+                        editor_state.pending_dropped_file = None;
                     }
                 });
             });
     }
 
     if editor_state.show_load_dialog {
-        egui::Window::new("Load Skill Tree")
+        let load_dialog_mode = editor_state.load_dialog_mode;
+        let title = match load_dialog_mode {
+            LoadDialogMode::Open => "Load Skill Tree",
+            LoadDialogMode::Merge => "Merge Skill Tree",
+            LoadDialogMode::Compare => "Compare Skill Tree",
+            LoadDialogMode::CsvUpdate => "Import CSV",
+            LoadDialogMode::MergeSubtree => "Merge Subtree",
+            LoadDialogMode::DotImport => "Import DOT",
+        };
+        egui::Window::new(title)
+            .id(egui::Id::new("load_dialog"))
             .collapsible(false)
             .resizable(true)
             .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
             .show(ctx, |ui| {
-                ui.heading("Select a .ron file to load:");
+                ui.heading(match load_dialog_mode {
+                    LoadDialogMode::Open => "Select a .ron or .json file to load:",
+                    LoadDialogMode::Merge => "Select a .ron or .json file to merge in:",
+                    LoadDialogMode::Compare => "Select a .ron or .json file to compare with:",
+                    LoadDialogMode::CsvUpdate => "Select a .csv file to update nodes from:",
+                    LoadDialogMode::MergeSubtree => "Select a subtree file to merge in:",
+                    LoadDialogMode::DotImport => "Select a .dot file to import:",
+                });
                 ui.separator();
                 let mut file_to_load_and_close_dialog = None;
 
+                if storage::BROWSER_STORAGE {
+                    // Web builds can only list files already in browser
+                    // storage, so other files are uploaded into it first
+                    let extensions: &[&str] = match load_dialog_mode {
+                        LoadDialogMode::CsvUpdate => &["csv"],
+                        LoadDialogMode::DotImport => &["dot"],
+                        _ => &SKILL_TREE_EXTENSIONS,
+                    };
+                    if ui.button("Upload File...").clicked() {
+                        storage::request_upload(extensions);
+                    }
+                    let files = &mut editor_state.available_skill_tree_files;
+                    for path in storage::take_uploads() {
+                        if !files.contains(&path) {
+                            files.push(path);
+                            files.sort();
+                        }
+                    }
+                    ui.separator();
+                }
+
                 egui::ScrollArea::vertical().show(ui, |ui| {
-                    for path_buf in &editor_state.available_ron_files {
+                    for path_buf in &editor_state.available_skill_tree_files {
                         if ui
                             .button(path_buf.file_name().unwrap_or_default().to_string_lossy())
                             .clicked()
@@ -643,31 +1893,98 @@ pub fn ui_system(
                     }
                 });
 
-                if let Some(path_to_load) = file_to_load_and_close_dialog {
-                    if let Ok(save_data) =
-                        load_skill_tree(path_to_load.to_str().unwrap_or_default())
-                    {
-                        // Clear existing tree before loading new one
-                        perform_new_file_action(
+                if let Some(path) = file_to_load_and_close_dialog {
+                    match load_dialog_mode {
+                        LoadDialogMode::Open => {
+                            if let Err(e) = load_file_into_editor(
+                                path.clone(),
+                                &mut commands,
+                                &mut editor_state,
+                                &mut skill_tree_data,
+                                &mut selected_node,
+                                &mut selected_connection,
+                                &mut multi_selected,
+                                &node_images,
+                                &mut camera_transition,
+                            ) {
+                                error!("Failed to load {}: {}", path.display(), e);
+                                editor_state.failed_load = Some((path.clone(), e.to_string()));
+                            }
+                        }
+                        LoadDialogMode::Merge => match merge_file_into_editor(
+                            &path,
                             &mut commands,
                             &mut editor_state,
                             &mut skill_tree_data,
-                            &mut selected_node,
-                            &mut selected_connection,
-                        );
-
-                        let mut max_id = 0;
-                        for node_data in save_data.nodes {
-                            let entity = spawn_node(&mut commands, &node_data, &node_images);
-                            skill_tree_data.nodes.insert(node_data.id, entity);
-                            if node_data.id >= max_id {
-                                max_id = node_data.id + 1;
+                            &mut multi_selected,
+                            &node_images,
+                        ) {
+                            Ok((node_count, connection_count)) => toast.info(format!(
+                                "Merged {} nodes and {} connections.",
+                                node_count, connection_count
+                            )),
+                            Err(e) => {
+                                toast.error(format!("Failed to merge {}: {}", path.display(), e))
+                            }
+                        },
+                        LoadDialogMode::Compare => {
+                            if let Err(e) = load_compare_tree(&path, &mut compare_tree) {
+                                toast.error(format!(
+                                    "Failed to compare with {}: {}",
+                                    path.display(),
+                                    e
+                                ));
+                            }
+                        }
+                        LoadDialogMode::MergeSubtree => match merge_subtree_into_editor(
+                            &path,
+                            &mut commands,
+                            &mut editor_state,
+                            &mut skill_tree_data,
+                            &mut node_query,
+                            &mut multi_selected,
+                            &node_images,
+                        ) {
+                            Ok((updated, added, connection_count)) => toast.info(format!(
+                                "Updated {} nodes, added {} nodes and {} connections.",
+                                updated, added, connection_count
+                            )),
+                            Err(e) => {
+                                toast.error(format!("Failed to merge {}: {}", path.display(), e))
+                            }
+                        },
+                        LoadDialogMode::DotImport => match import_dot_into_editor(
+                            &path,
+                            &mut commands,
+                            &mut editor_state,
+                            &mut skill_tree_data,
+                            &mut multi_selected,
+                            &node_images,
+                        ) {
+                            Ok((node_count, connection_count)) => toast.info(format!(
+                                "Imported {} nodes and {} connections.",
+                                node_count, connection_count
+                            )),
+                            Err(e) => {
+                                toast.error(format!("Failed to import {}: {}", path.display(), e))
+                            }
+                        },
+                        LoadDialogMode::CsvUpdate => {
+                            match update_nodes_from_csv(&path, &skill_tree_data, &mut node_query) {
+                                Ok((updated, skipped)) => {
+                                    editor_state.dirty |= updated > 0;
+                                    editor_state.csv_import_summary = Some(format!(
+                                        "Updated {} nodes, skipped {} unknown IDs.",
+                                        updated, skipped
+                                    ));
+                                }
+                                Err(e) => toast.error(format!(
+                                    "Failed to import {}: {}",
+                                    path.display(),
+                                    e
+                                )),
                             }
                         }
-                        editor_state.next_node_id = max_id;
-                        skill_tree_data.connections = save_data.connections;
-                        editor_state.current_file_path = Some(path_to_load);
-                        editor_state.dirty = false; // Loaded file is not dirty
                     }
                     editor_state.show_load_dialog = false;
                 }
@@ -678,6 +1995,83 @@ pub fn ui_system(
             });
     }
 
+    if editor_state.show_tree_name_prompt {
+        egui::Window::new("New Skill Tree")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label("Tree name:");
+                let response = ui.text_edit_singleline(&mut editor_state.tree_name_buffer);
+                response.request_focus();
+                let submitted =
+                    response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                ui.horizontal(|ui| {
+                    if ui.button("OK").clicked() || submitted {
+                        skill_tree_data.tree_name =
+                            editor_state.tree_name_buffer.trim().to_string();
+                        editor_state.show_tree_name_prompt = false;
+                    }
+                    if ui.button("Skip").clicked() {
+                        editor_state.show_tree_name_prompt = false;
+                    }
+                });
+            });
+    }
+
+    if editor_state.show_batch_delete_confirmation {
+        let node_type = editor_state.batch_delete_type.clone();
+        let matching: Vec<(u32, Entity)> = node_query
+            .iter()
+            .filter(|node| Some(&node.data.node_type) == node_type.as_ref())
+            .filter_map(|node| Some((node.id, *skill_tree_data.nodes.get(&node.id)?)))
+            .collect();
+        egui::Window::new("Delete Nodes")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "Delete all {} {} nodes? This cannot be undone.",
+                    matching.len(),
+                    node_type.as_ref().map_or("", NodeType::name)
+                ));
+                ui.horizontal(|ui| {
+                    if ui.button("Delete").clicked() {
+                        for &(id, entity) in &matching {
+                            delete_node(
+                                &mut commands,
+                                &mut skill_tree_data,
+                                &mut selected_node,
+                                &mut multi_selected,
+                                id,
+                                entity,
+                            );
+                        }
+                        editor_state.dirty |= !matching.is_empty();
+                        editor_state.batch_delete_type = None;
+                        editor_state.show_batch_delete_confirmation = false;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        editor_state.show_batch_delete_confirmation = false;
+                    }
+                });
+            });
+    }
+
+    if let Some(summary) = editor_state.csv_import_summary.clone() {
+        egui::Window::new("CSV Import")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(summary);
+                if ui.button("OK").clicked() {
+                    editor_state.csv_import_summary = None;
+                }
+            });
+    }
+
     let action_to_trigger = mem::replace(
         &mut editor_state.trigger_pending_action,
         NextActionAfterSaveAs::None,
@@ -693,24 +2087,2802 @@ pub fn ui_system(
                 &mut skill_tree_data,
                 &mut selected_node,
                 &mut selected_connection,
+                &mut multi_selected,
             );
+            open_tree_name_prompt(&mut editor_state);
+        }
+        NextActionAfterSaveAs::LoadDroppedFile => {
+            continue_load_sequence(&mut editor_state);
         }
         NextActionAfterSaveAs::None => {}
     }
+
+    if let Some(path) = editor_state.loading_file.clone() {
+        egui::Area::new(egui::Id::new("loading_overlay"))
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.label("Loading…");
+                });
+            });
+
+        // Give the overlay one frame on screen before the blocking load
+        if editor_state.loading_overlay_shown {
+            match load_file_into_editor(
+                path.clone(),
+                &mut commands,
+                &mut editor_state,
+                &mut skill_tree_data,
+                &mut selected_node,
+                &mut selected_connection,
+                &mut multi_selected,
+                &node_images,
+                &mut camera_transition,
+            ) {
+                Ok(true) => toast.info(format!(
+                    "Loaded {}",
+                    path.file_name().unwrap_or_default().to_string_lossy()
+                )),
+                Ok(false) => {}
+                Err(e) => {
+                    error!("Failed to load {}: {}", path.display(), e);
+                    editor_state.failed_load = Some((path.clone(), e.to_string()));
+                }
+            }
+            editor_state.loading_file = None;
+            editor_state.loading_overlay_shown = false;
+        } else {
+            editor_state.loading_overlay_shown = true;
+        }
+    }
+
+    if hot_reload.reload_prompt
+        && let Some(path) = hot_reload.watch_path.clone()
+    {
+        egui::Window::new("File Changed")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -40.0))
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} changed on disk. Reload?",
+                    path.file_name().unwrap_or_default().to_string_lossy()
+                ));
+                ui.horizontal(|ui| {
+                    if ui.button("Yes").clicked() {
+                        hot_reload.reload_prompt = false;
+                        if editor_state.dirty {
+                            editor_state.pending_dropped_file = Some(path.clone());
+                            editor_state.show_unsaved_changes_on_load_dialog = true;
+                            editor_state.next_action_after_save_as = NextActionAfterSaveAs::None;
+                        } else {
+                            editor_state.loading_file = Some(path.clone());
+                        }
+                    }
+                    if ui.button("No").clicked() {
+                        hot_reload.reload_prompt = false;
+                    }
+                });
+            });
+    }
+
+    if let Some((path, error)) = editor_state.failed_load.take() {
+        let mut keep = true;
+        egui::Window::new("Load Failed")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(format!("Failed to load {}:", path.display()));
+                ui.colored_label(egui::Color32::from_rgb(255, 100, 100), &error);
+                ui.label("A lenient load keeps every node and connection that still parses.");
+                ui.horizontal(|ui| {
+                    if ui.button("Try Lenient Load").clicked() {
+                        match load_skill_tree_lenient(path.to_str().unwrap_or_default()) {
+                            Ok(result) => {
+                                apply_save_data(
+                                    path.clone(),
+                                    result.data,
+                                    &mut commands,
+                                    &mut editor_state,
+                                    &mut skill_tree_data,
+                                    &mut selected_node,
+                                    &mut selected_connection,
+                                    &mut multi_selected,
+                                    &node_images,
+                                    &mut camera_transition,
+                                );
+                                // Saving over the damaged original would lose
+                                // the skipped entries, so ask for a new name
+                                editor_state.current_file_path = None;
+                                editor_state.dirty = true;
+                                editor_state.lenient_load_warnings = Some(result.warnings);
+                            }
+                            Err(e) => {
+                                toast.error(format!("Failed to recover {}: {}", path.display(), e))
+                            }
+                        }
+                        keep = false;
+                    }
+                    if ui.button("Close").clicked() {
+                        keep = false;
+                    }
+                });
+            });
+        if keep {
+            editor_state.failed_load = Some((path, error));
+        }
+    }
+
+    if let Some(warnings) = &editor_state.lenient_load_warnings {
+        let mut dismissed = false;
+        egui::Window::new("Lenient Load")
+            .collapsible(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                if warnings.is_empty() {
+                    ui.label("The file loaded without skipping anything.");
+                } else {
+                    ui.label(format!("Skipped {} entries:", warnings.len()));
+                    egui::ScrollArea::vertical()
+                        .max_height(300.0)
+                        .show(ui, |ui| {
+                            for warning in warnings {
+                                ui.label(warning);
+                            }
+                        });
+                }
+                ui.label("Save the recovered tree under a new name to keep the original.");
+                dismissed = ui.button("OK").clicked();
+            });
+        if dismissed {
+            editor_state.lenient_load_warnings = None;
+        }
+    }
+
+    if let Some((path, save_data)) = editor_state.pending_newer_load.take() {
+        let mut keep = true;
+        egui::Window::new("Newer File Version")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} was saved by a newer version of the editor (version {}, this editor supports up to {}).",
+                    path.file_name().unwrap_or_default().to_string_lossy(),
+                    save_data.version,
+                    migration::CURRENT_VERSION
+                ));
+                ui.label("Some of its data may be missing or misread, and saving may lose it.");
+                ui.horizontal(|ui| {
+                    if ui.button("Load Anyway").clicked() {
+                        apply_save_data(
+                            path.clone(),
+                            save_data.clone(),
+                            &mut commands,
+                            &mut editor_state,
+                            &mut skill_tree_data,
+                            &mut selected_node,
+                            &mut selected_connection,
+                            &mut multi_selected,
+                            &node_images,
+                            &mut camera_transition,
+                        );
+                        if editor_state.pending_newer_is_restore {
+                            editor_state.dirty = true;
+                        }
+                        toast.info(format!(
+                            "Loaded {}",
+                            path.file_name().unwrap_or_default().to_string_lossy()
+                        ));
+                        keep = false;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        keep = false;
+                    }
+                });
+            });
+        if keep {
+            editor_state.pending_newer_load = Some((path, save_data));
+        }
+    }
 }
 
-fn open_load_dialog_sequence(editor_state: &mut EditorState) {
-    editor_state.available_ron_files.clear();
-    if let Ok(entries) = fs::read_dir(".") {
-        for entry in entries.filter_map(Result::ok) {
-            let path = entry.path();
-            if path.is_file() && path.extension().is_some_and(|ext| ext == "ron") {
-                editor_state.available_ron_files.push(path);
+/// Diff panel, validation report and node comparison windows
+#[allow(clippy::too_many_arguments)]
+pub fn analysis_windows_ui(
+    mut contexts: EguiContexts,
+    mut editor_state: ResMut<EditorState>,
+    skill_tree_data: Res<SkillTreeData>,
+    mut selected_node: ResMut<SelectedNode>,
+    mut selected_connection: ResMut<SelectedConnection>,
+    node_query: Query<&mut SkillNode>,
+    mut toast: ResMut<TimedToast>,
+    mut compare_tree: ResMut<CompareTree>,
+    mut editor_camera: ResMut<EditorCamera>,
+) {
+    let ctx = contexts.ctx_mut();
+
+    if compare_tree.is_active() {
+        let mut close_diff = false;
+        egui::Window::new("Diff Panel")
+            .resizable(true)
+            .default_pos(egui::pos2(20.0, 60.0))
+            .show(ctx, |ui| {
+                let compare_name = compare_tree
+                    .path
+                    .as_ref()
+                    .and_then(|p| p.file_name())
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                ui.label(format!("Comparing with {}", compare_name));
+                ui.separator();
+
+                let diff = &compare_tree.diff;
+                if diff.is_empty() {
+                    ui.label("No differences");
+                }
+
+                let current_name = |id: u32| {
+                    node_query
+                        .iter()
+                        .find(|node| node.id == id)
+                        .map_or_else(String::new, |node| node.data.name.clone())
+                };
+                let compare_name = |id: u32| {
+                    compare_tree
+                        .nodes
+                        .get(&id)
+                        .map_or_else(String::new, |node| node.name.clone())
+                };
+
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        for (label, color, ids, name_of) in [
+                            (
+                                "Only in current",
+                                egui::Color32::from_rgb(100, 230, 100),
+                                &diff.only_current,
+                                &current_name as &dyn Fn(u32) -> String,
+                            ),
+                            (
+                                "Only in comparison",
+                                egui::Color32::from_rgb(230, 80, 80),
+                                &diff.only_compare,
+                                &compare_name,
+                            ),
+                            (
+                                "Changed",
+                                egui::Color32::from_rgb(240, 215, 80),
+                                &diff.changed,
+                                &current_name,
+                            ),
+                        ] {
+                            if ids.is_empty() {
+                                continue;
+                            }
+                            let mut ids: Vec<u32> = ids.iter().copied().collect();
+                            ids.sort_unstable();
+                            ui.colored_label(color, format!("{} ({})", label, ids.len()));
+                            for id in ids {
+                                ui.label(format!("  {}: {}", id, name_of(id)));
+                            }
+                        }
+                    });
+
+                ui.separator();
+                if ui.button("Close Diff").clicked() {
+                    close_diff = true;
+                }
+                ui.label("Press Escape to close");
+            });
+        if close_diff {
+            compare_tree.close();
+        }
+    }
+
+    if let Some(report) = editor_state.validation_report.take() {
+        let mut open = true;
+        let mut rerun = false;
+        let mut focus = None;
+        egui::Window::new("Validation Report")
+            .open(&mut open)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                let issue_count = report.issue_count();
+                if issue_count == 0 {
+                    ui.colored_label(egui::Color32::from_rgb(100, 220, 100), "All checks passed");
+                } else {
+                    ui.colored_label(
+                        egui::Color32::from_rgb(255, 100, 100),
+                        format!("{} issue(s) found", issue_count),
+                    );
+                }
+                ui.separator();
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        for check in &report.checks {
+                            if check.passed() {
+                                ui.colored_label(
+                                    egui::Color32::from_rgb(100, 220, 100),
+                                    format!("✔ {}", check.name),
+                                );
+                                continue;
+                            }
+                            egui::CollapsingHeader::new(
+                                egui::RichText::new(format!(
+                                    "✖ {} ({})",
+                                    check.name,
+                                    check.items.len()
+                                ))
+                                .color(egui::Color32::from_rgb(255, 100, 100)),
+                            )
+                            .id_salt(check.name)
+                            .show(ui, |ui| {
+                                for item in &check.items {
+                                    match item.target {
+                                        Some(target) => {
+                                            if ui.link(&item.message).clicked() {
+                                                focus = Some(target);
+                                            }
+                                        }
+                                        None => {
+                                            ui.label(&item.message);
+                                        }
+                                    }
+                                }
+                            });
+                        }
+                    });
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("Re-run").clicked() {
+                        rerun = true;
+                    }
+                    if ui.button("Export as .txt").clicked() {
+                        let path = validation_report_path(&editor_state);
+                        match storage::write(&path, report.to_text()) {
+                            Ok(()) => {
+                                storage::offer_download(&path);
+                                toast.info(format!("Exported {}", path.display()))
+                            }
+                            Err(e) => {
+                                toast.error(format!("Failed to export {}: {}", path.display(), e))
+                            }
+                        }
+                    }
+                });
+            });
+
+        match focus {
+            Some(ReportTarget::Node(id)) => {
+                if let Some(&entity) = skill_tree_data.nodes.get(&id) {
+                    if let Ok(node) = node_query.get(entity) {
+                        editor_camera.pan_offset = node.data.position;
+                    }
+                    selected_node.entity = Some(entity);
+                    selected_node.id = Some(id);
+                    selected_connection.index = None;
+                }
+            }
+            Some(ReportTarget::Connection(index)) => {
+                if let Some(connection) = skill_tree_data.connections.get(index) {
+                    let position_of = |id: u32| {
+                        skill_tree_data
+                            .nodes
+                            .get(&id)
+                            .and_then(|&entity| node_query.get(entity).ok())
+                            .map(|node| node.data.position)
+                    };
+                    if let (Some(from), Some(to)) = (
+                        position_of(connection.from_id),
+                        position_of(connection.to_id),
+                    ) {
+                        editor_camera.pan_offset =
+                            connection_midpoint(from, to, &connection.curve_type);
+                    }
+                    selected_connection.index = Some(index);
+                    selected_node.entity = None;
+                    selected_node.id = None;
+                }
             }
+            None => {}
+        }
+
+        if rerun {
+            editor_state.validation_report = Some(run_validation(&skill_tree_data, &node_query));
+        } else if open {
+            editor_state.validation_report = Some(report);
         }
     }
-    editor_state.available_ron_files.sort();
-    editor_state.show_load_dialog = true;
+
+    if let Some((first_id, second_id)) = editor_state.compared_nodes {
+        let node_data = |id| {
+            node_query
+                .iter()
+                .find(|node| node.id == id)
+                .map(|node| node.data.clone())
+        };
+        let mut open = true;
+        if let (Some(first), Some(second)) = (node_data(first_id), node_data(second_id)) {
+            egui::Window::new("Compare Nodes")
+                .open(&mut open)
+                .default_width(420.0)
+                .show(ctx, |ui| show_node_comparison(ui, &first, &second));
+        } else {
+            // One of the nodes was deleted
+            open = false;
+        }
+        if !open {
+            editor_state.compared_nodes = None;
+        }
+    }
+}
+
+/// Popups attached to canvas items: the hover tooltip, the node edit
+/// dialog, the inline rename box, the context menu and the connection
+/// toolbar
+#[allow(clippy::too_many_arguments)]
+pub fn canvas_popups_ui(
+    mut contexts: EguiContexts,
+    mut commands: Commands,
+    mut editor_state: ResMut<EditorState>,
+    mut skill_tree_data: ResMut<SkillTreeData>,
+    selection: Selection,
+    mut node_query: Query<&mut SkillNode>,
+    mut connection_mode: ResMut<ConnectionMode>,
+    node_images: Res<NodeImages>,
+    hovered_node: Res<HoveredNode>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut context_menu: ResMut<ContextMenuState>,
+    mut node_edit_dialog: ResMut<NodeEditDialog>,
+    mut inline_rename: ResMut<InlineRename>,
+) {
+    let Selection {
+        mut selected_node,
+        mut selected_connection,
+        mut multi_selected,
+    } = selection;
+    let ctx = contexts.ctx_mut();
+
+    // Hover tooltip, skipped for the selected node since the panel already shows it
+    let hovered = hovered_node
+        .entity
+        .filter(|_| hovered_node.id != selected_node.id)
+        .and_then(|entity| node_query.get(entity).ok());
+    if let (Some(node), Some(pointer)) = (hovered, ctx.pointer_hover_pos()) {
+        egui::Area::new(egui::Id::new("node_hover_tooltip"))
+            .order(egui::Order::Tooltip)
+            .fixed_pos(pointer + egui::vec2(16.0, 16.0))
+            .interactable(false)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.strong(&node.data.name);
+                    ui.label(node.data.node_type.name());
+                    for modifier in &node.data.stats {
+                        ui.label(stats::format_modifier(modifier));
+                    }
+                });
+            });
+    }
+
+    if let Some((entity, mut draft)) = node_edit_dialog.node.take() {
+        let known_stat_names: BTreeSet<String> = node_query
+            .iter()
+            .flat_map(|node| node.data.stats.iter().map(|stat| stat.stat_name.clone()))
+            .collect();
+        let mut keep_open = node_query.contains(entity);
+        let mut apply = false;
+        egui::Window::new(format!("Edit Node {}", draft.id))
+            .id(egui::Id::new("node_edit_dialog"))
+            .collapsible(false)
+            .default_size([420.0, 520.0])
+            .show(ctx, |ui| {
+                ui.label("Name:");
+                ui.text_edit_singleline(&mut draft.name);
+                ui.label("Description:");
+                ui.add(
+                    egui::TextEdit::multiline(&mut draft.description)
+                        .desired_rows(6)
+                        .desired_width(f32::INFINITY),
+                );
+                ui.separator();
+                image_picker(ui, &mut draft.image_name, &node_images);
+                node_type_editor(ui, &mut draft.node_type);
+                ui.horizontal(|ui| {
+                    ui.label("Tier:");
+                    ui.add(
+                        egui::DragValue::new(&mut draft.tier).range(0..=SkillNodeData::MAX_TIER),
+                    );
+                    ui.label("Cost:");
+                    ui.add(egui::DragValue::new(&mut draft.cost).range(0..=100));
+                });
+                ui.separator();
+                ui.label("Stats:");
+                egui::ScrollArea::vertical()
+                    .max_height(240.0)
+                    .show(ui, |ui| {
+                        stats_editor(ui, &mut draft.stats, &known_stat_names);
+                    });
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("OK").clicked() {
+                        apply = true;
+                        keep_open = false;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        keep_open = false;
+                    }
+                });
+            });
+
+        if apply {
+            if let Ok(mut node) = node_query.get_mut(entity) {
+                // The node may have been dragged while the dialog was open
+                draft.position = node.data.position;
+                if node.data != draft {
+                    node.data = draft;
+                    editor_state.dirty = true;
+                }
+            }
+        } else if keep_open {
+            node_edit_dialog.node = Some((entity, draft));
+        }
+    }
+
+    // F2 rename box over the node, closed once the node leaves the screen
+    if let Some((entity, mut name)) = inline_rename.node.take() {
+        let screen_pos = node_query.get(entity).ok().and_then(|node| {
+            let (camera, camera_transform) = camera_query.single().ok()?;
+            let screen = camera
+                .world_to_viewport(camera_transform, node.data.position.extend(0.0))
+                .ok()?;
+            let viewport = camera.logical_viewport_size()?;
+            (screen.cmpge(Vec2::ZERO).all() && screen.cmple(viewport).all())
+                .then(|| egui::pos2(screen.x, screen.y))
+        });
+
+        let mut finished = screen_pos.is_none();
+        let mut commit = finished;
+        if let Some(pos) = screen_pos {
+            egui::Window::new("Rename Node")
+                .title_bar(false)
+                .resizable(false)
+                .pivot(egui::Align2::CENTER_CENTER)
+                .fixed_pos(pos)
+                .show(ctx, |ui| {
+                    let response =
+                        ui.add(egui::TextEdit::singleline(&mut name).desired_width(160.0));
+                    if !inline_rename.focused {
+                        response.request_focus();
+                        inline_rename.focused = true;
+                    } else if response.lost_focus() {
+                        // Enter and clicking away both commit, Escape cancels
+                        finished = true;
+                        commit = !ui.input(|i| i.key_pressed(egui::Key::Escape));
+                    }
+                });
+        }
+
+        if commit
+            && let Ok(mut node) = node_query.get_mut(entity)
+            && node.data.name != name
+        {
+            node.data.name = name.clone();
+            editor_state.dirty = true;
+        }
+        if !finished {
+            inline_rename.node = Some((entity, name));
+        }
+    }
+
+    if let Some(target) = context_menu.target {
+        let screen_position = context_menu.screen_position;
+        let world_position = context_menu.world_position;
+        let mut close = ctx.input(|i| i.key_pressed(egui::Key::Escape));
+        let response = egui::Window::new("Context Menu")
+            .title_bar(false)
+            .resizable(false)
+            .fixed_pos(egui::pos2(screen_position.x, screen_position.y))
+            .show(ctx, |ui| {
+                ui.set_min_width(140.0);
+                match target {
+                    ContextMenuTarget::Canvas => {
+                        if ui.button("New Node Here").clicked() {
+                            create_node_at(
+                                &mut commands,
+                                &mut editor_state,
+                                &mut skill_tree_data,
+                                &node_images,
+                                world_position,
+                            );
+                            close = true;
+                        }
+                    }
+                    ContextMenuTarget::Node { id, entity } => {
+                        if ui.button("Delete").clicked() {
+                            delete_node(
+                                &mut commands,
+                                &mut skill_tree_data,
+                                &mut selected_node,
+                                &mut multi_selected,
+                                id,
+                                entity,
+                            );
+                            editor_state.dirty = true;
+                            close = true;
+                        }
+                        if ui.button("Duplicate").clicked() {
+                            if let Ok(node) = node_query.get(entity) {
+                                let mut node_data = mark_created(node.data.clone());
+                                node_data.id = editor_state.next_node_id;
+                                node_data.position +=
+                                    Vec2::new(DUPLICATE_OFFSET, -DUPLICATE_OFFSET);
+                                let entity = spawn_node(&mut commands, &node_data, &node_images);
+                                skill_tree_data.nodes.insert(node_data.id, entity);
+                                editor_state.next_node_id += 1;
+                                editor_state.dirty = true;
+                            }
+                            close = true;
+                        }
+                        if ui.button("Set as Start").clicked() {
+                            if let Ok(mut node) = node_query.get_mut(entity) {
+                                node.data.node_type = NodeType::Builtin(BuiltinNodeType::Start);
+                                editor_state.dirty = true;
+                            }
+                            close = true;
+                        }
+                        if ui.button("Connect From Here").clicked() {
+                            connection_mode.active = true;
+                            connection_mode.start_node = Some(id);
+                            connection_mode.start_port = context_menu.port;
+                            close = true;
+                        }
+                        if ui.button("Export Subtree…").clicked() {
+                            open_export_dialog(
+                                &mut editor_state,
+                                ExportFormat::Subtree { root: id },
+                            );
+                            editor_state.export_file_name_buffer = format!("subtree_{}.ron", id);
+                            close = true;
+                        }
+                    }
+                    ContextMenuTarget::Connection(index) => {
+                        if ui.button("Delete").clicked() {
+                            if index < skill_tree_data.connections.len() {
+                                skill_tree_data.connections.remove(index);
+                                selected_connection.index = match selected_connection.index {
+                                    Some(selected) if selected == index => None,
+                                    Some(selected) if selected > index => Some(selected - 1),
+                                    selected => selected,
+                                };
+                                editor_state.dirty = true;
+                            }
+                            close = true;
+                        }
+                        if let Some(connection) = skill_tree_data.connections.get_mut(index) {
+                            if ui.button("Reverse").clicked() {
+                                connection.reverse();
+                                editor_state.dirty = true;
+                                close = true;
+                            }
+                            if ui.button("Set Straight").clicked() {
+                                connection.curve_type = CurveType::Straight;
+                                editor_state.dirty = true;
+                                close = true;
+                            }
+                            if ui.button("Set Arc").clicked() {
+                                if !matches!(connection.curve_type, CurveType::Arc { .. }) {
+                                    connection.curve_type = CurveType::Arc {
+                                        radius: 100.0,
+                                        clockwise: false,
+                                        animated: false,
+                                    };
+                                    editor_state.dirty = true;
+                                }
+                                close = true;
+                            }
+                        }
+                    }
+                }
+            });
+        if response.is_some_and(|response| response.response.clicked_elsewhere()) {
+            close = true;
+        }
+        if close {
+            context_menu.target = None;
+        }
+    }
+
+    // Floating toolbar at the selected connection's midpoint, hidden once it leaves the screen
+    let toolbar_pos = selected_connection
+        .index
+        .and_then(|index| skill_tree_data.connections.get(index))
+        .and_then(|connection| {
+            let position = |id| {
+                node_query
+                    .iter()
+                    .find(|node| node.id == id)
+                    .map(|node| node.data.position)
+            };
+            let midpoint = connection_midpoint(
+                position(connection.from_id)?,
+                position(connection.to_id)?,
+                &connection.curve_type,
+            );
+            let (camera, camera_transform) = camera_query.single().ok()?;
+            let screen = camera
+                .world_to_viewport(camera_transform, midpoint.extend(0.0))
+                .ok()?;
+            let viewport = camera.logical_viewport_size()?;
+            (screen.cmpge(Vec2::ZERO).all() && screen.cmple(viewport).all())
+                .then(|| egui::pos2(screen.x, screen.y))
+        });
+    if let (Some(pos), Some(index)) = (toolbar_pos, selected_connection.index) {
+        egui::Area::new(egui::Id::new("connection_toolbar"))
+            .order(egui::Order::Foreground)
+            .pivot(egui::Align2::CENTER_BOTTOM)
+            .fixed_pos(pos - egui::vec2(0.0, 12.0))
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        let connection = &mut skill_tree_data.connections[index];
+                        let is_straight = matches!(connection.curve_type, CurveType::Straight);
+                        if ui.selectable_label(is_straight, "Straight").clicked() && !is_straight {
+                            connection.curve_type = CurveType::Straight;
+                            editor_state.dirty = true;
+                        }
+                        let is_arc = matches!(connection.curve_type, CurveType::Arc { .. });
+                        if ui.selectable_label(is_arc, "Arc").clicked() && !is_arc {
+                            connection.curve_type = CurveType::Arc {
+                                radius: 100.0,
+                                clockwise: false,
+                                animated: false,
+                            };
+                            editor_state.dirty = true;
+                        }
+                        ui.separator();
+                        if ui
+                            .button("Flip")
+                            .on_hover_text("Swap the from and to nodes")
+                            .clicked()
+                        {
+                            connection.reverse();
+                            editor_state.dirty = true;
+                        }
+                        if ui.button("Delete").clicked() {
+                            skill_tree_data.connections.remove(index);
+                            selected_connection.index = None;
+                            editor_state.dirty = true;
+                        }
+                    });
+                });
+            });
+    }
+}
+
+/// Toast message along the bottom of the canvas
+pub fn toast_ui(mut contexts: EguiContexts, toast: Res<TimedToast>) {
+    let ctx = contexts.ctx_mut();
+
+    if toast.remaining > 0.0 {
+        egui::Area::new(egui::Id::new("toast"))
+            .anchor(egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -40.0))
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    let color = match toast.kind {
+                        ToastKind::Info => egui::Color32::WHITE,
+                        ToastKind::Warning => egui::Color32::from_rgb(255, 200, 100),
+                        ToastKind::Error => egui::Color32::from_rgb(255, 100, 100),
+                    };
+                    ui.colored_label(color, &toast.message);
+                });
+            });
+    }
+}
+
+/// Connection or node properties for the current selection, shown in the
+/// side panel or in the detached Properties window
+#[allow(clippy::too_many_arguments)]
+fn properties_ui(
+    ui: &mut egui::Ui,
+    commands: &mut Commands,
+    editor_state: &mut EditorState,
+    skill_tree_data: &mut SkillTreeData,
+    selected_node: &mut SelectedNode,
+    selected_connection: &mut SelectedConnection,
+    multi_selected: &mut MultiSelectedNodes,
+    node_query: &mut Query<&mut SkillNode>,
+    node_images: &mut NodeImages,
+    toast: &mut TimedToast,
+    asset_server: &AssetServer,
+    template_library: &mut TemplateLibrary,
+    connection_style_presets: &mut ConnectionStylePresets,
+) {
+    // Connection Properties
+    if let Some(connection_index) = selected_connection.index {
+        if let Some(connection) = skill_tree_data.connections.get_mut(connection_index) {
+            ui.heading("Connection Properties");
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "From Node {} to Node {}",
+                    connection.from_id, connection.to_id
+                ));
+                if ui
+                    .button("Reverse")
+                    .on_hover_text("Swap the from and to nodes")
+                    .clicked()
+                {
+                    connection.reverse();
+                    editor_state.dirty = true;
+                }
+            });
+            if ui
+                .checkbox(&mut connection.enabled, "Enabled")
+                .on_hover_text(
+                    "Disabled connections are drawn dashed and ignored by path finding, \
+                     to try the tree without them",
+                )
+                .changed()
+            {
+                editor_state.dirty = true;
+            }
+
+            ui.separator();
+            ui.label("Curve Type:");
+
+            let mut curve_type_changed = false;
+            let is_straight = matches!(connection.curve_type, CurveType::Straight);
+
+            if ui.radio(is_straight, "Straight").clicked() {
+                connection.curve_type = CurveType::Straight;
+                curve_type_changed = true;
+            }
+
+            if ui
+                .radio(
+                    matches!(connection.curve_type, CurveType::Arc { .. }),
+                    "Arc",
+                )
+                .clicked()
+            {
+                connection.curve_type = CurveType::Arc {
+                    radius: 100.0,
+                    clockwise: false,
+                    animated: false,
+                };
+                curve_type_changed = true;
+            }
+
+            let is_curve = matches!(connection.curve_type, CurveType::CatmullRom { .. });
+            if ui.radio(is_curve, "Curve").clicked() && !is_curve {
+                // Start from a single waypoint halfway between the nodes,
+                // which looks the same as a straight line
+                let position = |id| {
+                    node_query
+                        .iter()
+                        .find(|node| node.id == id)
+                        .map(|node| node.data.position)
+                };
+                if let (Some(from), Some(to)) =
+                    (position(connection.from_id), position(connection.to_id))
+                {
+                    connection.curve_type = CurveType::CatmullRom {
+                        waypoints: vec![(from + to) * 0.5],
+                    };
+                    curve_type_changed = true;
+                }
+            }
+
+            if let CurveType::CatmullRom { waypoints } = &mut connection.curve_type {
+                ui.separator();
+                ui.label(format!("Curve Properties: {} waypoint(s)", waypoints.len()));
+                if ui
+                    .add_enabled(
+                        waypoints.len() > 1,
+                        egui::Button::new("Remove Last Waypoint"),
+                    )
+                    .clicked()
+                {
+                    waypoints.pop();
+                    curve_type_changed = true;
+                }
+
+                ui.add_space(5.0);
+                ui.label("Tips:");
+                ui.label("• Drag a waypoint handle to reshape the curve");
+                ui.label("• Drag a handle between waypoints to add one");
+            }
+
+            if let CurveType::Arc {
+                ref mut radius,
+                ref mut clockwise,
+                ref mut animated,
+            } = connection.curve_type
+            {
+                ui.separator();
+                ui.label("Arc Properties:");
+
+                if ui
+                    .add(egui::Slider::new(radius, 30.0..=500.0).text("Radius"))
+                    .changed()
+                {
+                    curve_type_changed = true;
+                }
+
+                if ui.checkbox(clockwise, "Clockwise").changed() {
+                    curve_type_changed = true;
+                }
+
+                if ui
+                    .checkbox(animated, "Animated")
+                    .on_hover_text("Send a pulse along the arc, to highlight it in presentations")
+                    .changed()
+                {
+                    curve_type_changed = true;
+                }
+
+                // Check if arc is valid and show warning
+                let mut from_pos = None;
+                let mut to_pos = None;
+                for node in node_query.iter() {
+                    if node.id == connection.from_id {
+                        from_pos = Some(node.data.position);
+                    }
+                    if node.id == connection.to_id {
+                        to_pos = Some(node.data.position);
+                    }
+                }
+
+                if let (Some(from), Some(to)) = (from_pos, to_pos) {
+                    let distance = from.distance(to);
+                    let min_radius = distance / 2.0;
+
+                    if *radius < min_radius {
+                        ui.colored_label(
+                            egui::Color32::from_rgb(255, 200, 100),
+                            format!("⚠ Radius too small! Minimum: {:.1}", min_radius),
+                        );
+                        ui.label("Arc will display as dashed line");
+
+                        if ui.button("Auto-fix radius").clicked() {
+                            *radius = min_radius + ARC_FIX_MARGIN;
+                            curve_type_changed = true;
+                        }
+                    }
+                }
+
+                ui.add_space(5.0);
+                ui.label("Tips:");
+                ui.label("• Larger radius = gentler curve");
+                ui.label("• Toggle clockwise to flip the arc");
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.menu_button("Presets", |ui| {
+                    if connection_style_presets.presets.is_empty() {
+                        ui.label("No presets saved yet");
+                    }
+                    let mut to_remove = None;
+                    for (index, preset) in connection_style_presets.presets.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            if ui.button(&preset.name).clicked() {
+                                connection.curve_type = preset.curve_type.clone();
+                                curve_type_changed = true;
+                                ui.close_menu();
+                            }
+                            if ui
+                                .small_button("🗑")
+                                .on_hover_text("Delete preset")
+                                .clicked()
+                            {
+                                to_remove = Some(index);
+                            }
+                        });
+                    }
+                    if let Some(index) = to_remove {
+                        connection_style_presets.presets.remove(index);
+                        connection_style_presets.needs_save = true;
+                    }
+                });
+                // A curve's waypoints only make sense between its own nodes
+                if ui
+                    .add_enabled(!is_curve, egui::Button::new("Save Current as Preset"))
+                    .on_disabled_hover_text("Curves can't be saved as presets")
+                    .clicked()
+                {
+                    let name = connection_style_presets.add(connection.curve_type.clone());
+                    toast.info(format!("Saved preset '{}'", name));
+                }
+            });
+
+            if curve_type_changed {
+                editor_state.dirty = true;
+            }
+
+            ui.separator();
+            if ui.button("Delete Connection").clicked() {
+                skill_tree_data.connections.remove(connection_index);
+                selected_connection.index = None;
+                editor_state.dirty = true;
+            }
+        }
+    }
+    // Node Properties
+    else if let Some(entity) = selected_node.entity {
+        let known_stat_names: BTreeSet<String> = node_query
+            .iter()
+            .flat_map(|node| node.data.stats.iter().map(|stat| stat.stat_name.clone()))
+            .collect();
+        let (min_z, max_z) = node_query
+            .iter()
+            .map(|node| node.data.z_order)
+            .fold((f32::MAX, f32::MIN), |(min, max), z| {
+                (min.min(z), max.max(z))
+            });
+        let mut renormalize_z = false;
+        if let Ok(mut node) = node_query.get_mut(entity) {
+            ui.heading("Node Properties");
+            ui.label(format!("ID: {}", node.id));
+            ui.label(format!(
+                "Created: {}",
+                format_timestamp(node.data.created_at)
+            ));
+            ui.label(format!(
+                "Modified: {}",
+                format_timestamp(node.data.modified_at)
+            ));
+            if multi_selected.nodes.len() == 2 {
+                let mut ids = multi_selected.nodes.iter().copied().collect::<Vec<_>>();
+                ids.sort_unstable();
+                if ui
+                    .button("Compare")
+                    .on_hover_text("Show the two selected nodes side by side")
+                    .clicked()
+                {
+                    editor_state.compared_nodes = Some((ids[0], ids[1]));
+                }
+            }
+            // Sections are stored as collapsed/expanded by key so the choice
+            // sticks when switching between nodes
+            let mut collapsed_sections = mem::take(&mut editor_state.collapsed_sections);
+            collapsible_section(
+                ui,
+                &mut collapsed_sections,
+                "general",
+                "Name & Description".to_string(),
+                |ui| {
+                    ui.label("Name:");
+                    if ui.text_edit_singleline(&mut node.data.name).changed() {
+                        editor_state.dirty = true;
+                    }
+                    ui.label("Description:");
+                    if ui.text_edit_multiline(&mut node.data.description).changed() {
+                        editor_state.dirty = true;
+                    }
+                },
+            );
+            collapsible_section(
+                ui,
+                &mut collapsed_sections,
+                "node_type",
+                "Node Type".to_string(),
+                |ui| {
+                    if node_type_editor(ui, &mut node.data.node_type) {
+                        editor_state.dirty = true;
+                    }
+                },
+            );
+            let stats_title = if collapsed_sections.get("stats").copied().unwrap_or(false) {
+                format!("Stats ({})", node.data.stats.len())
+            } else {
+                "Stats".to_string()
+            };
+            collapsible_section(ui, &mut collapsed_sections, "stats", stats_title, |ui| {
+                if stats_editor(ui, &mut node.data.stats, &known_stat_names) {
+                    editor_state.dirty = true;
+                }
+            });
+            collapsible_section(
+                ui,
+                &mut collapsed_sections,
+                "position",
+                "Position".to_string(),
+                |ui| {
+                    ui.horizontal(|ui| {
+                        let mut position = node.data.position;
+                        ui.label("X:");
+                        let x_changed = ui.add(egui::DragValue::new(&mut position.x)).changed();
+                        ui.label("Y:");
+                        let y_changed = ui.add(egui::DragValue::new(&mut position.y)).changed();
+                        if x_changed || y_changed {
+                            node.data.position = position;
+                            editor_state.dirty = true;
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Z Order:");
+                        if ui
+                            .add(
+                                egui::DragValue::new(&mut node.data.z_order)
+                                    .speed(0.1)
+                                    .range(
+                                        -SkillNodeData::MAX_Z_ORDER..=SkillNodeData::MAX_Z_ORDER,
+                                    ),
+                            )
+                            .changed()
+                        {
+                            editor_state.dirty = true;
+                        }
+                    });
+                    let mut z_order = node.data.z_order;
+                    ui.horizontal(|ui| {
+                        if ui.button("Bring to Front").clicked() {
+                            z_order = max_z + 1.0;
+                        }
+                        if ui.button("Bring Forward").clicked() {
+                            z_order += 1.0;
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        if ui.button("Send to Back").clicked() {
+                            z_order = min_z - 1.0;
+                        }
+                        if ui.button("Send Backward").clicked() {
+                            z_order -= 1.0;
+                        }
+                    });
+                    if z_order != node.data.z_order {
+                        // Past the band the whole tree is respaced, keeping
+                        // this node in front of or behind the rest
+                        renormalize_z = z_order.abs() > SkillNodeData::MAX_Z_ORDER;
+                        node.data.z_order = z_order;
+                        editor_state.dirty = true;
+                    }
+                },
+            );
+            let ports_title = format!("Ports ({})", node.data.ports.len());
+            collapsible_section(ui, &mut collapsed_sections, "ports", ports_title, |ui| {
+                if ports_editor(ui, &mut node.data, &mut skill_tree_data.connections) {
+                    editor_state.dirty = true;
+                }
+            });
+            collapsible_section(
+                ui,
+                &mut collapsed_sections,
+                "advanced",
+                "Advanced".to_string(),
+                |ui| {
+                    if image_picker(ui, &mut node.data.image_name, node_images) {
+                        editor_state.dirty = true;
+                    }
+                    ui.horizontal(|ui| {
+                        ui.text_edit_singleline(&mut editor_state.import_image_buffer)
+                            .on_hover_text("Image file name inside the assets folder");
+                        if ui.button("Import Image").clicked() {
+                            let image_name = editor_state.import_image_buffer.trim().to_string();
+                            let image_path = Path::new(ASSETS_DIR).join(&image_name);
+                            if !is_image_file(&image_path) || !storage::exists(&image_path) {
+                                toast.error(format!("No image named '{}' in assets", image_name));
+                            } else {
+                                node_images.load(asset_server, &image_name);
+                                node.data.image_name = image_name;
+                                editor_state.import_image_buffer.clear();
+                                editor_state.dirty = true;
+                            }
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Tier:");
+                        if ui
+                            .add(
+                                egui::DragValue::new(&mut node.data.tier)
+                                    .range(0..=SkillNodeData::MAX_TIER),
+                            )
+                            .changed()
+                        {
+                            editor_state.dirty = true;
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Cost:");
+                        if ui
+                            .add(egui::DragValue::new(&mut node.data.cost).range(0..=100))
+                            .changed()
+                        {
+                            editor_state.dirty = true;
+                        }
+                    });
+                },
+            );
+            collapsible_section(
+                ui,
+                &mut collapsed_sections,
+                "connections",
+                "Connections".to_string(),
+                |ui| {
+                    let in_degree = skill_tree_data
+                        .connections
+                        .iter()
+                        .filter(|conn| conn.to_id == node.id)
+                        .count();
+                    let out_degree = skill_tree_data
+                        .connections
+                        .iter()
+                        .filter(|conn| conn.from_id == node.id)
+                        .count();
+                    ui.label(format!("In-degree: {}", in_degree));
+                    ui.label(format!("Out-degree: {}", out_degree));
+                    ui.label(format!("Total: {}", in_degree + out_degree));
+                    if in_degree + out_degree == 0 {
+                        ui.colored_label(egui::Color32::from_rgb(255, 200, 100), "⚠ Isolated node");
+                    } else {
+                        ui.label("Connected to:");
+                    }
+                    for (index, connection) in skill_tree_data.connections.iter().enumerate() {
+                        let label = if connection.from_id == node.id {
+                            format!("→ Node {}", connection.to_id)
+                        } else if connection.to_id == node.id {
+                            format!("← Node {}", connection.from_id)
+                        } else {
+                            continue;
+                        };
+                        if ui.selectable_label(false, label).clicked() {
+                            selected_connection.index = Some(index);
+                            selected_node.entity = None;
+                            selected_node.id = None;
+                        }
+                    }
+                },
+            );
+            editor_state.collapsed_sections = collapsed_sections;
+
+            ui.separator();
+            if ui
+                .button("Save as Template")
+                .on_hover_text("Add this node to the Templates list")
+                .clicked()
+            {
+                template_library.add(&node.data.name, node.data.clone());
+                write_templates(template_library);
+                toast.info(format!("Saved '{}' as a template", node.data.name));
+            }
+            if ui.button("Delete Node").clicked() {
+                delete_node(
+                    commands,
+                    skill_tree_data,
+                    selected_node,
+                    multi_selected,
+                    node.id,
+                    entity,
+                );
+                editor_state.dirty = true;
+            }
+        }
+        if renormalize_z {
+            renormalize_z_orders(node_query);
+        }
+    } else {
+        ui.label("No node or connection selected");
+        ui.separator();
+        ui.label("Right-click for node, connection and canvas actions");
+        ui.label("Left-click to select nodes/connections");
+        ui.label("Ctrl + Left-click to add nodes to a group");
+        ui.label("Connect From Here, then right-click a node to connect");
+        ui.label("Middle mouse or Shift + Left Drag to pan");
+        ui.label("Scroll to zoom");
+        ui.label("Delete/Backspace to remove selected");
+
+        ui.separator();
+        ui.label("Delete All of Type:");
+        let mut node_types: Vec<NodeType> = Vec::new();
+        for node in node_query.iter() {
+            if !node_types.contains(&node.data.node_type) {
+                node_types.push(node.data.node_type.clone());
+            }
+        }
+        node_types.sort_by(|a, b| a.name().cmp(b.name()));
+        if editor_state
+            .batch_delete_type
+            .as_ref()
+            .is_some_and(|node_type| !node_types.contains(node_type))
+        {
+            editor_state.batch_delete_type = None;
+        }
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("batch_delete_type")
+                .selected_text(
+                    editor_state
+                        .batch_delete_type
+                        .as_ref()
+                        .map_or("Choose a type", NodeType::name),
+                )
+                .show_ui(ui, |ui| {
+                    for node_type in node_types {
+                        let label = node_type.name().to_string();
+                        ui.selectable_value(
+                            &mut editor_state.batch_delete_type,
+                            Some(node_type),
+                            label,
+                        );
+                    }
+                });
+            if ui
+                .add_enabled(
+                    editor_state.batch_delete_type.is_some(),
+                    egui::Button::new("Delete All"),
+                )
+                .clicked()
+            {
+                editor_state.show_batch_delete_confirmation = true;
+            }
+        });
+    }
+}
+
+/// Node type combo box, with a name field for custom types. Returns whether
+/// the type changed.
+fn node_type_editor(ui: &mut egui::Ui, node_type: &mut NodeType) -> bool {
+    ui.label("Node Type:");
+    let mut node_type_changed = false;
+    egui::ComboBox::from_label("NodeType")
+        .selected_text(match node_type {
+            NodeType::Builtin(builtin) => builtin.name(),
+            NodeType::Custom(_) => "Custom…",
+        })
+        .show_ui(ui, |ui| {
+            for builtin in BuiltinNodeType::ALL {
+                if ui
+                    .selectable_value(node_type, NodeType::Builtin(builtin), builtin.name())
+                    .clicked()
+                {
+                    node_type_changed = true;
+                }
+            }
+            let is_custom = matches!(node_type, NodeType::Custom(_));
+            if ui.selectable_label(is_custom, "Custom…").clicked() && !is_custom {
+                *node_type = NodeType::Custom(String::new());
+                node_type_changed = true;
+            }
+        });
+    if let NodeType::Custom(name) = node_type {
+        ui.horizontal(|ui| {
+            ui.label("Custom Type:");
+            if ui.text_edit_singleline(name).changed() {
+                node_type_changed = true;
+            }
+        });
+    }
+    node_type_changed
+}
+
+/// Combo box of loaded node images, warning when the current one isn't
+/// loaded. Returns whether the image changed.
+fn image_picker(ui: &mut egui::Ui, image_name: &mut String, node_images: &NodeImages) -> bool {
+    let mut changed = false;
+    ui.label("Image:");
+    let mut image_names: Vec<&String> = node_images.images.keys().collect();
+    image_names.sort();
+    egui::ComboBox::from_id_salt("node_image")
+        .selected_text(image_name.clone())
+        .show_ui(ui, |ui| {
+            for name in image_names {
+                if ui.selectable_label(image_name == name, name).clicked() {
+                    *image_name = name.clone();
+                    changed = true;
+                }
+            }
+        });
+    if !node_images.images.contains_key(image_name.as_str()) {
+        ui.colored_label(
+            egui::Color32::from_rgb(255, 200, 100),
+            "⚠ Image not loaded, showing placeholder",
+        );
+    }
+    changed
+}
+
+/// Spreads every node's z-order evenly across the allowed band, keeping
+/// their stacking order, so repeated Bring to Front can't grow it forever
+fn renormalize_z_orders(node_query: &mut Query<&mut SkillNode>) {
+    let mut nodes: Vec<Mut<SkillNode>> = node_query.iter_mut().collect();
+    nodes.sort_by(|a, b| {
+        a.data
+            .z_order
+            .total_cmp(&b.data.z_order)
+            .then(a.id.cmp(&b.id))
+    });
+    let half_span = nodes.len().saturating_sub(1) as f32 / 2.0;
+    let step = (SkillNodeData::MAX_Z_ORDER / half_span.max(1.0)).min(1.0);
+    for (rank, node) in nodes.iter_mut().enumerate() {
+        let z_order = (rank as f32 - half_span) * step;
+        if node.data.z_order != z_order {
+            node.data.z_order = z_order;
+        }
+    }
+}
+
+/// Rows for editing a node's ports, with Add Port and remove buttons.
+/// Connections on a removed port are moved to the node's center, so a port
+/// added later with the same id doesn't pick them up. Returns whether
+/// anything changed.
+fn ports_editor(
+    ui: &mut egui::Ui,
+    node_data: &mut SkillNodeData,
+    connections: &mut [ConnectionData],
+) -> bool {
+    let mut changed = false;
+    let mut removed = None;
+    for (index, port) in node_data.ports.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            changed |= ui
+                .add(egui::TextEdit::singleline(&mut port.name).desired_width(80.0))
+                .changed();
+            ui.label("X:");
+            changed |= ui
+                .add(egui::DragValue::new(&mut port.local_offset.x))
+                .changed();
+            ui.label("Y:");
+            changed |= ui
+                .add(egui::DragValue::new(&mut port.local_offset.y))
+                .changed();
+            if ui.button("✖").on_hover_text("Remove port").clicked() {
+                removed = Some(index);
+            }
+        });
+    }
+    if let Some(index) = removed {
+        let port_id = node_data.ports.remove(index).id;
+        for conn in connections.iter_mut() {
+            if conn.from_id == node_data.id && conn.from_port == Some(port_id) {
+                conn.from_port = None;
+            }
+            if conn.to_id == node_data.id && conn.to_port == Some(port_id) {
+                conn.to_port = None;
+            }
+        }
+        changed = true;
+    }
+    if ui
+        .button("Add Port")
+        .on_hover_text("Right-click near a port to start or end a connection there")
+        .clicked()
+    {
+        let id = node_data.next_port_id();
+        node_data.ports.push(PortAnchor {
+            id,
+            name: format!("Port {}", id + 1),
+            local_offset: Vec2::ZERO,
+        });
+        changed = true;
+    }
+    changed
+}
+
+/// Rows for editing a node's stat modifiers, with Add Stat and remove
+/// buttons. Returns whether anything changed.
+fn stats_editor(
+    ui: &mut egui::Ui,
+    stats: &mut Vec<StatModifier>,
+    known_stat_names: &BTreeSet<String>,
+) -> bool {
+    let mut changed = false;
+    let mut stat_to_remove_idx = None;
+    for (i, stat) in stats.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            if stat_name_edit(ui, &mut stat.stat_name, known_stat_names, i) {
+                changed = true;
+            }
+            if ui
+                .add(egui::DragValue::new(&mut stat.value).speed(0.1))
+                .changed()
+            {
+                changed = true;
+            }
+
+            let mut mod_type_changed = false;
+            egui::ComboBox::from_id_salt(format!("mod_type_{i}"))
+                .selected_text(format!("{:?}", stat.modifier_type))
+                .show_ui(ui, |ui| {
+                    for modifier_type in ModifierType::ALL {
+                        let label = format!("{:?}", modifier_type);
+                        if ui
+                            .selectable_value(&mut stat.modifier_type, modifier_type, label)
+                            .clicked()
+                        {
+                            mod_type_changed = true;
+                        }
+                    }
+                });
+            if mod_type_changed {
+                changed = true;
+            }
+
+            if ui.button("X").clicked() {
+                stat_to_remove_idx = Some(i);
+                changed = true;
+            }
+        });
+        ui.horizontal(|ui| {
+            // Starting bounds for a side that isn't set yet
+            let [default_min, default_max] = [0.0_f32.min(stat.value), stat.value.max(100.0)];
+            let mut clamp = stat.is_clamped();
+            if ui.checkbox(&mut clamp, "Clamp").changed() {
+                if clamp {
+                    // Only fills in a missing side, keeping any bound the
+                    // modifier already has
+                    stat.min_value.get_or_insert(default_min);
+                    stat.max_value.get_or_insert(default_max);
+                } else {
+                    stat.min_value = None;
+                    stat.max_value = None;
+                }
+                changed = true;
+            }
+            if !stat.is_clamped() {
+                return;
+            }
+            let bounds = [
+                ("Min", &mut stat.min_value, default_min),
+                ("Max", &mut stat.max_value, default_max),
+            ];
+            for (label, bound, default) in bounds {
+                match bound {
+                    Some(bound) => {
+                        ui.label(label);
+                        if ui.add(egui::DragValue::new(bound).speed(0.1)).changed() {
+                            changed = true;
+                        }
+                    }
+                    None => {
+                        if ui.small_button(format!("+ {label}")).clicked() {
+                            *bound = Some(default);
+                            changed = true;
+                        }
+                    }
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            let mut conditional = stat.condition.is_some();
+            if ui.checkbox(&mut conditional, "Condition").changed() {
+                stat.condition = conditional.then_some(StatCondition::NodeAllocated(0));
+                changed = true;
+            }
+            if let Some(condition) = &mut stat.condition {
+                let mut node_id = condition.node_id();
+                let mut allocated = matches!(condition, StatCondition::NodeAllocated(_));
+                egui::ComboBox::from_id_salt(format!("condition_{i}"))
+                    .selected_text(if allocated {
+                        "Allocated"
+                    } else {
+                        "Not Allocated"
+                    })
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut allocated, true, "Allocated");
+                        ui.selectable_value(&mut allocated, false, "Not Allocated");
+                    });
+                ui.label("Node");
+                ui.add(egui::DragValue::new(&mut node_id));
+                let updated = if allocated {
+                    StatCondition::NodeAllocated(node_id)
+                } else {
+                    StatCondition::NodeNotAllocated(node_id)
+                };
+                if updated != *condition {
+                    *condition = updated;
+                    changed = true;
+                }
+            }
+        });
+    }
+    if let Some(index) = stat_to_remove_idx {
+        stats.remove(index);
+    }
+    if ui.button("Add Stat").clicked() {
+        stats.push(StatModifier {
+            stat_name: "New Stat".to_string(),
+            value: 0.0,
+            modifier_type: ModifierType::Flat,
+            min_value: None,
+            max_value: None,
+            condition: None,
+        });
+        changed = true;
+    }
+    changed
+}
+
+/// Stat name field with a dropdown of names already used in the tree that
+/// start with the typed text. Tab or a click fills in a suggestion. Returns
+/// whether the name changed.
+fn stat_name_edit(
+    ui: &mut egui::Ui,
+    stat_name: &mut String,
+    known_stat_names: &BTreeSet<String>,
+    index: usize,
+) -> bool {
+    let suggestions = |stat_name: &str| -> Vec<String> {
+        let typed = stat_name.to_lowercase();
+        known_stat_names
+            .iter()
+            .filter(|name| *name != stat_name && name.to_lowercase().starts_with(&typed))
+            .cloned()
+            .collect()
+    };
+    let popup_id = ui.make_persistent_id(("stat_name_suggestions", index));
+
+    // Keep Tab from moving focus while there is something to complete
+    let lock_focus = !suggestions(stat_name).is_empty();
+    let response = ui.add(egui::TextEdit::singleline(stat_name).lock_focus(lock_focus));
+    let mut changed = response.changed();
+    let matches = suggestions(stat_name);
+
+    if response.has_focus() {
+        if matches.is_empty() {
+            ui.memory_mut(|mem| {
+                if mem.is_popup_open(popup_id) {
+                    mem.close_popup();
+                }
+            });
+        } else if ui.input(|input| input.key_pressed(egui::Key::Tab)) {
+            *stat_name = matches[0].clone();
+            changed = true;
+            ui.memory_mut(|mem| mem.close_popup());
+        } else {
+            ui.memory_mut(|mem| mem.open_popup(popup_id));
+        }
+    }
+
+    let chosen = egui::popup_below_widget(
+        ui,
+        popup_id,
+        &response,
+        egui::PopupCloseBehavior::CloseOnClickOutside,
+        |ui| {
+            let mut chosen = None;
+            for name in &matches {
+                if ui.selectable_label(false, name).clicked() {
+                    chosen = Some(name.clone());
+                }
+            }
+            chosen
+        },
+    )
+    .flatten();
+    if let Some(name) = chosen {
+        *stat_name = name;
+        changed = true;
+        ui.memory_mut(|mem| mem.close_popup());
+    }
+    changed
+}
+
+/// A collapsing header whose open state is kept in `collapsed_sections` by
+/// `key`, so `title` can change without resetting it
+fn collapsible_section(
+    ui: &mut egui::Ui,
+    collapsed_sections: &mut HashMap<String, bool>,
+    key: &str,
+    title: String,
+    add_contents: impl FnOnce(&mut egui::Ui),
+) {
+    let collapsed = collapsed_sections.get(key).copied().unwrap_or(false);
+    let response = egui::CollapsingHeader::new(title)
+        .id_salt(key)
+        .open(Some(!collapsed))
+        .show(ui, add_contents);
+    if response.header_response.clicked() {
+        collapsed_sections.insert(key.to_string(), !collapsed);
+    }
+}
+
+/// `text` with the characters at `indices` drawn bold and highlighted
+fn highlighted_match(text: &str, indices: &[usize], style: &egui::Style) -> egui::text::LayoutJob {
+    let font_id = egui::TextStyle::Button.resolve(style);
+    let normal = egui::TextFormat {
+        font_id: font_id.clone(),
+        color: style.visuals.text_color(),
+        ..Default::default()
+    };
+    let highlighted = egui::TextFormat {
+        font_id,
+        color: egui::Color32::from_rgb(255, 210, 90),
+        ..Default::default()
+    };
+
+    let mut job = egui::text::LayoutJob::default();
+    let mut buffer = [0; 4];
+    for (index, c) in text.chars().enumerate() {
+        let format = if indices.contains(&index) {
+            highlighted.clone()
+        } else {
+            normal.clone()
+        };
+        job.append(c.encode_utf8(&mut buffer), 0.0, format);
+    }
+    job
+}
+
+/// Resumes a load after the unsaved changes prompt, either loading a dropped
+/// file or opening the load dialog
+fn continue_load_sequence(editor_state: &mut EditorState) {
+    if let Some(path) = editor_state.pending_dropped_file.take() {
+        editor_state.loading_file = Some(path);
+    } else {
+        open_load_dialog_sequence(editor_state);
+    }
+}
+
+fn open_load_dialog_sequence(editor_state: &mut EditorState) {
+    editor_state.available_skill_tree_files = storage::list_files(".")
+        .into_iter()
+        .filter(|path| is_skill_tree_file(path))
+        .collect();
+    editor_state.available_skill_tree_files.sort();
+    editor_state.load_dialog_mode = LoadDialogMode::Open;
+    editor_state.show_load_dialog = true;
+}
+
+fn open_tree_name_prompt(editor_state: &mut EditorState) {
+    editor_state.tree_name_buffer.clear();
+    editor_state.show_tree_name_prompt = true;
+}
+
+/// Opens the load dialog in `mode`, listing files with the given extension
+fn open_import_dialog(editor_state: &mut EditorState, extension: &str, mode: LoadDialogMode) {
+    editor_state.available_skill_tree_files = storage::list_files(".")
+        .into_iter()
+        .filter(|path| path.extension().is_some_and(|ext| ext == extension))
+        .collect();
+    editor_state.available_skill_tree_files.sort();
+    editor_state.load_dialog_mode = mode;
+    editor_state.show_load_dialog = true;
+}
+
+fn open_export_dialog(editor_state: &mut EditorState, export_format: ExportFormat) {
+    let file_stem = editor_state
+        .current_file_path
+        .as_ref()
+        .and_then(|p| p.file_stem())
+        .and_then(|os_str| os_str.to_str())
+        .unwrap_or("untitled");
+    editor_state.export_file_name_buffer = format!("{}.{}", file_stem, export_format.extension());
+    editor_state.export_format = export_format;
+    editor_state.export_show_overwrite_prompt = false;
+    editor_state.show_export_dialog = true;
+}
+
+fn run_export(
+    export_format: ExportFormat,
+    path: &Path,
+    skill_tree_data: &SkillTreeData,
+    node_query: &Query<&mut SkillNode>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut nodes: Vec<SkillNodeData> = node_query.iter().map(|node| node.data.clone()).collect();
+    nodes.sort_by_key(|node| node.id);
+    let path = path.to_str().ok_or("Export path is not valid UTF-8")?;
+
+    match export_format {
+        ExportFormat::Svg => export_svg(path, &nodes, &skill_tree_data.connections)?,
+        ExportFormat::Png => return Err("PNG exports are rendered by run_png_export".into()),
+        ExportFormat::Binary => write_skill_tree_binary(
+            path,
+            &SkillTreeSaveData {
+                version: migration::CURRENT_VERSION,
+                start_node_id: first_start_node_id(&nodes),
+                nodes,
+                connections: skill_tree_data.connections.clone(),
+                tree_name: skill_tree_data.tree_name.clone(),
+                tree_description: skill_tree_data.tree_description.clone(),
+                saved_camera_state: None,
+            },
+        )?,
+        ExportFormat::Csv => export_csv(path, &nodes)?,
+        ExportFormat::Dot => export_dot(path, &nodes, &skill_tree_data.connections)?,
+        ExportFormat::Subtree { root } => {
+            let adjacency = graph::directed_adjacency(&skill_tree_data.connections);
+            let subtree_ids = graph::reachable_from([root], &adjacency);
+            nodes.retain(|node| subtree_ids.contains(&node.id));
+            write_skill_tree(
+                path,
+                &SkillTreeSaveData {
+                    version: migration::CURRENT_VERSION,
+                    start_node_id: first_start_node_id(&nodes),
+                    nodes,
+                    connections: skill_tree_data
+                        .connections
+                        .iter()
+                        .filter(|conn| {
+                            subtree_ids.contains(&conn.from_id) && subtree_ids.contains(&conn.to_id)
+                        })
+                        .cloned()
+                        .collect(),
+                    tree_name: skill_tree_data.tree_name.clone(),
+                    tree_description: skill_tree_data.tree_description.clone(),
+                    saved_camera_state: None,
+                },
+                SaveFormat::Ron,
+            )?
+        }
+    }
+    storage::offer_download(path);
+    Ok(())
+}
+
+/// Loads a file into the editor. Returns `Ok(false)` when the file is from a
+/// newer editor and is waiting on the user to confirm.
+#[allow(clippy::too_many_arguments)]
+fn load_file_into_editor(
+    path: PathBuf,
+    commands: &mut Commands,
+    editor_state: &mut EditorState,
+    skill_tree_data: &mut SkillTreeData,
+    selected_node: &mut SelectedNode,
+    selected_connection: &mut SelectedConnection,
+    multi_selected: &mut MultiSelectedNodes,
+    node_images: &NodeImages,
+    camera_transition: &mut CameraTransition,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    if is_scene_file(&path) {
+        perform_new_file_action(
+            commands,
+            editor_state,
+            skill_tree_data,
+            selected_node,
+            selected_connection,
+            multi_selected,
+        );
+        queue_scene_load(commands, path.clone());
+        editor_state.current_file_path = Some(path);
+        return Ok(true);
+    }
+
+    let save_data = load_skill_tree(path.to_str().unwrap_or_default())?;
+    if save_data.version > migration::CURRENT_VERSION {
+        editor_state.pending_newer_load = Some((path, save_data));
+        editor_state.pending_newer_is_restore = false;
+        return Ok(false);
+    }
+
+    apply_save_data(
+        path,
+        save_data,
+        commands,
+        editor_state,
+        skill_tree_data,
+        selected_node,
+        selected_connection,
+        multi_selected,
+        node_images,
+        camera_transition,
+    );
+    Ok(true)
+}
+
+/// Loads a backup in place of the open file. The file path stays on the
+/// original, so saving writes the restored tree back over it.
+#[allow(clippy::too_many_arguments)]
+fn restore_backup(
+    backup: &Path,
+    commands: &mut Commands,
+    editor_state: &mut EditorState,
+    skill_tree_data: &mut SkillTreeData,
+    selected_node: &mut SelectedNode,
+    selected_connection: &mut SelectedConnection,
+    multi_selected: &mut MultiSelectedNodes,
+    node_images: &NodeImages,
+    camera_transition: &mut CameraTransition,
+    toast: &mut TimedToast,
+) {
+    let Some(original) = editor_state.current_file_path.clone() else {
+        return;
+    };
+    match load_skill_tree(backup.to_str().unwrap_or_default()) {
+        Ok(save_data) if save_data.version > migration::CURRENT_VERSION => {
+            editor_state.pending_newer_load = Some((original, save_data));
+            editor_state.pending_newer_is_restore = true;
+        }
+        Ok(save_data) => {
+            apply_save_data(
+                original,
+                save_data,
+                commands,
+                editor_state,
+                skill_tree_data,
+                selected_node,
+                selected_connection,
+                multi_selected,
+                node_images,
+                camera_transition,
+            );
+            editor_state.dirty = true;
+            toast.info(format!(
+                "Restored {}",
+                backup.file_name().unwrap_or_default().to_string_lossy()
+            ));
+        }
+        Err(e) => toast.error(format!("Failed to restore {}: {}", backup.display(), e)),
+    }
+}
+
+/// Rough age for menus, like "5 min" or "2 days"
+fn format_age(age: std::time::Duration) -> String {
+    let seconds = age.as_secs();
+    match seconds {
+        0..60 => format!("{} s", seconds),
+        60..3600 => format!("{} min", seconds / 60),
+        3600..86400 => format!("{} h", seconds / 3600),
+        _ => format!("{} days", seconds / 86400),
+    }
+}
+
+/// Saves to `path` as a Bevy scene or as plain save data, going by its
+/// extension
+fn save_tree_to_path(
+    path: &Path,
+    commands: &mut Commands,
+    hot_reload: &mut HotReloadSettings,
+    skill_tree_data: &SkillTreeData,
+    node_query: &Query<&mut SkillNode>,
+    camera_state: Option<(Vec2, f32)>,
+    max_backups: u32,
+) {
+    hot_reload.rebaseline();
+    if is_scene_file(path) {
+        queue_scene_save(commands, path.to_path_buf(), skill_tree_data, max_backups);
+    } else {
+        save_skill_tree(
+            path.to_str().unwrap_or_default(),
+            skill_tree_data,
+            node_query,
+            camera_state,
+            max_backups,
+        );
+    }
+}
+
+/// Color bar explaining the Color by Centrality gradient
+fn show_centrality_legend(ui: &mut egui::Ui) {
+    const STEPS: usize = 32;
+    let (rect, _) =
+        ui.allocate_exact_size(egui::vec2(ui.available_width(), 12.0), egui::Sense::hover());
+    let step_width = rect.width() / STEPS as f32;
+    for step in 0..STEPS {
+        let value = step as f32 / (STEPS - 1) as f32;
+        let [r, g, b, _] = CentralityColoring::color(value).to_srgba().to_u8_array();
+        let min = rect.left_top() + egui::vec2(step as f32 * step_width, 0.0);
+        ui.painter().rect_filled(
+            egui::Rect::from_min_size(min, egui::vec2(step_width + 0.5, rect.height())),
+            0.0,
+            egui::Color32::from_rgb(r, g, b),
+        );
+    }
+    ui.horizontal(|ui| {
+        ui.label("Low");
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            ui.label("High");
+        });
+    });
+}
+
+/// Records the frame time and, when View > Performance Overlay is on, shows
+/// recent frame times as a bar chart beside what the editor drew this frame
+pub fn performance_overlay_ui(
+    mut contexts: EguiContexts,
+    mut editor_state: ResMut<EditorState>,
+    mut history: ResMut<FrameTimeHistory>,
+    time: Res<Time>,
+    diagnostics: Res<DiagnosticsStore>,
+    skill_tree_data: Res<SkillTreeData>,
+    editor_stats: Res<EditorStats>,
+) {
+    history.record(time.delta_secs());
+    if !editor_state.show_performance_overlay {
+        return;
+    }
+
+    let ctx = contexts.ctx_mut();
+    egui::Window::new("Performance")
+        .open(&mut editor_state.show_performance_overlay)
+        .default_pos(egui::pos2(20.0, 60.0))
+        .resizable(false)
+        .show(ctx, |ui| {
+            let current_ms = time.delta_secs() * 1000.0;
+            ui.label(format!("Frame time: {:.2} ms", current_ms));
+            // Smoothed by the diagnostics plugin, so it reads steadier than
+            // the current frame
+            if let Some(fps) = diagnostics
+                .get(&FrameTimeDiagnosticsPlugin::FPS)
+                .and_then(|fps| fps.smoothed())
+            {
+                ui.label(format!("FPS: {:.0}", fps));
+            }
+
+            const CHART_HEIGHT: f32 = 60.0;
+            let (rect, _) = ui.allocate_exact_size(
+                egui::vec2(FrameTimeHistory::LENGTH as f32 * 4.0, CHART_HEIGHT),
+                egui::Sense::hover(),
+            );
+            let painter = ui.painter_at(rect);
+            painter.rect_filled(rect, 0.0, egui::Color32::from_black_alpha(80));
+            // Scaled to the slowest recent frame, but never below 60 FPS so
+            // a smooth run doesn't look spiky
+            let scale = history
+                .frame_times
+                .iter()
+                .copied()
+                .fold(1.0 / 60.0, f32::max);
+            let bar_width = rect.width() / FrameTimeHistory::LENGTH as f32;
+            for (index, frame_time) in history.frame_times.iter().enumerate() {
+                let height = frame_time / scale * CHART_HEIGHT;
+                let left = rect.left() + index as f32 * bar_width;
+                let color = if *frame_time > 1.0 / 30.0 {
+                    egui::Color32::from_rgb(255, 100, 100)
+                } else if *frame_time > 1.0 / 60.0 {
+                    egui::Color32::from_rgb(255, 200, 100)
+                } else {
+                    egui::Color32::from_rgb(100, 220, 100)
+                };
+                painter.rect_filled(
+                    egui::Rect::from_min_max(
+                        egui::pos2(left, rect.bottom() - height),
+                        egui::pos2(left + bar_width - 1.0, rect.bottom()),
+                    ),
+                    0.0,
+                    color,
+                );
+            }
+            ui.label(format!("Chart top: {:.1} ms", scale * 1000.0));
+
+            ui.separator();
+            egui::Grid::new("performance_counts")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Nodes");
+                    ui.label(skill_tree_data.nodes.len().to_string());
+                    ui.end_row();
+                    ui.label("Connections drawn");
+                    ui.label(format!(
+                        "{} of {}",
+                        editor_stats.drawn_connections,
+                        skill_tree_data.connections.len()
+                    ));
+                    ui.end_row();
+                    ui.label("Connections culled");
+                    ui.label(editor_stats.culled_connections.to_string());
+                    ui.end_row();
+                    ui.label("Grid lines");
+                    ui.label(editor_stats.grid_lines.to_string());
+                    ui.end_row();
+                });
+        });
+}
+
+/// File > Print Layout: paper settings for tiling the tree across pages, and
+/// queueing a render of every page, either as separate PNGs or stitched
+/// into one
+pub fn print_layout_ui(
+    mut contexts: EguiContexts,
+    mut editor_state: ResMut<EditorState>,
+    mut print_layout: ResMut<PrintLayout>,
+    mut png_export_state: ResMut<PngExportState>,
+) {
+    if !editor_state.show_print_layout {
+        return;
+    }
+
+    let ctx = contexts.ctx_mut();
+    egui::Window::new("Print Layout")
+        .open(&mut editor_state.show_print_layout)
+        .resizable(false)
+        .show(ctx, |ui| {
+            egui::Grid::new("print_layout_settings")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Paper:");
+                    egui::ComboBox::from_id_salt("paper_size")
+                        .selected_text(print_layout.paper_size.label())
+                        .show_ui(ui, |ui| {
+                            for paper_size in PaperSize::ALL {
+                                ui.selectable_value(
+                                    &mut print_layout.paper_size,
+                                    paper_size,
+                                    paper_size.label(),
+                                );
+                            }
+                        });
+                    ui.end_row();
+                    ui.label("DPI:");
+                    let max_dpi = print_layout.max_dpi();
+                    print_layout.dpi = print_layout.dpi.min(max_dpi);
+                    ui.add(egui::DragValue::new(&mut print_layout.dpi).range(1..=max_dpi));
+                    ui.end_row();
+                    ui.label("Scale:");
+                    ui.add(
+                        egui::DragValue::new(&mut print_layout.scale)
+                            .speed(0.01)
+                            .range(0.05..=20.0)
+                            .suffix("×"),
+                    )
+                    .on_hover_text("Output pixels per canvas unit");
+                    ui.end_row();
+                    ui.label("Margin:");
+                    ui.add(
+                        egui::DragValue::new(&mut print_layout.margin_mm)
+                            .range(0.0..=50.0)
+                            .suffix(" mm"),
+                    )
+                    .on_hover_text("Overlap shown around each page for lining up printouts");
+                    ui.end_row();
+                    ui.label("File name:");
+                    ui.text_edit_singleline(&mut print_layout.file_name_buffer);
+                    ui.end_row();
+                });
+
+            ui.separator();
+            let page_size = print_layout.page_pixels();
+            if print_layout.pages.is_empty() {
+                ui.label("Add nodes to lay out pages");
+            } else {
+                ui.label(format!(
+                    "{} × {} pages, each {}×{} px",
+                    print_layout.columns,
+                    print_layout.rows(),
+                    page_size.x,
+                    page_size.y
+                ));
+            }
+
+            let base_name = print_layout.file_name_buffer.trim().to_string();
+            let busy = png_export_state.pending() > 0;
+            if busy {
+                ui.label(format!("Rendering… {} left", png_export_state.pending()));
+            }
+            let enabled = !busy && !base_name.is_empty() && !print_layout.pages.is_empty();
+            ui.horizontal(|ui| {
+                let settings = PngExportSettings {
+                    width: page_size.x,
+                    height: page_size.y,
+                    dpi: print_layout.dpi,
+                    include_grid: false,
+                    fit_all: false,
+                };
+                let view = |page: &Rect| (page.center(), 1.0 / print_layout.scale);
+
+                if ui
+                    .add_enabled(enabled, egui::Button::new("Export All Pages as PNGs"))
+                    .on_hover_text(format!(
+                        "Writes {base_name}_page_1.png, {base_name}_page_2.png, …"
+                    ))
+                    .clicked()
+                {
+                    for (index, page) in print_layout.pages.iter().enumerate() {
+                        png_export_state.requests.push_back(PngExportRequest {
+                            path: PathBuf::from(format!("{}_page_{}.png", base_name, index + 1)),
+                            settings,
+                            view: Some(view(page)),
+                            stitch_tile: None,
+                        });
+                    }
+                }
+
+                let stitched_size = print_layout.stitched_pixels();
+                let mut stitch_button = ui
+                    .add_enabled(
+                        enabled && stitched_size.is_some(),
+                        egui::Button::new("Stitch to Single PNG"),
+                    )
+                    .on_hover_text(format!(
+                        "Writes {base_name}.png with every page's printable area side by side"
+                    ));
+                if stitched_size.is_none() {
+                    stitch_button = stitch_button.on_disabled_hover_text(format!(
+                        "The stitched image would be over {} px per side or {} megapixels, \
+                         lower the DPI or scale or export separate pages",
+                        PrintLayout::MAX_STITCHED_SIDE,
+                        PrintLayout::MAX_STITCHED_PIXELS >> 20
+                    ));
+                }
+                if let Some(size) = stitched_size.filter(|_| stitch_button.clicked()) {
+                    let path = PathBuf::from(format!("{}.png", base_name));
+                    let (columns, rows) = (print_layout.columns, print_layout.rows());
+                    let tile_size = print_layout.tile_pixels();
+                    let pixel_count = size.x as usize * size.y as usize;
+                    png_export_state.stitch = Some(StitchedPng {
+                        path: path.clone(),
+                        dpi: print_layout.dpi,
+                        columns,
+                        rows,
+                        tile_size,
+                        margin: print_layout.margin_pixels(),
+                        pixels: vec![0; pixel_count * 4],
+                        remaining: print_layout.pages.len() as u32,
+                    });
+                    for (index, page) in print_layout.pages.iter().enumerate() {
+                        let index = index as u32;
+                        png_export_state.requests.push_back(PngExportRequest {
+                            path: path.clone(),
+                            settings,
+                            view: Some(view(page)),
+                            stitch_tile: Some(UVec2::new(index % columns, index / columns)),
+                        });
+                    }
+                }
+            });
+        });
+}
+
+/// Seconds since the Unix epoch as an ISO 8601 UTC date and time, or
+/// "Unknown" for nodes from before timestamps were kept
+fn format_timestamp(timestamp: u64) -> String {
+    if timestamp == 0 {
+        return "Unknown".to_string();
+    }
+    // Civil date from a day count, following Howard Hinnant's
+    // `civil_from_days` with years starting in March
+    let days = (timestamp / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    let seconds = timestamp % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds / 3_600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// Side-by-side table of two nodes' properties for the Compare Nodes
+/// window. Values that differ are yellow, and stats are lined up by name,
+/// leaving a cell empty when only one node has the stat.
+fn show_node_comparison(ui: &mut egui::Ui, first: &SkillNodeData, second: &SkillNodeData) {
+    let stat_values = |node: &SkillNodeData, name: &str| {
+        node.stats
+            .iter()
+            .filter(|stat| stat.stat_name == name)
+            .map(|stat| format!("{} {:?}", stat.value, stat.modifier_type))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let mut stat_names: Vec<&str> = Vec::new();
+    for stat in first.stats.iter().chain(&second.stats) {
+        if !stat_names.contains(&stat.stat_name.as_str()) {
+            stat_names.push(&stat.stat_name);
+        }
+    }
+
+    let properties = [
+        ("Name".to_string(), first.name.clone(), second.name.clone()),
+        (
+            "Description".to_string(),
+            first.description.clone(),
+            second.description.clone(),
+        ),
+        (
+            "Type".to_string(),
+            first.node_type.name().to_string(),
+            second.node_type.name().to_string(),
+        ),
+        (
+            "Image".to_string(),
+            first.image_name.clone(),
+            second.image_name.clone(),
+        ),
+        (
+            "Cost".to_string(),
+            first.cost.to_string(),
+            second.cost.to_string(),
+        ),
+        (
+            "Tier".to_string(),
+            first.tier.to_string(),
+            second.tier.to_string(),
+        ),
+    ];
+    let stats: Vec<_> = stat_names
+        .into_iter()
+        .map(|name| {
+            (
+                name.to_string(),
+                stat_values(first, name),
+                stat_values(second, name),
+            )
+        })
+        .collect();
+    let show_row =
+        |ui: &mut egui::Ui, (field, first_value, second_value): &(String, String, String)| {
+            let color = if first_value == second_value {
+                ui.visuals().text_color()
+            } else {
+                egui::Color32::YELLOW
+            };
+            ui.label(field);
+            ui.colored_label(color, first_value);
+            ui.colored_label(color, second_value);
+            ui.end_row();
+        };
+
+    egui::ScrollArea::vertical()
+        .max_height(400.0)
+        .show(ui, |ui| {
+            egui::Grid::new("node_comparison")
+                .num_columns(3)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong("");
+                    ui.strong(format!("Node {}", first.id));
+                    ui.strong(format!("Node {}", second.id));
+                    ui.end_row();
+                    for row in &properties {
+                        show_row(ui, row);
+                    }
+                    if !stats.is_empty() {
+                        ui.strong("Stats");
+                        ui.end_row();
+                    }
+                    for row in &stats {
+                        show_row(ui, row);
+                    }
+                });
+        });
+}
+
+/// Rows of the side panel's Metrics section, with a warning beside any
+/// metric over its threshold
+fn show_topology_metrics(ui: &mut egui::Ui, metrics: &TopologyMetrics) {
+    let thresholds = &metrics.thresholds;
+    let rows = [
+        ("Nodes", metrics.node_count.to_string(), None),
+        ("Connections", metrics.connection_count.to_string(), None),
+        (
+            "Average degree",
+            format!("{:.2}", metrics.average_degree),
+            None,
+        ),
+        (
+            "Max degree",
+            metrics.max_degree.to_string(),
+            (metrics.max_degree > thresholds.max_degree).then(|| {
+                format!(
+                    "Max degree above {} is unusual, consider splitting the hub",
+                    thresholds.max_degree
+                )
+            }),
+        ),
+        (
+            "Isolated nodes",
+            metrics.isolated_nodes.to_string(),
+            (metrics.isolated_nodes > thresholds.isolated_nodes)
+                .then(|| "Isolated nodes can never be allocated".to_string()),
+        ),
+        (
+            "Longest path",
+            metrics
+                .longest_path
+                .map_or_else(|| "∞".to_string(), |length| length.to_string()),
+            match metrics.longest_path {
+                None => Some("A cycle reachable from Start makes paths endless".to_string()),
+                Some(length) if length > thresholds.longest_path => Some(format!(
+                    "Paths more than {} steps from Start take long to walk",
+                    thresholds.longest_path
+                )),
+                Some(_) => None,
+            },
+        ),
+        (
+            "Cycles",
+            metrics.cycles.to_string(),
+            (metrics.cycles > thresholds.cycles)
+                .then(|| "Cycles make prerequisites ambiguous".to_string()),
+        ),
+        (
+            "Components",
+            metrics.components.to_string(),
+            (metrics.components > thresholds.components)
+                .then(|| "Parts of the tree aren't connected to each other".to_string()),
+        ),
+    ];
+
+    egui::Grid::new("topology_metrics")
+        .num_columns(3)
+        .show(ui, |ui| {
+            for (label, value, warning) in rows {
+                ui.label(label);
+                ui.label(value);
+                match warning {
+                    Some(warning) => {
+                        ui.colored_label(egui::Color32::from_rgb(255, 100, 100), "⚠")
+                            .on_hover_text(warning);
+                    }
+                    None => {
+                        ui.label("");
+                    }
+                }
+                ui.end_row();
+            }
+        });
+}
+
+/// View recorded in saves while Remember camera on save is enabled
+fn saved_camera_state(
+    editor_state: &EditorState,
+    editor_camera: &EditorCamera,
+) -> Option<(Vec2, f32)> {
+    editor_state
+        .remember_camera
+        .then_some((editor_camera.pan_offset, editor_camera.target_zoom))
+}
+
+/// Replaces the open tree with loaded save data
+#[allow(clippy::too_many_arguments)]
+fn apply_save_data(
+    path: PathBuf,
+    save_data: SkillTreeSaveData,
+    commands: &mut Commands,
+    editor_state: &mut EditorState,
+    skill_tree_data: &mut SkillTreeData,
+    selected_node: &mut SelectedNode,
+    selected_connection: &mut SelectedConnection,
+    multi_selected: &mut MultiSelectedNodes,
+    node_images: &NodeImages,
+    camera_transition: &mut CameraTransition,
+) {
+    // Clear existing tree before loading new one
+    perform_new_file_action(
+        commands,
+        editor_state,
+        skill_tree_data,
+        selected_node,
+        selected_connection,
+        multi_selected,
+    );
+
+    let mut max_id = 0;
+    for node_data in save_data.nodes {
+        let entity = spawn_node(commands, &node_data, node_images);
+        skill_tree_data.nodes.insert(node_data.id, entity);
+        if node_data.id >= max_id {
+            max_id = node_data.id + 1;
+        }
+    }
+    editor_state.next_node_id = max_id;
+    skill_tree_data.connections = save_data.connections;
+    skill_tree_data.tree_name = save_data.tree_name;
+    skill_tree_data.tree_description = save_data.tree_description;
+    editor_state.current_file_path = Some(path);
+    editor_state.dirty = false; // Loaded file is not dirty
+    camera_transition.pending = true;
+    camera_transition.saved_view = save_data
+        .saved_camera_state
+        .filter(|_| editor_state.remember_camera);
+}
+
+/// Indices of arcs whose radius is below half the distance between their
+/// nodes, which can't be drawn and show as dashed lines
+fn invalid_arc_indices(
+    connections: &[ConnectionData],
+    positions: &HashMap<u32, Vec2>,
+) -> Vec<usize> {
+    connections
+        .iter()
+        .enumerate()
+        .filter(|(_, connection)| {
+            let CurveType::Arc { radius, .. } = connection.curve_type else {
+                return false;
+            };
+            match (
+                positions.get(&connection.from_id),
+                positions.get(&connection.to_id),
+            ) {
+                (Some(from), Some(to)) => radius < from.distance(*to) / 2.0,
+                _ => false,
+            }
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// Median distance between the endpoints of the connections, skipping any
+/// whose nodes are missing
+fn median_connection_length(
+    connections: &[ConnectionData],
+    positions: &HashMap<u32, Vec2>,
+) -> Option<f32> {
+    let mut lengths: Vec<f32> = connections
+        .iter()
+        .filter_map(|connection| {
+            let from = positions.get(&connection.from_id)?;
+            let to = positions.get(&connection.to_id)?;
+            Some(from.distance(*to))
+        })
+        .collect();
+    if lengths.is_empty() {
+        return None;
+    }
+    lengths.sort_by(f32::total_cmp);
+    let mid = lengths.len() / 2;
+    Some(if lengths.len().is_multiple_of(2) {
+        (lengths[mid - 1] + lengths[mid]) * 0.5
+    } else {
+        lengths[mid]
+    })
+}
+
+fn run_validation(
+    skill_tree_data: &SkillTreeData,
+    node_query: &Query<&mut SkillNode>,
+) -> ValidationReport {
+    let mut nodes: Vec<SkillNodeData> = node_query.iter().map(|node| node.data.clone()).collect();
+    nodes.sort_by_key(|node| node.id);
+    validation::validate_tree(&nodes, &skill_tree_data.connections)
+}
+
+/// `<file>_validation.txt` beside the open file, or in the working directory
+/// for an unsaved tree
+fn validation_report_path(editor_state: &EditorState) -> PathBuf {
+    let stem = editor_state
+        .current_file_path
+        .as_ref()
+        .and_then(|path| path.file_stem())
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("untitled");
+    let file_name = format!("{}_validation.txt", stem);
+    match editor_state
+        .current_file_path
+        .as_ref()
+        .and_then(|path| path.parent())
+    {
+        Some(dir) => dir.join(file_name),
+        None => PathBuf::from(file_name),
+    }
+}
+
+fn write_templates(template_library: &TemplateLibrary) {
+    let path = Path::new(TemplateLibrary::FILE_NAME);
+    if let Err(e) = save_templates(path, &template_library.templates) {
+        error!("Failed to save {}: {}", path.display(), e);
+    }
+}
+
+fn write_editor_config(
+    editor_state: &EditorState,
+    node_type_colors: &NodeTypeColors,
+    node_type_hit_radius: &NodeTypeHitRadius,
+    connection_style_presets: &ConnectionStylePresets,
+    hot_reload: &HotReloadSettings,
+    metric_thresholds: &MetricThresholds,
+    theme_settings: &ThemeSettings,
+) {
+    let config = EditorConfig {
+        side_panel_width: editor_state.side_panel_width,
+        node_type_colors: node_type_colors.colors.clone(),
+        max_backups: editor_state.max_backups,
+        node_hit_radii: node_type_hit_radius.radii.clone(),
+        connection_style_presets: connection_style_presets.presets.clone(),
+        reload_changed_files: hot_reload.enabled,
+        remember_camera: editor_state.remember_camera,
+        metric_thresholds: metric_thresholds.clone(),
+        colorblind_mode: theme_settings.colorblind_mode,
+        high_contrast: theme_settings.high_contrast,
+    };
+    if let Err(e) = save_editor_config(Path::new(EditorConfig::FILE_NAME), &config) {
+        error!("Failed to save {}: {}", EditorConfig::FILE_NAME, e);
+    }
+}
+
+/// Pans and zooms the camera so every position fits in a viewport of the
+/// given size
+fn frame_positions(editor_camera: &mut EditorCamera, positions: &[Vec2], viewport: egui::Vec2) {
+    if let Some((center, zoom)) = framing(positions, Vec2::new(viewport.x, viewport.y)) {
+        editor_camera.pan_offset = center;
+        editor_camera.target_zoom = zoom;
+    }
+}
+
+/// Appends another file's tree to the current one. Node ids are remapped past
+/// `next_node_id` so they can't collide, and the merged nodes become the
+/// multi-selection so they can be moved as a group.
+fn merge_file_into_editor(
+    path: &Path,
+    commands: &mut Commands,
+    editor_state: &mut EditorState,
+    skill_tree_data: &mut SkillTreeData,
+    multi_selected: &mut MultiSelectedNodes,
+    node_images: &NodeImages,
+) -> Result<(usize, usize), Box<dyn std::error::Error>> {
+    let save_data = load_skill_tree(path.to_str().unwrap_or_default())?;
+    Ok(append_to_editor(
+        save_data.nodes,
+        save_data.connections,
+        commands,
+        editor_state,
+        skill_tree_data,
+        multi_selected,
+        node_images,
+    ))
+}
+
+/// File > Import > DOT (Graphviz). The graph is appended like Merge From.
+fn import_dot_into_editor(
+    path: &Path,
+    commands: &mut Commands,
+    editor_state: &mut EditorState,
+    skill_tree_data: &mut SkillTreeData,
+    multi_selected: &mut MultiSelectedNodes,
+    node_images: &NodeImages,
+) -> Result<(usize, usize), Box<dyn std::error::Error>> {
+    let graph = read_dot(path.to_str().ok_or("Import path is not valid UTF-8")?)?;
+    Ok(append_to_editor(
+        graph.nodes,
+        graph.connections,
+        commands,
+        editor_state,
+        skill_tree_data,
+        multi_selected,
+        node_images,
+    ))
+}
+
+/// Spawns `nodes` under fresh ids and adds the connections between them.
+/// Returns the number of added nodes and connections.
+fn append_to_editor(
+    nodes: Vec<SkillNodeData>,
+    connections: Vec<ConnectionData>,
+    commands: &mut Commands,
+    editor_state: &mut EditorState,
+    skill_tree_data: &mut SkillTreeData,
+    multi_selected: &mut MultiSelectedNodes,
+    node_images: &NodeImages,
+) -> (usize, usize) {
+    multi_selected.nodes.clear();
+    let mut id_map = HashMap::new();
+    for mut node_data in nodes {
+        let new_id = editor_state.next_node_id;
+        editor_state.next_node_id += 1;
+        id_map.insert(node_data.id, new_id);
+        node_data.id = new_id;
+
+        let entity = spawn_node(commands, &node_data, node_images);
+        skill_tree_data.nodes.insert(new_id, entity);
+        multi_selected.nodes.insert(new_id);
+    }
+
+    let mut connection_count = 0;
+    for mut connection in connections {
+        let (Some(&from_id), Some(&to_id)) = (
+            id_map.get(&connection.from_id),
+            id_map.get(&connection.to_id),
+        ) else {
+            warn!(
+                "Skipped merged connection {} -> {} with a missing node",
+                connection.from_id, connection.to_id
+            );
+            continue;
+        };
+        connection.from_id = from_id;
+        connection.to_id = to_id;
+        skill_tree_data.connections.push(connection);
+        connection_count += 1;
+    }
+
+    editor_state.dirty = true;
+    (id_map.len(), connection_count)
+}
+
+/// Merges a file written by Export Subtree without remapping ids, so an
+/// edited subtree goes back where it came from. Nodes whose id already exists
+/// are overwritten and the rest are spawned; connections the tree already has
+/// are skipped. The merged nodes become the multi-selection. Returns the
+/// number of updated nodes, added nodes and added connections.
+fn merge_subtree_into_editor(
+    path: &Path,
+    commands: &mut Commands,
+    editor_state: &mut EditorState,
+    skill_tree_data: &mut SkillTreeData,
+    node_query: &mut Query<&mut SkillNode>,
+    multi_selected: &mut MultiSelectedNodes,
+    node_images: &NodeImages,
+) -> Result<(usize, usize, usize), Box<dyn std::error::Error>> {
+    let save_data = load_skill_tree(path.to_str().unwrap_or_default())?;
+
+    multi_selected.nodes.clear();
+    let (mut updated, mut added) = (0, 0);
+    for node_data in save_data.nodes {
+        multi_selected.nodes.insert(node_data.id);
+        editor_state.next_node_id = editor_state.next_node_id.max(node_data.id + 1);
+        let existing = skill_tree_data
+            .nodes
+            .get(&node_data.id)
+            .and_then(|&entity| node_query.get_mut(entity).ok());
+        if let Some(mut node) = existing {
+            node.data = node_data;
+            updated += 1;
+        } else {
+            let entity = spawn_node(commands, &node_data, node_images);
+            skill_tree_data.nodes.insert(node_data.id, entity);
+            added += 1;
+        }
+    }
+
+    // Matched in either direction, like `graph::duplicate_connection_indices`
+    let mut existing: HashSet<(u32, u32)> = skill_tree_data
+        .connections
+        .iter()
+        .map(graph::node_pair)
+        .collect();
+    let mut connection_count = 0;
+    for connection in save_data.connections {
+        if existing.insert(graph::node_pair(&connection)) {
+            skill_tree_data.connections.push(connection);
+            connection_count += 1;
+        }
+    }
+
+    editor_state.dirty = true;
+    Ok((updated, added, connection_count))
+}
+
+/// Applies a CSV from File > Import > CSV (Update Nodes) to the nodes with
+/// matching ids. Returns the number of updated rows and of skipped unknown ids.
+fn update_nodes_from_csv(
+    path: &Path,
+    skill_tree_data: &SkillTreeData,
+    node_query: &mut Query<&mut SkillNode>,
+) -> Result<(usize, usize), Box<dyn std::error::Error>> {
+    let rows = read_csv_nodes(path.to_str().ok_or("Import path is not valid UTF-8")?)?;
+    let (mut updated, mut skipped) = (0, 0);
+    for row in rows {
+        let node = skill_tree_data
+            .nodes
+            .get(&row.id)
+            .and_then(|&entity| node_query.get_mut(entity).ok());
+        match node {
+            Some(mut node) => {
+                row.apply(&mut node.data);
+                updated += 1;
+            }
+            None => {
+                warn!("CSV import skipped unknown node id {}", row.id);
+                skipped += 1;
+            }
+        }
+    }
+    Ok((updated, skipped))
+}
+
+/// Loads a second tree for File > Compare With..., leaving the open tree as is.
+/// The diff itself is computed by `update_tree_diff`.
+fn load_compare_tree(
+    path: &Path,
+    compare_tree: &mut CompareTree,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let save_data = load_skill_tree(path.to_str().unwrap_or_default())?;
+    compare_tree.close();
+    compare_tree.path = Some(path.to_path_buf());
+    compare_tree.nodes = save_data
+        .nodes
+        .into_iter()
+        .map(|node| (node.id, node))
+        .collect();
+    Ok(())
 }
 
 fn perform_new_file_action(
@@ -719,15 +4891,19 @@ fn perform_new_file_action(
     skill_tree_data: &mut SkillTreeData,
     selected_node: &mut SelectedNode,
     selected_connection: &mut SelectedConnection,
+    multi_selected: &mut MultiSelectedNodes,
 ) {
     for entity in skill_tree_data.nodes.values() {
         commands.entity(*entity).despawn();
     }
     skill_tree_data.nodes.clear();
     skill_tree_data.connections.clear();
+    skill_tree_data.tree_name.clear();
+    skill_tree_data.tree_description.clear();
     selected_node.entity = None;
     selected_node.id = None;
     selected_connection.index = None;
+    multi_selected.nodes.clear();
     editor_state.current_file_path = None;
     editor_state.next_node_id = 0;
     editor_state.dirty = false;
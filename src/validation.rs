@@ -0,0 +1,248 @@
+use crate::components::*;
+use crate::graph;
+use bevy::prelude::Vec2;
+use std::collections::HashMap;
+
+/// What a report entry points at, so the report can select and frame it
+#[derive(Clone, Copy)]
+pub enum ReportTarget {
+    Node(u32),
+    Connection(usize),
+}
+
+pub struct ReportItem {
+    pub message: String,
+    pub target: Option<ReportTarget>,
+}
+
+impl ReportItem {
+    fn node(id: u32, message: String) -> Self {
+        Self {
+            message,
+            target: Some(ReportTarget::Node(id)),
+        }
+    }
+
+    fn connection(index: usize, message: String) -> Self {
+        Self {
+            message,
+            target: Some(ReportTarget::Connection(index)),
+        }
+    }
+}
+
+/// One check of Edit > Validate Tree, passed when it found nothing
+pub struct ValidationCheck {
+    pub name: &'static str,
+    pub items: Vec<ReportItem>,
+}
+
+impl ValidationCheck {
+    pub fn passed(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+pub struct ValidationReport {
+    pub checks: Vec<ValidationCheck>,
+}
+
+impl ValidationReport {
+    pub fn issue_count(&self) -> usize {
+        self.checks.iter().map(|check| check.items.len()).sum()
+    }
+
+    /// Plain text version for the .txt export
+    pub fn to_text(&self) -> String {
+        let mut text = format!("Skill tree validation: {} issue(s)\n", self.issue_count());
+        for check in &self.checks {
+            let status = if check.passed() { "PASS" } else { "FAIL" };
+            text.push_str(&format!("\n[{}] {}\n", status, check.name));
+            for item in &check.items {
+                text.push_str(&format!("  - {}\n", item.message));
+            }
+        }
+        text
+    }
+}
+
+/// Runs every check over a snapshot of the tree. `nodes` should be sorted by
+/// id so the report reads in a stable order.
+pub fn validate_tree(nodes: &[SkillNodeData], connections: &[ConnectionData]) -> ValidationReport {
+    let node_ids: Vec<u32> = nodes.iter().map(|node| node.id).collect();
+    let positions: HashMap<u32, Vec2> = nodes.iter().map(|node| (node.id, node.position)).collect();
+    let start_ids: Vec<u32> = nodes
+        .iter()
+        .filter(|node| node.node_type.is_builtin(BuiltinNodeType::Start))
+        .map(|node| node.id)
+        .collect();
+
+    let cycles = graph::find_cycles(&node_ids, &graph::directed_adjacency(connections))
+        .into_iter()
+        .map(|cycle| {
+            let mut ids: Vec<String> = cycle.iter().map(u32::to_string).collect();
+            ids.push(cycle[0].to_string());
+            ReportItem::node(cycle[0], format!("Cycle: {}", ids.join(" → ")))
+        })
+        .collect();
+
+    let reachability = if start_ids.is_empty() {
+        vec![ReportItem {
+            message: "No Start node to reach nodes from".to_string(),
+            target: None,
+        }]
+    } else {
+        let reachable = graph::reachable_from(
+            start_ids.iter().copied(),
+            &graph::undirected_adjacency(connections),
+        );
+        nodes
+            .iter()
+            .filter(|node| !reachable.contains(&node.id))
+            .map(|node| {
+                ReportItem::node(
+                    node.id,
+                    format!(
+                        "Node {} ({}) can't be reached from a Start node",
+                        node.id, node.name
+                    ),
+                )
+            })
+            .collect()
+    };
+
+    let duplicates = graph::duplicate_connection_indices(connections)
+        .into_iter()
+        .map(|index| {
+            let conn = &connections[index];
+            ReportItem::connection(
+                index,
+                format!(
+                    "Connection {} -> {} is a duplicate",
+                    conn.from_id, conn.to_id
+                ),
+            )
+        })
+        .collect();
+
+    let mut self_loops = Vec::new();
+    let mut invalid_arcs = Vec::new();
+    for (index, conn) in connections.iter().enumerate() {
+        if conn.from_id == conn.to_id {
+            self_loops.push(ReportItem::connection(
+                index,
+                format!(
+                    "Connection {} -> {} connects a node to itself",
+                    conn.from_id, conn.to_id
+                ),
+            ));
+        }
+        if let (CurveType::Arc { radius, .. }, Some(from), Some(to)) = (
+            &conn.curve_type,
+            positions.get(&conn.from_id),
+            positions.get(&conn.to_id),
+        ) {
+            let distance = from.distance(*to);
+            if *radius < distance / 2.0 {
+                invalid_arcs.push(ReportItem::connection(
+                    index,
+                    format!(
+                        "Connection {} -> {}: arc radius {:.1} is below half the distance ({:.1})",
+                        conn.from_id,
+                        conn.to_id,
+                        radius,
+                        distance / 2.0
+                    ),
+                ));
+            }
+        }
+    }
+
+    let orphans = nodes
+        .iter()
+        .filter(|node| {
+            !connections
+                .iter()
+                .any(|conn| conn.from_id == node.id || conn.to_id == node.id)
+        })
+        .map(|node| {
+            ReportItem::node(
+                node.id,
+                format!("Node {} ({}) has no connections", node.id, node.name),
+            )
+        })
+        .collect();
+
+    let mut start_node = Vec::new();
+    if start_ids.is_empty() {
+        start_node.push(ReportItem {
+            message: "The tree has no Start node".to_string(),
+            target: None,
+        });
+    }
+
+    let missing_names = nodes
+        .iter()
+        .filter(|node| node.name.trim().is_empty())
+        .map(|node| ReportItem::node(node.id, format!("Node {} has no name", node.id)))
+        .collect();
+
+    let stat_bounds = nodes
+        .iter()
+        .flat_map(|node| {
+            node.stats
+                .iter()
+                .filter(|stat| stat.violates_bounds())
+                .map(|stat| {
+                    ReportItem::node(
+                        node.id,
+                        format!(
+                            "Node {} ({}): {} value {} is outside its min/max",
+                            node.id, node.name, stat.stat_name, stat.value
+                        ),
+                    )
+                })
+        })
+        .collect();
+
+    ValidationReport {
+        checks: vec![
+            ValidationCheck {
+                name: "No cycles",
+                items: cycles,
+            },
+            ValidationCheck {
+                name: "Every node reachable from Start",
+                items: reachability,
+            },
+            ValidationCheck {
+                name: "No duplicate connections",
+                items: duplicates,
+            },
+            ValidationCheck {
+                name: "No self-loops",
+                items: self_loops,
+            },
+            ValidationCheck {
+                name: "Arc radii large enough",
+                items: invalid_arcs,
+            },
+            ValidationCheck {
+                name: "No orphaned nodes",
+                items: orphans,
+            },
+            ValidationCheck {
+                name: "Has a Start node",
+                items: start_node,
+            },
+            ValidationCheck {
+                name: "Every node named",
+                items: missing_names,
+            },
+            ValidationCheck {
+                name: "Stat values within bounds",
+                items: stat_bounds,
+            },
+        ],
+    }
+}